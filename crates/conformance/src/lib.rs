@@ -10,7 +10,7 @@
 // This crate is non-normative with respect to language semantics.
 // It is normative for conformance enforcement within the dustrun repository.
 
-use dust_dvm::{Dvm, DvmConfig, DvmError, DvmTrace, EffectMode};
+use dust_dvm::{DirProgram, Dvm, DvmConfig, DvmError, DvmTrace, EffectMode};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -36,6 +36,10 @@ pub struct Fixture {
     pub name: String,
     pub dir: String,
 
+    /// Further DIR artifacts linked after `dir`, in order (`dir::link`).
+    #[serde(default)]
+    pub link: Vec<String>,
+
     #[serde(default = "default_entry")]
     pub entry: String,
 
@@ -45,6 +49,15 @@ pub struct Fixture {
     #[serde(default)]
     pub trace: bool,
 
+    /// Record per-statement `steps` in the trace.
+    #[serde(default)]
+    pub steps: bool,
+
+    /// Compare source spans (`error.span`); otherwise they are dropped from both
+    /// the produced and the golden trace.
+    #[serde(default)]
+    pub spans: bool,
+
     #[serde(default)]
     pub expect_trace: Option<String>,
 
@@ -136,6 +149,29 @@ impl Runner {
         Ok(serde_json::from_slice(&bytes)?)
     }
 
+    /// Load the fixture's DIR artifacts, linked in order. Artifacts are named
+    /// as the fixture names them.
+    pub fn load_program(
+        dvm: &Dvm,
+        fixture_file: impl AsRef<Path>,
+        fixture: &Fixture,
+    ) -> Result<Result<DirProgram, DvmError>, ConformanceError> {
+        let base = fixture.base_dir(fixture_file.as_ref());
+        let mut artifacts = Vec::new();
+        for rel in std::iter::once(&fixture.dir).chain(&fixture.link) {
+            let path = base.join(rel);
+            let bytes = fs::read(&path)?;
+            match dvm.load_dir(&path, &bytes) {
+                Ok(p) => artifacts.push((rel.clone(), p)),
+                Err(e) => return Ok(Err(e)),
+            }
+        }
+        if fixture.link.is_empty() {
+            return Ok(Ok(artifacts.remove(0).1));
+        }
+        Ok(dust_dvm::dir::link::link(artifacts))
+    }
+
     pub fn run_fixture(
         &self,
         fixture_file: impl AsRef<Path>,
@@ -143,22 +179,24 @@ impl Runner {
     ) -> Result<DvmTrace, ConformanceError> {
         let fixture_file = fixture_file.as_ref();
 
-        let dir_path = fixture.dir_path(fixture_file);
-        let dir_bytes = fs::read(&dir_path)?;
-
         let dvm = Dvm::new(DvmConfig {
             effect_mode: fixture.effect_mode()?,
             trace: fixture.trace,
+            steps: fixture.steps,
         });
 
-        // If DIR fails to load, represent it as a failure trace (stable).
-        let program = match dvm.load_dir_json(&dir_bytes) {
+        // If DIR fails to load or link, represent it as a failure trace (stable).
+        let program = match Self::load_program(&dvm, fixture_file, fixture)? {
             Ok(p) => p,
             Err(e) => return Ok(trace_from_error(e)),
         };
 
         // Use the trace API so failures can carry partial effects/time.
-        Ok(dvm.run_entrypoint_trace(&program, &fixture.entry))
+        let mut trace = dvm.run_entrypoint_trace(&program, &fixture.entry);
+        if !fixture.spans {
+            trace.strip_spans();
+        }
+        Ok(trace)
     }
 
     pub fn assert_matches(
@@ -189,19 +227,21 @@ impl Runner {
         };
 
         let golden_bytes = fs::read(&golden_path)?;
-        let golden: DvmTrace = serde_json::from_slice(&golden_bytes)?;
+        let mut golden: DvmTrace = serde_json::from_slice(&golden_bytes)?;
+        if !fixture.spans {
+            golden.strip_spans();
+        }
 
         if &golden != produced {
-            let golden_s = serde_json::to_string_pretty(&golden)?;
-            let produced_s = serde_json::to_string_pretty(produced)?;
+            // Report semantic differences (golden vs produced) rather than both traces in full.
+            let differences = dust_dvm::trace::diff(&golden, produced);
             return Err(ConformanceError::GoldenMismatch(format!(
-                "fixture '{}' produced trace does not match golden.\nfixture_file: {}\ndir: {}\nexpected: {}\n\n--- golden ---\n{}\n\n--- produced ---\n{}\n",
+                "fixture '{}' produced trace does not match golden.\nfixture_file: {}\ndir: {}\nexpected: {}\n\n--- golden vs produced ---\n{}",
                 fixture.name,
                 fixture_file.display(),
                 fixture.dir_path(fixture_file).display(),
                 golden_path.display(),
-                golden_s,
-                produced_s,
+                differences,
             )));
         }
 
@@ -218,10 +258,5 @@ impl Runner {
 }
 
 fn trace_from_error(e: DvmError) -> DvmTrace {
-    use dust_dvm::{DvmFailureTrace, TraceError};
-    DvmTrace::Failure(DvmFailureTrace {
-        error: TraceError::from(&e),
-        effects: None,
-        time: None,
-    })
+    DvmTrace::Failure(dust_dvm::DvmFailureTrace::from_error(&e))
 }
@@ -15,6 +15,8 @@
 //   - `expect_trace` (success trace), or
 //   - `expect_error` (failure trace)
 
+use dust_dvm::dir::validate::Severity;
+use dust_dvm::{Dvm, DvmConfig};
 use dustrun_conformance::{Runner, RunnerConfig};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -64,3 +66,26 @@ fn conformance_fixtures_match_golden() {
         });
     }
 }
+
+#[test]
+fn passing_fixtures_check_without_errors() {
+    // `dustrun check` must not reject a program the interpreter runs.
+    let dvm = Dvm::new(DvmConfig::default());
+    let mut checked = 0;
+    for f in list_fixture_files(&fixture_root()) {
+        let fixture = Runner::load_fixture(&f).unwrap();
+        if fixture.expect_trace.is_none() {
+            continue;
+        }
+        let program = Runner::load_program(&dvm, &f, &fixture).unwrap().unwrap();
+        let errors: Vec<String> = dvm
+            .check_dir(&program)
+            .iter()
+            .filter(|i| i.severity == Severity::Error)
+            .map(ToString::to_string)
+            .collect();
+        assert!(errors.is_empty(), "{}: {errors:?}", f.display());
+        checked += 1;
+    }
+    assert!(checked > 0, "no passing fixtures found");
+}
@@ -5,7 +5,7 @@
 // This file defines the public CLI contract only.
 // It does NOT contain execution logic or semantics.
 
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum};
 
 /// Dust Virtual Machine (DVM) reference executor.
 ///
@@ -16,12 +16,75 @@ use clap::{Parser, ValueEnum};
 #[command(author = "Dust Research Division")]
 #[command(version = "0.1.0")]
 #[command(about = "Dust Virtual Machine (DVM) reference executor", long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct Args {
-    /// Path to the DIR artifact (JSON)
-    #[arg(value_name = "DIR_FILE")]
-    pub dir_path: String,
+    /// Auxiliary command (omit to execute a DIR artifact)
+    #[command(subcommand)]
+    pub command: Option<Command>,
 
-    /// Entrypoint procedure name
+    #[command(flatten)]
+    pub run: RunArgs,
+}
+
+/// Auxiliary commands. Plain `dustrun <DIR_FILE>` executes the artifact.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Verify a Φ witness (JSON) against the proc it describes
+    VerifyWitness(VerifyWitnessArgs),
+
+    /// Resume a run paused with --pause-after
+    Resume(ResumeArgs),
+
+    /// Debug a DIR artifact interactively (commands are read from stdin)
+    Debug(DebugArgs),
+
+    /// Run a Debug Adapter Protocol server on stdin/stdout
+    Dap,
+
+    /// Evaluate DIR statements and expressions interactively
+    Repl(ReplArgs),
+
+    /// Assemble DIR assembly (*.dasm) into a DIR artifact (JSON)
+    Asm(AsmArgs),
+
+    /// Disassemble a DIR artifact into DIR assembly
+    Disasm(AsmArgs),
+
+    /// Convert a DIR artifact between JSON, binary and assembly
+    Convert(ConvertArgs),
+
+    /// Link several DIR artifacts into one program
+    Link(LinkArgs),
+
+    /// Validate DIR and check Q linearity without executing
+    Check(CheckArgs),
+
+    /// Report semantic differences between two trace files
+    Diff(DiffArgs),
+
+    /// Work with emitted trace files
+    #[command(subcommand)]
+    Trace(TraceCommand),
+}
+
+/// `dustrun trace` subcommands.
+#[derive(Debug, Subcommand)]
+pub enum TraceCommand {
+    /// Rewrite trace files (e.g. golden traces) in the current schema version
+    Upgrade(TraceUpgradeArgs),
+}
+
+/// Arguments for executing a DIR artifact.
+#[derive(Debug, clap::Args)]
+#[command(group = clap::ArgGroup::new("trace_output").multiple(true).args(["emit_trace", "trace_jsonl"]))]
+pub struct RunArgs {
+    /// Path to the DIR artifact (JSON, binary, or DIR assembly for *.dasm)
+    ///
+    /// Several artifacts are linked into one program before running.
+    #[arg(value_name = "DIR_FILE", required = true)]
+    pub dir_paths: Vec<String>,
+
+    /// Entrypoint procedure name (`proc` or `forge::proc`)
     ///
     /// If not specified, defaults to `main`.
     #[arg(short, long, default_value = "main")]
@@ -47,6 +110,22 @@ pub struct Args {
     #[arg(long)]
     pub emit_trace: bool,
 
+    /// Stream the trace as JSON Lines to FILE while executing
+    ///
+    /// Effects (and step records with --steps) are written as they happen,
+    /// followed by a terminal outcome record.
+    #[arg(long, value_name = "FILE", conflicts_with = "pause_after")]
+    pub trace_jsonl: Option<String>,
+
+    /// Include per-statement `steps` in the emitted or streamed trace
+    ///
+    /// Each step records forge/proc/index, tick, bindings written and effects emitted.
+    #[arg(long, requires = "trace_output")]
+    pub steps: bool,
+
+    #[command(flatten)]
+    pub checkpoint: CheckpointArgs,
+
     /// Suppress non-essential output
     ///
     /// When set, only structured outputs (JSON) are printed.
@@ -54,6 +133,171 @@ pub struct Args {
     pub quiet: bool,
 }
 
+/// Pausing a run into a snapshot file.
+#[derive(Debug, clap::Args)]
+pub struct CheckpointArgs {
+    /// Pause after N statements and write a snapshot (see --snapshot)
+    ///
+    /// Resume with `dustrun resume <SNAPSHOT_FILE>`; the final trace is identical
+    /// to an uninterrupted run.
+    #[arg(long, value_name = "N", requires = "snapshot")]
+    pub pause_after: Option<usize>,
+
+    /// Snapshot file written when the run pauses
+    #[arg(long, value_name = "FILE", requires = "pause_after")]
+    pub snapshot: Option<String>,
+}
+
+/// Arguments for `dustrun resume`.
+#[derive(Debug, clap::Args)]
+pub struct ResumeArgs {
+    /// Snapshot written by --pause-after
+    #[arg(value_name = "SNAPSHOT_FILE")]
+    pub snapshot_path: String,
+
+    /// Enable execution tracing
+    #[arg(long)]
+    pub trace: bool,
+
+    /// Emit execution trace as JSON to stdout
+    #[arg(long)]
+    pub emit_trace: bool,
+
+    #[command(flatten)]
+    pub checkpoint: CheckpointArgs,
+
+    /// Suppress non-essential output
+    #[arg(long)]
+    pub quiet: bool,
+}
+
+/// Arguments for `dustrun debug`.
+#[derive(Debug, clap::Args)]
+pub struct DebugArgs {
+    /// Path to the DIR artifact (JSON, binary, or DIR assembly for *.dasm)
+    #[arg(value_name = "DIR_FILE")]
+    pub dir_path: String,
+
+    /// Entrypoint procedure name
+    #[arg(short, long, default_value = "main")]
+    pub entry: String,
+
+    /// Effect handling mode
+    #[arg(long, value_enum, default_value = "simulate")]
+    pub effects: EffectModeArg,
+}
+
+/// Arguments for `dustrun repl`.
+#[derive(Debug, clap::Args)]
+pub struct ReplArgs {
+    /// Regime of the session proc
+    #[arg(long, value_enum, ignore_case = true, default_value = "k")]
+    pub regime: RegimeArg,
+}
+
+/// CLI-visible REPL regime selector.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum RegimeArg {
+    K,
+    Q,
+}
+
+/// Arguments for `dustrun verify-witness`.
+#[derive(Debug, clap::Args)]
+pub struct VerifyWitnessArgs {
+    /// Path to the witness (JSON: the native encoding or a `PhiWitness` value)
+    #[arg(value_name = "WITNESS_FILE")]
+    pub witness_path: String,
+
+    /// Path to the DIR artifact (JSON, binary or *.dasm) containing the Φ proc
+    #[arg(value_name = "DIR_FILE")]
+    pub dir_path: String,
+
+    /// Name of the Φ proc the witness describes
+    #[arg(short, long = "proc", default_value = "main")]
+    pub proc_name: String,
+}
+
+/// Arguments for `dustrun asm` and `dustrun disasm`.
+#[derive(Debug, clap::Args)]
+pub struct AsmArgs {
+    /// Input file (`disasm` also reads DIR assembly, to normalize it)
+    #[arg(value_name = "INPUT")]
+    pub input: String,
+
+    /// Write the result to FILE instead of stdout
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<String>,
+}
+
+/// Arguments for `dustrun convert`.
+#[derive(Debug, clap::Args)]
+pub struct ConvertArgs {
+    /// DIR artifact to convert (JSON, binary or *.dasm)
+    #[arg(value_name = "INPUT")]
+    pub input: String,
+
+    /// File to write
+    #[arg(value_name = "OUTPUT")]
+    pub output: String,
+
+    /// Output format (default: by OUTPUT extension; *.dirb binary, *.dasm assembly, else JSON)
+    #[arg(long, value_enum)]
+    pub to: Option<DirFormatArg>,
+}
+
+/// Arguments for `dustrun link`.
+#[derive(Debug, clap::Args)]
+pub struct LinkArgs {
+    /// DIR artifacts to link, in order (JSON, binary or *.dasm)
+    #[arg(value_name = "INPUT", required = true)]
+    pub inputs: Vec<String>,
+
+    /// Write the linked program to FILE (format by extension) instead of JSON on stdout
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<String>,
+}
+
+/// Arguments for `dustrun check`.
+#[derive(Debug, clap::Args)]
+pub struct CheckArgs {
+    /// DIR artifacts to check (JSON, binary or *.dasm); several are linked first
+    #[arg(value_name = "DIR_FILE", required = true)]
+    pub dir_paths: Vec<String>,
+}
+
+/// CLI-visible DIR encoding selector.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum DirFormatArg {
+    Json,
+    Binary,
+    Asm,
+}
+
+/// Arguments for `dustrun diff`.
+#[derive(Debug, clap::Args)]
+pub struct DiffArgs {
+    /// Left trace (JSON)
+    #[arg(value_name = "LEFT")]
+    pub left: String,
+
+    /// Right trace (JSON)
+    #[arg(value_name = "RIGHT")]
+    pub right: String,
+}
+
+/// Arguments for `dustrun trace upgrade`.
+#[derive(Debug, clap::Args)]
+pub struct TraceUpgradeArgs {
+    /// Trace files (JSON) to upgrade in place
+    #[arg(value_name = "TRACE_FILE", required = true)]
+    pub paths: Vec<String>,
+
+    /// Report outdated files without rewriting them (exit 1 if any)
+    #[arg(long)]
+    pub check: bool,
+}
+
 /// CLI-visible effect mode selector.
 ///
 /// This is intentionally a thin wrapper over the DVM's internal EffectMode.
@@ -0,0 +1,969 @@
+// File: crates/dustrun/src/dap.rs
+//
+// `dustrun dap`: a Debug Adapter Protocol server over stdio.
+//
+// The adapter drives the same `Debugger` as `dustrun debug`. Each proc is a
+// source (`forge::proc`, served through `sourceReference`) whose lines are its
+// statements, so stack frames, line breakpoints and stepping map directly onto
+// DIR statement indices. Function breakpoints accept the `dustrun debug`
+// syntax (`[forge::]proc[#index]`, `effect <kind>`). Effects are reported as
+// output events and faults stop the run as exceptions.
+
+use crate::debug::{location, resource_state, statement, Breakpoint, Debugger, Resume, Stop};
+use dust_dvm::{
+    DirProgram, DirStmt, Dvm, DvmConfig, DvmFault, EffectMode, QSnapshot, TraceError, Value,
+};
+use serde_json::{json, Value as Json};
+use std::fs;
+use std::io::{self, BufRead, Write};
+
+/// Serve one debug session on `input` / `output`, returning when the client
+/// disconnects or the input ends.
+pub fn serve(input: impl BufRead, output: impl Write) -> io::Result<()> {
+    let mut conn = Connection {
+        input,
+        output,
+        seq: 1,
+        line_base: 1,
+    };
+
+    let Some((request, launch)) = conn.until_launch()? else {
+        return Ok(());
+    };
+
+    let dvm = Dvm::new(DvmConfig {
+        effect_mode: launch.effect_mode,
+        ..DvmConfig::default()
+    });
+    let program = match fs::read(&launch.program)
+        .map_err(|e| format!("failed to read DIR file '{}': {e}", launch.program))
+        .and_then(|bytes| {
+            dvm.load_dir(&launch.program, &bytes)
+                .map_err(|e| e.to_string())
+        }) {
+        Ok(p) => p,
+        Err(e) => return conn.launch_failed(&request, &e),
+    };
+    let stepper = match dvm.stepper(&program, &launch.entry) {
+        Ok(s) => s,
+        Err(fault) => return conn.launch_failed(&request, &fault.error.to_string()),
+    };
+
+    conn.respond(&request, Json::Null)?;
+    conn.event("initialized", Json::Null)?;
+
+    let mut session = Session {
+        conn,
+        program: &program,
+        debugger: Debugger::new(stepper),
+        stop_on_entry: launch.stop_on_entry,
+        source_breakpoints: Vec::new(),
+        function_breakpoints: Vec::new(),
+        containers: Vec::new(),
+        reported_effects: 0,
+        ended: false,
+    };
+    session.run()
+}
+
+/// `launch` arguments.
+struct Launch {
+    program: String,
+    entry: String,
+    stop_on_entry: bool,
+    effect_mode: EffectMode,
+}
+
+/// DAP framing and sequence numbers.
+struct Connection<R, W> {
+    input: R,
+    output: W,
+    seq: u64,
+
+    /// Line number of statement 0 (`linesStartAt1`).
+    line_base: usize,
+}
+
+impl<R: BufRead, W: Write> Connection<R, W> {
+    /// Read the next request, answering malformed ones with an error response.
+    /// Only broken framing ends the session.
+    fn request(&mut self) -> io::Result<Option<Json>> {
+        while let Some(body) = self.read()? {
+            match serde_json::from_slice::<Json>(&body) {
+                Ok(request) if request["command"].is_string() => return Ok(Some(request)),
+                Ok(request) => {
+                    let unknown = json!({ "seq": request["seq"], "command": "" });
+                    self.respond_error(&unknown, "malformed request: missing 'command'")?;
+                }
+                Err(e) => {
+                    let unknown = json!({ "seq": 0, "command": "" });
+                    self.respond_error(&unknown, &format!("malformed request: {e}"))?;
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Read one message body; `None` at the end of the input.
+    fn read(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut length = None;
+        let mut headers = false;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.input.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            let header = line.trim_end();
+            if header.is_empty() {
+                if headers {
+                    break;
+                }
+                continue;
+            }
+            headers = true;
+            if let Some(n) = header.strip_prefix("Content-Length:") {
+                length = Some(n.trim().parse::<usize>().map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidData, "invalid Content-Length")
+                })?);
+            }
+        }
+        let Some(length) = length else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "missing Content-Length header",
+            ));
+        };
+        let mut body = vec![0; length];
+        self.input.read_exact(&mut body)?;
+        Ok(Some(body))
+    }
+
+    fn send(&mut self, mut message: Json) -> io::Result<()> {
+        message["seq"] = self.seq.into();
+        self.seq += 1;
+        let body = serde_json::to_vec(&message)?;
+        write!(self.output, "Content-Length: {}\r\n\r\n", body.len())?;
+        self.output.write_all(&body)?;
+        self.output.flush()
+    }
+
+    fn respond(&mut self, request: &Json, body: Json) -> io::Result<()> {
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "success": true,
+            "command": request["command"],
+        });
+        if !body.is_null() {
+            response["body"] = body;
+        }
+        self.send(response)
+    }
+
+    fn respond_error(&mut self, request: &Json, message: &str) -> io::Result<()> {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "success": false,
+            "command": request["command"],
+            "message": message,
+        }))
+    }
+
+    fn event(&mut self, event: &str, body: Json) -> io::Result<()> {
+        let mut message = json!({ "type": "event", "event": event });
+        if !body.is_null() {
+            message["body"] = body;
+        }
+        self.send(message)
+    }
+
+    /// Answer `initialize` until a `launch` request arrives.
+    fn until_launch(&mut self) -> io::Result<Option<(Json, Launch)>> {
+        while let Some(request) = self.request()? {
+            match request["command"].as_str().unwrap_or_default() {
+                "initialize" => {
+                    if request["arguments"]["linesStartAt1"] == json!(false) {
+                        self.line_base = 0;
+                    }
+                    self.respond(&request, capabilities())?;
+                }
+                "launch" => {
+                    let args = &request["arguments"];
+                    let Some(program) = args["program"].as_str() else {
+                        self.respond_error(&request, "launch requires a 'program' (DIR file)")?;
+                        continue;
+                    };
+                    let effect_mode = match args["effects"].as_str() {
+                        None | Some("simulate") => EffectMode::Simulate,
+                        Some("realize") => EffectMode::Realize,
+                        Some(other) => {
+                            let message = format!("unknown effect mode '{other}'");
+                            self.respond_error(&request, &message)?;
+                            continue;
+                        }
+                    };
+                    let launch = Launch {
+                        program: program.to_string(),
+                        entry: args["entry"].as_str().unwrap_or("main").to_string(),
+                        stop_on_entry: args["stopOnEntry"].as_bool().unwrap_or(false),
+                        effect_mode,
+                    };
+                    return Ok(Some((request, launch)));
+                }
+                "disconnect" => {
+                    self.respond(&request, Json::Null)?;
+                    return Ok(None);
+                }
+                other => {
+                    let message = format!("'{other}' before launch");
+                    self.respond_error(&request, &message)?;
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn launch_failed(&mut self, request: &Json, message: &str) -> io::Result<()> {
+        self.respond_error(request, message)?;
+        self.event("terminated", Json::Null)
+    }
+}
+
+fn capabilities() -> Json {
+    json!({
+        "supportsConfigurationDoneRequest": true,
+        "supportsFunctionBreakpoints": true,
+        "supportsExceptionInfoRequest": true,
+        "supportsTerminateRequest": true,
+    })
+}
+
+/// What a `variablesReference` expands to. References are valid until the
+/// next resume.
+enum Container {
+    Locals(usize),
+    Assignment(usize),
+    Q,
+    Effects,
+    Struct(Value),
+}
+
+struct Session<'p, R, W> {
+    conn: Connection<R, W>,
+    program: &'p DirProgram,
+    debugger: Debugger<'p>,
+    stop_on_entry: bool,
+
+    /// `(sourceReference, breakpoint id)` for line breakpoints.
+    source_breakpoints: Vec<(usize, usize)>,
+    function_breakpoints: Vec<usize>,
+
+    containers: Vec<Container>,
+
+    /// Effects already sent as output events.
+    reported_effects: usize,
+
+    /// Set once `terminated` has been sent.
+    ended: bool,
+}
+
+impl<R: BufRead, W: Write> Session<'_, R, W> {
+    fn run(&mut self) -> io::Result<()> {
+        while let Some(request) = self.conn.request()? {
+            let command = request["command"].as_str().unwrap_or_default().to_string();
+            let args = &request["arguments"];
+            match command.as_str() {
+                "disconnect" | "terminate" => {
+                    self.conn.respond(&request, Json::Null)?;
+                    if command == "disconnect" {
+                        return Ok(());
+                    }
+                    self.end(None)?;
+                }
+                "setBreakpoints" => {
+                    let body = self.set_breakpoints(args);
+                    self.conn.respond(&request, body)?;
+                }
+                "setFunctionBreakpoints" => {
+                    let body = self.set_function_breakpoints(args);
+                    self.conn.respond(&request, body)?;
+                }
+                "setExceptionBreakpoints" => self.conn.respond(&request, Json::Null)?,
+                "configurationDone" => {
+                    self.conn.respond(&request, Json::Null)?;
+                    if self.stop_on_entry {
+                        self.stopped("entry", None)?;
+                    } else if let Some(id) = self.debugger.hit() {
+                        self.stopped("breakpoint", Some(id))?;
+                    } else {
+                        self.resume(Resume::Continue)?;
+                    }
+                }
+                "threads" => self.conn.respond(
+                    &request,
+                    json!({ "threads": [ { "id": 1, "name": "main" } ] }),
+                )?,
+                "stackTrace" => {
+                    let body = self.stack_trace();
+                    self.conn.respond(&request, body)?;
+                }
+                "scopes" => {
+                    let frame = args["frameId"].as_u64().unwrap_or_default() as usize;
+                    match self.scopes(frame) {
+                        Some(body) => self.conn.respond(&request, body)?,
+                        None => self.conn.respond_error(&request, "unknown frameId")?,
+                    }
+                }
+                "variables" => {
+                    let reference = args["variablesReference"].as_u64().unwrap_or_default();
+                    match self.variables(reference as usize) {
+                        Some(body) => self.conn.respond(&request, body)?,
+                        None => self
+                            .conn
+                            .respond_error(&request, "unknown variablesReference")?,
+                    }
+                }
+                "source" => {
+                    let reference = args["sourceReference"].as_u64().unwrap_or_default();
+                    match self.source(reference as usize) {
+                        Some(content) => {
+                            self.conn.respond(&request, json!({ "content": content }))?
+                        }
+                        None => self
+                            .conn
+                            .respond_error(&request, "unknown sourceReference")?,
+                    }
+                }
+                "exceptionInfo" => match self.debugger.stepper().fault() {
+                    Some(fault) => {
+                        let error = TraceError::from(&fault.error);
+                        let body = json!({
+                            "exceptionId": error.kind,
+                            "description": error.message,
+                            "breakMode": "always",
+                        });
+                        self.conn.respond(&request, body)?;
+                    }
+                    None => self.conn.respond_error(&request, "no fault")?,
+                },
+                "continue" | "next" | "stepIn" | "stepOut" => {
+                    let mode = match command.as_str() {
+                        "continue" => Resume::Continue,
+                        "next" => Resume::Next,
+                        "stepIn" => Resume::Step,
+                        _ => Resume::Finish,
+                    };
+                    if command == "continue" {
+                        let body = json!({ "allThreadsContinued": true });
+                        self.conn.respond(&request, body)?;
+                    } else {
+                        self.conn.respond(&request, Json::Null)?;
+                    }
+                    self.resume(mode)?;
+                }
+                other => {
+                    let message = format!("unsupported request '{other}'");
+                    self.conn.respond_error(&request, &message)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn resume(&mut self, mode: Resume) -> io::Result<()> {
+        if self.ended {
+            return Ok(());
+        }
+        if self.debugger.stepper().fault().is_some() {
+            // Nothing runs past a fault.
+            return self.end(Some(10));
+        }
+        let stop = self.debugger.resume(mode);
+        self.containers.clear();
+        self.report_effects()?;
+        match stop {
+            Stop::Step => self.stopped("step", None),
+            Stop::Breakpoint { id, .. } => self.stopped("breakpoint", Some(id)),
+            Stop::Finished => self.end(Some(0)),
+            Stop::Fault(fault) => self.faulted(&fault),
+        }
+    }
+
+    fn report_effects(&mut self) -> io::Result<()> {
+        let events = self.debugger.stepper().effects().events.clone();
+        for event in &events[self.reported_effects..] {
+            let output = format!("{} {}\n", event.kind, event.payload);
+            self.conn
+                .event("output", json!({ "category": "stdout", "output": output }))?;
+        }
+        self.reported_effects = events.len();
+        Ok(())
+    }
+
+    fn stopped(&mut self, reason: &str, breakpoint: Option<usize>) -> io::Result<()> {
+        let mut body = json!({
+            "reason": reason,
+            "threadId": 1,
+            "allThreadsStopped": true,
+        });
+        if let Some(id) = breakpoint {
+            body["hitBreakpointIds"] = json!([id]);
+        }
+        self.conn.event("stopped", body)
+    }
+
+    fn faulted(&mut self, fault: &DvmFault) -> io::Result<()> {
+        let error = TraceError::from(&fault.error);
+        self.conn.event(
+            "stopped",
+            json!({
+                "reason": "exception",
+                "description": error.kind,
+                "text": error.message,
+                "threadId": 1,
+                "allThreadsStopped": true,
+            }),
+        )
+    }
+
+    fn end(&mut self, exit_code: Option<i32>) -> io::Result<()> {
+        if self.ended {
+            return Ok(());
+        }
+        self.ended = true;
+        if let Some(code) = exit_code {
+            self.conn.event("exited", json!({ "exitCode": code }))?;
+        }
+        self.conn.event("terminated", Json::Null)
+    }
+
+    /// Procs in forge order; `sourceReference` is the 1-based position.
+    fn procs(&self) -> impl Iterator<Item = (String, &[DirStmt])> + '_ {
+        self.program.forges.iter().flat_map(|forge| {
+            forge
+                .procs
+                .iter()
+                .map(move |p| (format!("{}::{}", forge.name, p.name), p.body.as_slice()))
+        })
+    }
+
+    fn source_ref(&self, forge: &str, proc_: &str) -> usize {
+        let name = format!("{forge}::{proc_}");
+        self.procs()
+            .position(|(n, _)| n == name)
+            .unwrap_or_default()
+            + 1
+    }
+
+    fn source(&self, reference: usize) -> Option<String> {
+        let (_, body) = self.procs().nth(reference.checked_sub(1)?)?;
+        Some(body.iter().map(|s| statement(s) + "\n").collect())
+    }
+
+    fn source_json(&self, reference: usize) -> Json {
+        let name = self
+            .procs()
+            .nth(reference - 1)
+            .map(|(n, _)| n)
+            .unwrap_or_default();
+        json!({ "name": name, "sourceReference": reference })
+    }
+
+    /// Replace the line breakpoints of one source (`sourceReference`, or a
+    /// `forge::proc` source name).
+    fn set_breakpoints(&mut self, args: &Json) -> Json {
+        let source = &args["source"];
+        let reference = match source["sourceReference"].as_u64() {
+            Some(r) if r > 0 => Some(r as usize),
+            _ => source["name"].as_str().and_then(|name| {
+                let position = self.procs().position(|(n, _)| n == name);
+                position.map(|p| p + 1)
+            }),
+        };
+
+        for (r, id) in std::mem::take(&mut self.source_breakpoints) {
+            if Some(r) == reference {
+                self.debugger.remove_breakpoint(id);
+            } else {
+                self.source_breakpoints.push((r, id));
+            }
+        }
+
+        let mut verified = Vec::new();
+        let empty = Vec::new();
+        let requested = args["breakpoints"].as_array().unwrap_or(&empty);
+        for bp in requested {
+            let line = bp["line"].as_u64().unwrap_or_default() as usize;
+            let target = reference.and_then(|r| {
+                let (name, body) = self.procs().nth(r - 1)?;
+                let index = line.checked_sub(self.conn.line_base)?;
+                (index < body.len()).then_some((r, name, index))
+            });
+            match target {
+                Some((r, name, index)) => {
+                    let (forge, proc_) = name.split_once("::").unwrap_or_default();
+                    let id = self.debugger.add_breakpoint(Breakpoint::Stmt {
+                        forge: Some(forge.to_string()),
+                        proc: proc_.to_string(),
+                        index,
+                    });
+                    self.source_breakpoints.push((r, id));
+                    verified.push(json!({ "id": id, "verified": true, "line": line }));
+                }
+                None => verified.push(json!({
+                    "verified": false,
+                    "line": line,
+                    "message": "no statement at this line",
+                })),
+            }
+        }
+        json!({ "breakpoints": verified })
+    }
+
+    /// Replace all function breakpoints.
+    fn set_function_breakpoints(&mut self, args: &Json) -> Json {
+        for id in self.function_breakpoints.drain(..) {
+            self.debugger.remove_breakpoint(id);
+        }
+        let mut result = Vec::new();
+        let empty = Vec::new();
+        for bp in args["breakpoints"].as_array().unwrap_or(&empty) {
+            match Breakpoint::parse(bp["name"].as_str().unwrap_or_default()) {
+                Ok(parsed) => {
+                    let id = self.debugger.add_breakpoint(parsed);
+                    self.function_breakpoints.push(id);
+                    result.push(json!({ "id": id, "verified": true }));
+                }
+                Err(e) => result.push(json!({ "verified": false, "message": e })),
+            }
+        }
+        json!({ "breakpoints": result })
+    }
+
+    /// Frames innermost first; `id` is the frame's 1-based depth, which stays
+    /// the same while the frame is active.
+    fn stack_trace(&self) -> Json {
+        let frames = self.debugger.stepper().frames();
+        let out: Vec<Json> = frames
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(depth, frame)| {
+                let loc = frame.location;
+                json!({
+                    "id": depth + 1,
+                    "name": location(loc),
+                    "source": self.source_json(self.source_ref(loc.forge, loc.proc)),
+                    "line": loc.index + self.conn.line_base,
+                    "column": 0,
+                })
+            })
+            .collect();
+        json!({ "stackFrames": out, "totalFrames": frames.len() })
+    }
+
+    fn scopes(&mut self, frame_id: usize) -> Option<Json> {
+        let has_assignment = {
+            let frames = self.debugger.stepper().frames();
+            !frames.get(frame_id.checked_sub(1)?)?.assignment.is_empty()
+        };
+
+        let mut scopes = vec![self.scope("Locals", Container::Locals(frame_id - 1))];
+        if has_assignment {
+            scopes.push(self.scope("Φ assignment", Container::Assignment(frame_id - 1)));
+        }
+        scopes.push(self.scope("Q state", Container::Q));
+        scopes.push(self.scope("Effects", Container::Effects));
+        Some(json!({ "scopes": scopes }))
+    }
+
+    fn scope(&mut self, name: &str, container: Container) -> Json {
+        json!({
+            "name": name,
+            "variablesReference": self.container(container),
+            "expensive": false,
+        })
+    }
+
+    fn container(&mut self, container: Container) -> usize {
+        self.containers.push(container);
+        self.containers.len()
+    }
+
+    fn variables(&mut self, reference: usize) -> Option<Json> {
+        let vars = self.collect_variables(reference)?;
+        let variables: Vec<Json> = vars
+            .into_iter()
+            .map(|var| {
+                let reference = var.child.map_or(0, |c| self.container(c));
+                json!({
+                    "name": var.name,
+                    "value": var.value,
+                    "type": var.ty,
+                    "variablesReference": reference,
+                })
+            })
+            .collect();
+        Some(json!({ "variables": variables }))
+    }
+
+    fn collect_variables(&self, reference: usize) -> Option<Vec<Variable>> {
+        let stepper = self.debugger.stepper();
+        let frames = stepper.frames();
+        let q = stepper.q();
+
+        let mut vars = Vec::new();
+        match self.containers.get(reference.checked_sub(1)?)? {
+            Container::Locals(depth) => {
+                let frame = frames.get(*depth)?;
+                for (name, v) in frame.env {
+                    let q_bound = (frame.regime == "Q").then(|| q.env.get(name)).flatten();
+                    vars.push(match q_bound {
+                        Some(_) => Variable::q(name, &q),
+                        None => Variable::value(name, v),
+                    });
+                }
+            }
+            Container::Assignment(depth) => {
+                for (name, v) in frames.get(*depth)?.assignment {
+                    vars.push(Variable::value(name, v));
+                }
+            }
+            Container::Q => {
+                for name in q.env.keys() {
+                    vars.push(Variable::q(name, &q));
+                }
+            }
+            Container::Effects => {
+                for (i, e) in stepper.effects().events.iter().enumerate() {
+                    vars.push(Variable {
+                        name: format!("{}", i + 1),
+                        value: format!("{} {}", e.kind, e.payload),
+                        ty: e.kind.clone(),
+                        child: None,
+                    });
+                }
+            }
+            Container::Struct(Value::Struct { fields, .. }) => {
+                for (name, v) in fields {
+                    vars.push(Variable::value(name, v));
+                }
+            }
+            Container::Struct(_) => {}
+        }
+        Some(vars)
+    }
+}
+
+/// A DAP variable; structs expand through `child`.
+struct Variable {
+    name: String,
+    value: String,
+    ty: String,
+    child: Option<Container>,
+}
+
+impl Variable {
+    fn value(name: &str, v: &Value) -> Self {
+        let (ty, child) = match v {
+            Value::Struct { ty, .. } => (ty.clone(), Some(Container::Struct(v.clone()))),
+            Value::Int(_) => ("Int".into(), None),
+            Value::Bool(_) => ("Bool".into(), None),
+            Value::String(_) => ("String".into(), None),
+            Value::Unit => ("Unit".into(), None),
+        };
+        Self {
+            name: name.to_string(),
+            value: v.to_string(),
+            ty,
+            child,
+        }
+    }
+
+    /// A Q binding: its resource and state, typed by the resource type.
+    fn q(name: &str, q: &QSnapshot) -> Self {
+        let b = &q.env[name];
+        let (ty, state) = q.resources.get(&b.res).map_or(("?", "missing"), |m| {
+            (m.ty.as_str(), resource_state(&m.state))
+        });
+        let moved = if b.moved { ", moved" } else { "" };
+        Self {
+            name: name.to_string(),
+            value: format!("{} ({state}{moved})", b.res.0),
+            ty: ty.to_string(),
+            child: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const PROGRAM: &str = include_str!("../../../tests/fixtures/dir/regime_tour.dir.json");
+
+    /// A scripted client: frame `requests`, serve them, and return the messages
+    /// written back.
+    fn exchange(requests: &[Json]) -> Vec<Json> {
+        let mut input = Vec::new();
+        for (i, r) in requests.iter().enumerate() {
+            let mut r = r.clone();
+            r["seq"] = (i + 1).into();
+            r["type"] = "request".into();
+            let body = serde_json::to_vec(&r).unwrap();
+            write!(input, "Content-Length: {}\r\n\r\n", body.len()).unwrap();
+            input.extend(body);
+        }
+
+        serve_raw(&input)
+    }
+
+    /// Serve framed `input` and return the messages written back.
+    fn serve_raw(input: &[u8]) -> Vec<Json> {
+        let mut output = Vec::new();
+        serve(input, &mut output).unwrap();
+
+        let mut reader = Connection {
+            input: output.as_slice(),
+            output: io::sink(),
+            seq: 0,
+            line_base: 1,
+        };
+        let mut messages = Vec::new();
+        while let Some(body) = reader.read().unwrap() {
+            messages.push(serde_json::from_slice(&body).unwrap());
+        }
+        messages
+    }
+
+    /// `response <command>` / `event <event>` per message.
+    fn kinds(messages: &[Json]) -> Vec<String> {
+        messages
+            .iter()
+            .map(|m| match m["type"].as_str() {
+                Some("response") if m["success"] == json!(false) => {
+                    format!("error {}: {}", m["command"].as_str().unwrap(), m["message"])
+                }
+                Some("response") => format!("response {}", m["command"].as_str().unwrap()),
+                _ => format!("event {}", m["event"].as_str().unwrap()),
+            })
+            .collect()
+    }
+
+    fn program_file(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!(
+            "dustrun-dap-{name}-{}.dir.json",
+            std::process::id()
+        ));
+        // Fail the final constraint so sessions end in an exception.
+        fs::write(&path, PROGRAM.replace("m Gt n", "m Lt n")).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn find<'a>(messages: &'a [Json], command: &str) -> &'a Json {
+        messages
+            .iter()
+            .find(|m| m["command"] == json!(command))
+            .unwrap()
+    }
+
+    #[test]
+    fn breakpoints_stepping_and_variables() {
+        let program = program_file("session");
+        let messages = exchange(&[
+            json!({ "command": "initialize", "arguments": { "adapterID": "dust" } }),
+            json!({ "command": "launch", "arguments": { "program": program } }),
+            // Line 2 of core::measure (source 2) is statement 1.
+            json!({ "command": "setBreakpoints", "arguments": {
+                "source": { "name": "core::measure" },
+                "breakpoints": [ { "line": 2 }, { "line": 9 } ] } }),
+            json!({ "command": "configurationDone" }),
+            json!({ "command": "stackTrace", "arguments": { "threadId": 1 } }),
+            json!({ "command": "scopes", "arguments": { "frameId": 2 } }),
+            json!({ "command": "variables", "arguments": { "variablesReference": 1 } }),
+            json!({ "command": "stepOut", "arguments": { "threadId": 1 } }),
+            json!({ "command": "next", "arguments": { "threadId": 1 } }),
+            json!({ "command": "scopes", "arguments": { "frameId": 1 } }),
+            json!({ "command": "variables", "arguments": { "variablesReference": 1 } }),
+            json!({ "command": "variables", "arguments": { "variablesReference": 4 } }),
+            json!({ "command": "source", "arguments": { "sourceReference": 2 } }),
+            json!({ "command": "continue", "arguments": { "threadId": 1 } }),
+            json!({ "command": "exceptionInfo", "arguments": { "threadId": 1 } }),
+            json!({ "command": "continue", "arguments": { "threadId": 1 } }),
+            json!({ "command": "disconnect" }),
+        ]);
+
+        assert_eq!(
+            kinds(&messages),
+            vec![
+                "response initialize",
+                "response launch",
+                "event initialized",
+                "response setBreakpoints",
+                "response configurationDone",
+                "event stopped",
+                "response stackTrace",
+                "response scopes",
+                "response variables",
+                "response stepOut",
+                "event stopped",
+                "response next",
+                "event stopped",
+                "response scopes",
+                "response variables",
+                "response variables",
+                "response source",
+                "response continue",
+                "event output",
+                "event output",
+                "event stopped",
+                "response exceptionInfo",
+                "response continue",
+                "event exited",
+                "event terminated",
+                "response disconnect",
+            ]
+        );
+
+        let bps = &find(&messages, "setBreakpoints")["body"]["breakpoints"];
+        assert_eq!(bps[0], json!({ "id": 1, "verified": true, "line": 2 }));
+        assert_eq!(bps[1]["verified"], json!(false));
+        assert_eq!(messages[5]["body"]["reason"], "breakpoint");
+        assert_eq!(messages[5]["body"]["hitBreakpointIds"], json!([1]));
+
+        let frames = &find(&messages, "stackTrace")["body"]["stackFrames"];
+        assert_eq!(
+            frames,
+            &json!([
+                { "id": 2, "name": "core::measure#1",
+                  "source": { "name": "core::measure", "sourceReference": 2 },
+                  "line": 2, "column": 0 },
+                { "id": 1, "name": "core::main#1",
+                  "source": { "name": "core::main", "sourceReference": 1 },
+                  "line": 2, "column": 0 },
+            ])
+        );
+
+        let q_vars = &messages[8]["body"]["variables"];
+        assert_eq!(
+            q_vars,
+            &json!([ { "name": "a", "value": "qres:a:1 (live)", "type": "QBit",
+                       "variablesReference": 0 } ])
+        );
+
+        // After `pick` returns, `w` is a struct whose fields expand.
+        let locals = &messages[14]["body"]["variables"];
+        assert_eq!(locals[2]["name"], "w");
+        assert_eq!(locals[2]["type"], "PhiWitness");
+        assert_eq!(locals[2]["variablesReference"], 4);
+        let fields = &messages[15]["body"]["variables"];
+        assert_eq!(
+            fields[0],
+            json!({ "name": "kind", "value": "\"Admissible\"",
+                                      "type": "String", "variablesReference": 0 })
+        );
+
+        assert_eq!(
+            find(&messages, "source")["body"]["content"],
+            "let a = q_alloc(QBit)\nlet r = q_consume(a)\nreturn 7\n"
+        );
+        assert_eq!(messages[18]["body"]["output"], "emit 9\n");
+        assert_eq!(messages[19]["body"]["output"], "seal 2\n");
+        assert_eq!(messages[20]["body"]["reason"], "exception");
+        assert_eq!(
+            find(&messages, "exceptionInfo")["body"]["exceptionId"],
+            "Inadmissible"
+        );
+        assert_eq!(messages[23]["body"]["exitCode"], 10);
+        fs::remove_file(program).unwrap();
+    }
+
+    #[test]
+    fn function_breakpoints_and_stop_on_entry() {
+        let program = program_file("entry");
+        let messages = exchange(&[
+            json!({ "command": "initialize", "arguments": {} }),
+            json!({ "command": "threads" }),
+            json!({ "command": "launch", "arguments": {
+                "program": program, "stopOnEntry": true } }),
+            json!({ "command": "setFunctionBreakpoints", "arguments": {
+                "breakpoints": [ { "name": "effect emit" }, { "name": "main#x" } ] } }),
+            json!({ "command": "configurationDone" }),
+            json!({ "command": "continue", "arguments": { "threadId": 1 } }),
+            json!({ "command": "stackTrace", "arguments": { "threadId": 1 } }),
+        ]);
+
+        assert_eq!(
+            kinds(&messages),
+            vec![
+                "response initialize",
+                "error threads: \"'threads' before launch\"",
+                "response launch",
+                "event initialized",
+                "response setFunctionBreakpoints",
+                "response configurationDone",
+                "event stopped",
+                "response continue",
+                "event output",
+                "event stopped",
+                "response stackTrace",
+            ]
+        );
+        assert_eq!(messages[6]["body"]["reason"], "entry");
+        assert_eq!(
+            messages[4]["body"]["breakpoints"][1]["verified"],
+            json!(false)
+        );
+        // Effect breakpoints stop after the emitting statement.
+        assert_eq!(messages[9]["body"]["hitBreakpointIds"], json!([1]));
+        assert_eq!(
+            messages[10]["body"]["stackFrames"][0]["name"],
+            "core::main#4"
+        );
+        fs::remove_file(program).unwrap();
+    }
+
+    #[test]
+    fn malformed_requests_get_error_responses() {
+        let mut input = Vec::new();
+        for body in [
+            "{ not json".to_string(),
+            json!({ "seq": 1, "type": "request" }).to_string(),
+            json!({ "seq": 2, "type": "request", "command": "disconnect" }).to_string(),
+        ] {
+            write!(input, "Content-Length: {}\r\n\r\n{body}", body.len()).unwrap();
+        }
+        let messages = serve_raw(&input);
+        assert_eq!(
+            kinds(&messages),
+            vec![
+                "error : \"malformed request: key must be a string at line 1 column 3\"",
+                "error : \"malformed request: missing 'command'\"",
+                "response disconnect",
+            ]
+        );
+        assert_eq!(messages[1]["request_seq"], 1);
+    }
+
+    #[test]
+    fn missing_content_length_is_rejected() {
+        let input = b"Content-Type: application/json\r\n\r\n{}";
+        let err = serve(&input[..], io::sink()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "missing Content-Length header");
+    }
+
+    #[test]
+    fn launch_failures_terminate_the_session() {
+        let messages = exchange(&[
+            json!({ "command": "initialize", "arguments": {} }),
+            json!({ "command": "launch", "arguments": {
+                "program": "/nonexistent/program.dir.json" } }),
+        ]);
+        assert_eq!(messages[1]["success"], json!(false));
+        assert_eq!(messages[2]["event"], "terminated");
+    }
+}
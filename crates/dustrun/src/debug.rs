@@ -0,0 +1,630 @@
+// File: crates/dustrun/src/debug.rs
+//
+// `dustrun debug`: a line-oriented debugger over `dust_dvm::Stepper`.
+//
+// `Debugger` holds the breakpoints and stepping rules; `session` reads one
+// command per line and writes deterministic output, so a session can be
+// scripted by piping commands on stdin.
+
+use dust_dvm::{
+    DirStmt, DvmFault, EffectEvent, EffectLog, QResState, QSnapshot, StackFrame, StepStatus,
+    Stepper, StmtLocation, Value,
+};
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+/// Where execution stops.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Before statement `index` of `proc` (in any forge unless `forge` is set).
+    Stmt {
+        forge: Option<String>,
+        proc: String,
+        index: usize,
+    },
+
+    /// After a statement emits an effect of `kind`.
+    Effect { kind: String },
+}
+
+impl Breakpoint {
+    /// Parse `[forge::]proc[#index]` or `effect <kind>`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        let effect = spec
+            .strip_prefix("effect")
+            .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace));
+        if let Some(kind) = effect {
+            let kind = kind.trim();
+            if kind.is_empty() {
+                return Err("missing effect kind".into());
+            }
+            return Ok(Breakpoint::Effect {
+                kind: kind.to_string(),
+            });
+        }
+        let (qualified, index) = match spec.split_once('#') {
+            Some((name, index)) => (
+                name,
+                index
+                    .parse()
+                    .map_err(|_| format!("invalid statement index '{index}'"))?,
+            ),
+            None => (spec, 0),
+        };
+        let (forge, proc_) = match qualified.split_once("::") {
+            Some((forge, proc_)) => (Some(forge.to_string()), proc_),
+            None => (None, qualified),
+        };
+        if proc_.is_empty() || spec.contains(char::is_whitespace) {
+            return Err(format!(
+                "invalid breakpoint '{spec}' (expected [forge::]proc[#index] or effect <kind>)"
+            ));
+        }
+        Ok(Breakpoint::Stmt {
+            forge,
+            proc: proc_.to_string(),
+            index,
+        })
+    }
+
+    fn at(&self, loc: StmtLocation<'_>) -> bool {
+        match self {
+            Breakpoint::Stmt { forge, proc, index } => {
+                forge.as_deref().map_or(true, |f| f == loc.forge)
+                    && proc == loc.proc
+                    && *index == loc.index
+            }
+            Breakpoint::Effect { .. } => false,
+        }
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Breakpoint::Stmt {
+                forge: Some(forge),
+                proc,
+                index,
+            } => write!(f, "{forge}::{proc}#{index}"),
+            Breakpoint::Stmt {
+                forge: None,
+                proc,
+                index,
+            } => write!(f, "{proc}#{index}"),
+            Breakpoint::Effect { kind } => write!(f, "effect {kind}"),
+        }
+    }
+}
+
+/// How far to run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resume {
+    /// One statement, stepping into calls.
+    Step,
+
+    /// One statement of the current frame, running calls to completion.
+    Next,
+
+    /// Until the current frame returns.
+    Finish,
+
+    /// Until a breakpoint, the end of the run, or a fault.
+    Continue,
+}
+
+/// Why execution stopped.
+#[derive(Debug, Clone)]
+pub enum Stop {
+    /// The requested step completed.
+    Step,
+
+    /// Breakpoint `id` was hit; `effect` is the event that triggered an effect breakpoint.
+    Breakpoint {
+        id: usize,
+        effect: Option<EffectEvent>,
+    },
+
+    /// The entrypoint returned.
+    Finished,
+
+    /// A statement faulted; the run cannot continue.
+    Fault(Box<DvmFault>),
+}
+
+/// Breakpoints and stepping over a `Stepper`.
+#[derive(Debug)]
+pub struct Debugger<'p> {
+    stepper: Stepper<'p>,
+
+    /// `(id, breakpoint)`, in creation order.
+    breakpoints: Vec<(usize, Breakpoint)>,
+    next_id: usize,
+}
+
+impl<'p> Debugger<'p> {
+    pub fn new(stepper: Stepper<'p>) -> Self {
+        Self {
+            stepper,
+            breakpoints: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    pub fn stepper(&self) -> &Stepper<'p> {
+        &self.stepper
+    }
+
+    /// Add a breakpoint, returning its id.
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.breakpoints.push((id, breakpoint));
+        id
+    }
+
+    /// Remove a breakpoint; `false` if there is none with this id.
+    pub fn remove_breakpoint(&mut self, id: usize) -> bool {
+        let before = self.breakpoints.len();
+        self.breakpoints.retain(|(b, _)| *b != id);
+        self.breakpoints.len() != before
+    }
+
+    pub fn breakpoints(&self) -> &[(usize, Breakpoint)] {
+        &self.breakpoints
+    }
+
+    /// The statement breakpoint at the current location, if any.
+    pub fn hit(&self) -> Option<usize> {
+        let loc = self.stepper.location()?;
+        self.breakpoints
+            .iter()
+            .find(|(_, bp)| bp.at(loc))
+            .map(|(id, _)| *id)
+    }
+
+    /// Execute at least one statement, stopping as `mode` asks.
+    ///
+    /// Effect breakpoints stop after the emitting statement, statement breakpoints
+    /// before theirs. Once the run has finished or faulted, this keeps returning
+    /// `Finished` or the same fault.
+    pub fn resume(&mut self, mode: Resume) -> Stop {
+        let depth = self.stepper.depth();
+        loop {
+            let emitted = self.stepper.effects().events.len();
+            match self.stepper.step() {
+                Ok(StepStatus::Running) => {}
+                Ok(StepStatus::Finished) => return Stop::Finished,
+                Err(fault) => return Stop::Fault(Box::new(fault)),
+            }
+
+            let new_events = &self.stepper.effects().events[emitted..];
+            for (id, bp) in &self.breakpoints {
+                let Breakpoint::Effect { kind } = bp else {
+                    continue;
+                };
+                if let Some(event) = new_events.iter().find(|e| &e.kind == kind) {
+                    return Stop::Breakpoint {
+                        id: *id,
+                        effect: Some(event.clone()),
+                    };
+                }
+            }
+
+            let now = self.stepper.depth();
+            match mode {
+                Resume::Step => return Stop::Step,
+                Resume::Next if now <= depth => return Stop::Step,
+                Resume::Finish if now < depth => return Stop::Step,
+                _ => {}
+            }
+
+            if let Some(id) = self.hit() {
+                return Stop::Breakpoint { id, effect: None };
+            }
+        }
+    }
+}
+
+/// Run a debugging session: one command per line of `input`, output to `out`.
+///
+/// With `prompt`, a `(dust) ` prompt precedes each command (interactive use).
+pub fn session(
+    debugger: &mut Debugger<'_>,
+    input: impl BufRead,
+    mut out: impl Write,
+    prompt: bool,
+) -> io::Result<()> {
+    show_location(debugger.stepper(), &mut out)?;
+
+    let mut lines = input.lines();
+    loop {
+        if prompt {
+            write!(out, "(dust) ")?;
+            out.flush()?;
+        }
+        let Some(line) = lines.next().transpose()? else {
+            break;
+        };
+        let (cmd, arg) = match line.trim().split_once(char::is_whitespace) {
+            Some((cmd, arg)) => (cmd, arg.trim()),
+            None => (line.trim(), ""),
+        };
+
+        match cmd {
+            "" => {}
+            "quit" | "exit" => break,
+            "help" | "h" => write!(out, "{HELP}")?,
+            "step" | "s" => report(debugger, Resume::Step, &mut out)?,
+            "next" | "n" => report(debugger, Resume::Next, &mut out)?,
+            "finish" => report(debugger, Resume::Finish, &mut out)?,
+            "continue" | "c" => report(debugger, Resume::Continue, &mut out)?,
+            "break" | "b" => match Breakpoint::parse(arg) {
+                Ok(bp) => {
+                    let text = bp.to_string();
+                    let id = debugger.add_breakpoint(bp);
+                    writeln!(out, "breakpoint {id}: {text}")?;
+                }
+                Err(e) => writeln!(out, "error: {e}")?,
+            },
+            "delete" | "d" => match arg.parse() {
+                Ok(id) if debugger.remove_breakpoint(id) => {
+                    writeln!(out, "deleted breakpoint {id}")?
+                }
+                _ => writeln!(out, "error: no breakpoint '{arg}'")?,
+            },
+            "breakpoints" => {
+                if debugger.breakpoints().is_empty() {
+                    writeln!(out, "no breakpoints")?;
+                }
+                for (id, bp) in debugger.breakpoints() {
+                    writeln!(out, "breakpoint {id}: {bp}")?;
+                }
+            }
+            "where" | "bt" => show_stack(debugger.stepper(), &mut out)?,
+            "locals" => show_locals(debugger.stepper(), &mut out)?,
+            "print" | "p" => show_value(debugger.stepper(), arg, &mut out)?,
+            "qstate" => show_q(&debugger.stepper().q(), &mut out)?,
+            "effects" => show_effects(debugger.stepper().effects(), &mut out)?,
+            other => writeln!(out, "error: unknown command '{other}' (try 'help')")?,
+        }
+    }
+    out.flush()
+}
+
+const HELP: &str = "\
+commands:
+  step (s)                 execute one statement, stepping into calls
+  next (n)                 execute one statement, running calls to completion
+  finish                   run until the current proc returns
+  continue (c)             run until a breakpoint, the end, or a fault
+  break (b) SPEC           break before [forge::]proc[#index] or after `effect <kind>`
+  delete (d) ID            delete a breakpoint
+  breakpoints              list breakpoints
+  where (bt)               show the call stack
+  locals                   show the bindings of the current proc
+  print (p) NAME[.FIELD]   show a binding, struct field or Q binding
+  qstate                   show Q bindings and resources
+  effects                  show the effect log so far
+  quit                     end the session
+";
+
+fn report(debugger: &mut Debugger<'_>, mode: Resume, out: &mut impl Write) -> io::Result<()> {
+    match debugger.resume(mode) {
+        Stop::Step => {}
+        Stop::Breakpoint { id, effect: None } => writeln!(out, "breakpoint {id} hit")?,
+        Stop::Breakpoint {
+            id,
+            effect: Some(e),
+        } => writeln!(out, "breakpoint {id} hit: effect {} {}", e.kind, e.payload)?,
+        Stop::Finished => {
+            let stepper = debugger.stepper();
+            match stepper.returned() {
+                Some(v) => writeln!(out, "finished: return {v}")?,
+                None => writeln!(out, "finished: return <none>")?,
+            }
+            return writeln!(out, "time.ticks: {}", stepper.time().tick.0);
+        }
+        Stop::Fault(fault) => {
+            let at = debugger
+                .stepper()
+                .location()
+                .map(|loc| format!(" at {}", location(loc)))
+                .unwrap_or_default();
+            writeln!(out, "fault{at}: {}", fault.error)?;
+            if let Some(x) = &fault.explanation {
+                writeln!(out, "explanation:")?;
+                for line in x.to_string().lines() {
+                    writeln!(out, "  {line}")?;
+                }
+            }
+            return Ok(());
+        }
+    }
+    show_location(debugger.stepper(), out)
+}
+
+fn show_location(stepper: &Stepper<'_>, out: &mut impl Write) -> io::Result<()> {
+    match (stepper.location(), stepper.statement()) {
+        (Some(loc), Some(stmt)) => writeln!(
+            out,
+            "stopped at {} (tick {}): {}",
+            location(loc),
+            stepper.time().tick.0,
+            statement(stmt)
+        ),
+        _ => writeln!(out, "not running"),
+    }
+}
+
+fn show_stack(stepper: &Stepper<'_>, out: &mut impl Write) -> io::Result<()> {
+    let frames = stepper.frames();
+    if frames.is_empty() {
+        return writeln!(out, "not running");
+    }
+    for (i, frame) in frames.iter().rev().enumerate() {
+        writeln!(out, "#{i} {}", location(frame.location))?;
+    }
+    Ok(())
+}
+
+fn show_locals(stepper: &Stepper<'_>, out: &mut impl Write) -> io::Result<()> {
+    let frames = stepper.frames();
+    let Some(frame) = frames.last() else {
+        return writeln!(out, "not running");
+    };
+    let pending: Vec<_> = frame
+        .assignment
+        .iter()
+        .filter(|(name, _)| !frame.env.contains_key(*name))
+        .collect();
+    if frame.env.is_empty() && pending.is_empty() {
+        return writeln!(out, "no bindings");
+    }
+    let q = stepper.q();
+    for name in frame.env.keys() {
+        show_binding(frame, &q, name, out)?;
+    }
+    for (name, v) in pending {
+        writeln!(out, "{name} = {v} (Φ assignment)")?;
+    }
+    Ok(())
+}
+
+/// Print `name[.field...]` from the current frame.
+fn show_value(stepper: &Stepper<'_>, path: &str, out: &mut impl Write) -> io::Result<()> {
+    let frames = stepper.frames();
+    let Some(frame) = frames.last() else {
+        return writeln!(out, "not running");
+    };
+    let mut parts = path.split('.');
+    let name = parts.next().unwrap_or_default();
+    let q = stepper.q();
+    if parts.clone().next().is_none() {
+        return show_binding(frame, &q, name, out);
+    }
+
+    let Some(mut v) = frame.env.get(name).or_else(|| frame.assignment.get(name)) else {
+        return writeln!(
+            out,
+            "error: no binding '{name}' in {}",
+            location(frame.location)
+        );
+    };
+    let mut walked = name.to_string();
+    for field in parts {
+        match v {
+            Value::Struct { fields, .. } if fields.contains_key(field) => v = &fields[field],
+            _ => return writeln!(out, "error: '{walked}' has no field '{field}'"),
+        }
+        walked = format!("{walked}.{field}");
+    }
+    writeln!(out, "{path} = {v}")
+}
+
+/// Print one binding: a Q binding in a Q frame, otherwise a value (or a Φ
+/// assignment not yet bound).
+fn show_binding(
+    frame: &StackFrame<'_>,
+    q: &QSnapshot,
+    name: &str,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    if frame.regime == "Q" {
+        if let Some(b) = q.env.get(name) {
+            let (ty, state) = q.resources.get(&b.res).map_or(("?", "missing"), |m| {
+                (m.ty.as_str(), resource_state(&m.state))
+            });
+            let moved = if b.moved { ", moved" } else { "" };
+            return writeln!(out, "{name} = {} ({ty}, {state}{moved})", b.res.0);
+        }
+    }
+    match (frame.env.get(name), frame.assignment.get(name)) {
+        (Some(v), _) => writeln!(out, "{name} = {v}"),
+        (None, Some(v)) => writeln!(out, "{name} = {v} (Φ assignment)"),
+        (None, None) => writeln!(
+            out,
+            "error: no binding '{name}' in {}",
+            location(frame.location)
+        ),
+    }
+}
+
+pub(crate) fn show_q(q: &QSnapshot, out: &mut impl Write) -> io::Result<()> {
+    if q.env.is_empty() {
+        writeln!(out, "q bindings: <none>")?;
+    } else {
+        writeln!(out, "q bindings:")?;
+        for (name, b) in &q.env {
+            let moved = if b.moved { " (moved)" } else { "" };
+            writeln!(out, "  {name} -> {}{moved}", b.res.0)?;
+        }
+    }
+    if q.resources.is_empty() {
+        writeln!(out, "q resources: <none>")
+    } else {
+        writeln!(out, "q resources:")?;
+        for (id, meta) in &q.resources {
+            writeln!(
+                out,
+                "  {} {} {}",
+                id.0,
+                meta.ty,
+                resource_state(&meta.state)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn show_effects(effects: &EffectLog, out: &mut impl Write) -> io::Result<()> {
+    let events = &effects.events;
+    if events.is_empty() {
+        return writeln!(out, "effects: <none>");
+    }
+    writeln!(out, "effects:")?;
+    for (i, ev) in events.iter().enumerate() {
+        writeln!(out, "  {}. {} {}", i + 1, ev.kind, ev.payload)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn resource_state(state: &QResState) -> &'static str {
+    match state {
+        QResState::Live => "live",
+        QResState::Consumed => "consumed",
+        QResState::Invalid => "invalid",
+    }
+}
+
+pub(crate) fn location(loc: StmtLocation<'_>) -> String {
+    format!("{}::{}#{}", loc.forge, loc.proc, loc.index)
+}
+
+/// One-line rendering of a statement.
+pub(crate) fn statement(stmt: &DirStmt) -> String {
+    match stmt {
+        DirStmt::Let { name, expr } => format!("let {name} = {expr}"),
+        DirStmt::Constrain { predicate } => format!("constrain {predicate}"),
+        DirStmt::Prove { name, from } => format!("prove {name} from {from}"),
+        DirStmt::Effect { kind, payload } => format!("effect {kind} {payload}"),
+        DirStmt::Return { expr } => format!("return {expr}"),
+        DirStmt::Call { name, callee, args } => {
+            let target = name.as_ref().map(|n| format!("{n} = ")).unwrap_or_default();
+            format!("call {target}{callee}({})", args.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dust_dvm::{Dvm, DvmConfig};
+    use pretty_assertions::assert_eq;
+
+    const PROGRAM: &str = include_str!("../../../tests/fixtures/dir/regime_tour.dir.json");
+
+    fn transcript(program: &str, commands: &str) -> String {
+        let dvm = Dvm::new(DvmConfig::default());
+        let program = dvm.load_dir_json(program.as_bytes()).unwrap();
+        let mut debugger = Debugger::new(dvm.stepper(&program, "main").unwrap());
+        let mut out = Vec::new();
+        session(&mut debugger, commands.as_bytes(), &mut out, false).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn scripted_session_is_deterministic() {
+        let commands = "\
+break measure#1
+break effect seal
+continue
+where
+qstate
+print a
+finish
+step
+locals
+next
+next
+next
+next
+print w.kind
+print w.assignment.x
+print w.nope
+print z
+next
+effects
+continue
+continue
+step
+";
+        let expected = "\
+stopped at core::main#0 (tick 0): let n = 2
+breakpoint 1: measure#1
+breakpoint 2: effect seal
+breakpoint 1 hit
+stopped at core::measure#1 (tick 2): let r = q_consume(a)
+#0 core::measure#1
+#1 core::main#1
+q bindings:
+  a -> qres:a:1
+q resources:
+  qres:a:1 QBit live
+a = qres:a:1 (QBit, live)
+stopped at core::main#2 (tick 4): call w = pick()
+stopped at core::pick#0 (tick 4): let x = phi_unknown(Int, 0, 5)
+x = 4 (Φ assignment)
+stopped at core::pick#1 (tick 5): constrain x Gt 3
+stopped at core::pick#2 (tick 6): let w = phi_witness()
+stopped at core::pick#3 (tick 7): return w
+stopped at core::main#3 (tick 8): effect emit m Add n
+w.kind = \"Admissible\"
+w.assignment.x = 4
+error: 'w' has no field 'nope'
+error: no binding 'z' in core::main#3
+stopped at core::main#4 (tick 9): effect seal n
+effects:
+  1. emit 9
+breakpoint 2 hit: effect seal 2
+stopped at core::main#5 (tick 10): constrain m Gt n
+finished: return 7
+time.ticks: 11
+finished: return 7
+time.ticks: 11
+";
+        assert_eq!(transcript(PROGRAM, commands), expected);
+        assert_eq!(transcript(PROGRAM, commands), expected);
+    }
+
+    #[test]
+    fn faults_stop_at_the_faulting_statement() {
+        let program = PROGRAM.replace("q_consume(a)", "a Add 1");
+        assert_eq!(
+            transcript(&program, "continue\nwhere\nstep\n"),
+            "\
+stopped at core::main#0 (tick 0): let n = 2
+fault at core::measure#1: runtime error: Add/Sub requires int operands
+#0 core::measure#1
+#1 core::main#1
+fault at core::measure#1: runtime error: Add/Sub requires int operands
+"
+        );
+    }
+
+    #[test]
+    fn breakpoint_specs() {
+        assert_eq!(
+            Breakpoint::parse("core::main#3").unwrap().to_string(),
+            "core::main#3"
+        );
+        assert_eq!(Breakpoint::parse("main").unwrap().to_string(), "main#0");
+        assert_eq!(
+            Breakpoint::parse("effects_log").unwrap().to_string(),
+            "effects_log#0"
+        );
+        assert!(Breakpoint::parse("main#x").is_err());
+        assert!(Breakpoint::parse("effect").is_err());
+    }
+}
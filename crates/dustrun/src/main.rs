@@ -1,11 +1,30 @@
 // File: crates/dustrun/src/main.rs
 
 mod args;
+mod dap;
+mod debug;
+mod repl;
 
-use args::{Args, EffectModeArg};
+use args::{
+    Args, AsmArgs, CheckArgs, CheckpointArgs, Command, ConvertArgs, DebugArgs, DiffArgs,
+    DirFormatArg, EffectModeArg, LinkArgs, RegimeArg, ReplArgs, ResumeArgs, RunArgs, TraceCommand,
+    TraceUpgradeArgs, VerifyWitnessArgs,
+};
 use clap::Parser;
-use dust_dvm::{Dvm, DvmConfig, DvmTrace, EffectMode};
+use dust_dvm::dir::asm as dir_asm;
+use dust_dvm::dir::format::{self as dir_format, DirEncoding, DirProducer};
+use dust_dvm::dir::link as dir_link;
+use dust_dvm::dir::validate::Severity;
+use dust_dvm::trace::jsonl::{self, JsonlSink};
+use dust_dvm::trace::migrate::{upgrade as upgrade_trace, Upgraded};
+use dust_dvm::{
+    Checkpoint, DirProgram, Dvm, DvmConfig, DvmFault, DvmOutcome, DvmSnapshot, DvmTrace,
+    EffectMode, PhiVerification, PhiWitness, Value,
+};
+use std::cell::RefCell;
 use std::fs;
+use std::io::{self, IsTerminal, LineWriter};
+use std::rc::Rc;
 
 fn main() {
     // Deterministic logging initialization:
@@ -15,14 +34,24 @@ fn main() {
 
     let args = Args::parse();
 
-    let bytes = match fs::read(&args.dir_path) {
-        Ok(b) => b,
-        Err(e) => {
-            eprintln!("dustrun: failed to read DIR file '{}': {e}", args.dir_path);
-            std::process::exit(2);
-        }
-    };
+    match args.command {
+        Some(Command::VerifyWitness(a)) => verify_witness(a),
+        Some(Command::Resume(a)) => resume(a),
+        Some(Command::Debug(a)) => debug(a),
+        Some(Command::Dap) => dap(),
+        Some(Command::Repl(a)) => repl(a),
+        Some(Command::Asm(a)) => asm(a),
+        Some(Command::Disasm(a)) => disasm(a),
+        Some(Command::Convert(a)) => convert(a),
+        Some(Command::Link(a)) => link(a),
+        Some(Command::Check(a)) => check(a),
+        Some(Command::Diff(a)) => diff(a),
+        Some(Command::Trace(TraceCommand::Upgrade(a))) => trace_upgrade(a),
+        None => run(args.run),
+    }
+}
 
+fn run(args: RunArgs) {
     let effect_mode = match args.effects {
         EffectModeArg::Simulate => EffectMode::Simulate,
         EffectModeArg::Realize => EffectMode::Realize,
@@ -31,49 +60,214 @@ fn main() {
     let cfg = DvmConfig {
         effect_mode,
         trace: args.trace,
+        // A streamed trace records its steps in the sink, not in memory.
+        steps: args.steps && args.emit_trace,
     };
 
-    let dvm = Dvm::new(cfg);
+    let mut dvm = Dvm::new(cfg);
 
-    let program = match dvm.load_dir_json(&bytes) {
-        Ok(p) => p,
+    let program = load_programs(&dvm, &args.dir_paths);
+
+    let jsonl = args.trace_jsonl.as_deref().map(|path| {
+        let file = match fs::File::create(path) {
+            Ok(f) => f,
+            Err(e) => {
+                eprintln!("dustrun: failed to create JSONL trace file '{path}': {e}");
+                std::process::exit(2);
+            }
+        };
+        let sink = Rc::new(RefCell::new(JsonlSink::new(
+            LineWriter::new(file),
+            args.steps,
+        )));
+        dvm.attach_observer(sink.clone());
+        (path, sink)
+    });
+
+    let result = match &args.checkpoint.pause_after {
+        Some(n) => match dvm.run_for(&program, &args.entry, *n) {
+            Ok(Checkpoint::Paused(snapshot)) => {
+                write_snapshot(&snapshot, &args.checkpoint, *n, args.quiet);
+                return;
+            }
+            Ok(Checkpoint::Finished(outcome)) => Ok(outcome),
+            Err(fault) => Err(fault),
+        },
+        None => dvm.run_entrypoint_with_fault(&program, &args.entry),
+    };
+
+    if let Some((path, sink)) = &jsonl {
+        let trace = match &result {
+            Ok(o) => DvmTrace::Success(o.clone().into()),
+            Err(f) => DvmTrace::Failure(f.clone().into()),
+        };
+        if let Err(e) = sink.borrow_mut().finish(&trace) {
+            eprintln!("dustrun: failed to write JSONL trace file '{path}': {e}");
+            std::process::exit(2);
+        }
+    }
+
+    let footer = [
+        ("effect_mode", args.effects.as_str().to_string()),
+        ("entry", args.entry.clone()),
+    ];
+    report(result, args.emit_trace, args.quiet, &footer);
+}
+
+fn resume(args: ResumeArgs) {
+    let bytes = read_file(&args.snapshot_path, "snapshot");
+    let snapshot: DvmSnapshot = match serde_json::from_slice(&bytes) {
+        Ok(s) => s,
         Err(e) => {
-            eprintln!("dustrun: DIR load error: {e}");
+            eprintln!("dustrun: snapshot load error: {e}");
             std::process::exit(3);
         }
     };
 
-    let outcome = match dvm.run_entrypoint(&program, &args.entry) {
-        Ok(o) => o,
+    let dvm = Dvm::new(DvmConfig {
+        trace: args.trace,
+        ..DvmConfig::default()
+    });
+
+    let result = match &args.checkpoint.pause_after {
+        Some(n) => match dvm.resume_for(&snapshot, *n) {
+            Ok(Checkpoint::Paused(next)) => {
+                write_snapshot(&next, &args.checkpoint, *n, args.quiet);
+                return;
+            }
+            Ok(Checkpoint::Finished(outcome)) => Ok(outcome),
+            Err(fault) => Err(fault),
+        },
+        None => dvm.resume(&snapshot),
+    };
+
+    let effect_mode = match snapshot.effect_mode() {
+        EffectMode::Simulate => "simulate",
+        EffectMode::Realize => "realize",
+    };
+    let footer = [
+        ("effect_mode", effect_mode.to_string()),
+        ("resumed", args.snapshot_path.clone()),
+    ];
+    report(result, args.emit_trace, args.quiet, &footer);
+}
+
+fn debug(args: DebugArgs) {
+    let bytes = read_file(&args.dir_path, "DIR");
+    let effect_mode = match args.effects {
+        EffectModeArg::Simulate => EffectMode::Simulate,
+        EffectModeArg::Realize => EffectMode::Realize,
+    };
+    let dvm = Dvm::new(DvmConfig {
+        effect_mode,
+        ..DvmConfig::default()
+    });
+    let program = load_program(&dvm, &args.dir_path, &bytes);
+    let stepper = match dvm.stepper(&program, &args.entry) {
+        Ok(s) => s,
+        Err(fault) => {
+            eprintln!("dustrun: {}", fault.error);
+            std::process::exit(10);
+        }
+    };
+
+    let stdin = io::stdin();
+    let prompt = stdin.is_terminal();
+    let mut debugger = debug::Debugger::new(stepper);
+    if let Err(e) = debug::session(&mut debugger, stdin.lock(), io::stdout().lock(), prompt) {
+        eprintln!("dustrun: debug session I/O error: {e}");
+        std::process::exit(2);
+    }
+}
+
+fn dap() {
+    if let Err(e) = dap::serve(io::stdin().lock(), io::stdout().lock()) {
+        eprintln!("dustrun: DAP session I/O error: {e}");
+        std::process::exit(2);
+    }
+}
+
+fn repl(args: ReplArgs) {
+    let regime = match args.regime {
+        RegimeArg::K => repl::Regime::K,
+        RegimeArg::Q => repl::Regime::Q,
+    };
+    let stdin = io::stdin();
+    let prompt = stdin.is_terminal();
+    let mut session = repl::Repl::new(regime);
+    if let Err(e) = repl::session(&mut session, stdin.lock(), io::stdout().lock(), prompt) {
+        eprintln!("dustrun: REPL I/O error: {e}");
+        std::process::exit(2);
+    }
+}
+
+/// Write the snapshot of a paused run.
+fn write_snapshot(snapshot: &DvmSnapshot, args: &CheckpointArgs, statements: usize, quiet: bool) {
+    let path = args.snapshot.as_deref().unwrap_or_default();
+    let json = match serde_json::to_string_pretty(snapshot) {
+        Ok(s) => s,
         Err(e) => {
+            eprintln!("dustrun: failed to serialize snapshot: {e}");
+            std::process::exit(4);
+        }
+    };
+    if let Err(e) = fs::write(path, json) {
+        eprintln!("dustrun: failed to write snapshot file '{path}': {e}");
+        std::process::exit(2);
+    }
+    if !quiet {
+        let at = snapshot
+            .location()
+            .map(|l| format!("{}::{}#{}", l.forge, l.proc, l.index))
+            .unwrap_or_default();
+        println!("paused: after {statements} statement(s) at {at}");
+        println!("snapshot: {path}");
+    }
+}
+
+/// Print the outcome of a run and exit with its status.
+fn report(
+    result: Result<DvmOutcome, DvmFault>,
+    emit_trace: bool,
+    quiet: bool,
+    footer: &[(&str, String)],
+) {
+    let outcome = match result {
+        Ok(o) => o,
+        Err(fault) => {
             // Inadmissibility is a first-class outcome, but it is still a failure to execute.
             // Exit code reflects semantic failure vs IO failure.
-            if !args.quiet {
-                eprintln!("dustrun: {e}");
+            if emit_trace {
+                print_trace(&DvmTrace::Failure(fault.into()));
+            } else if !quiet {
+                match &fault.span {
+                    Some(span) => eprintln!("dustrun: {span}: {}", fault.error),
+                    None => eprintln!("dustrun: {}", fault.error),
+                }
+                if let Some(w) = &fault.witness {
+                    eprintln!("dustrun: witness {} ({:?})", w.id, w.kind);
+                }
+                if let Some(x) = &fault.explanation {
+                    eprintln!("dustrun: explanation:");
+                    for line in x.to_string().lines() {
+                        eprintln!("dustrun:   {line}");
+                    }
+                }
             }
             // 10-series codes are semantic failures (inadmissible / time / effect / runtime)
             std::process::exit(10);
         }
     };
 
-    if args.emit_trace {
-        let trace: DvmTrace = DvmTrace::Success(outcome.into());
-        match serde_json::to_string_pretty(&trace) {
-            Ok(s) => {
-                println!("{s}");
-            }
-            Err(e) => {
-                eprintln!("dustrun: failed to serialize trace: {e}");
-                std::process::exit(4);
-            }
-        }
+    if emit_trace {
+        print_trace(&DvmTrace::Success(outcome.into()));
         return;
     }
 
     // Human-readable deterministic output.
-    if !args.quiet {
+    if !quiet {
         if let Some(ret) = outcome.returned {
-            println!("return: {}", format_value(&ret));
+            println!("return: {ret}");
         } else {
             println!("return: <none>");
         }
@@ -88,8 +282,360 @@ fn main() {
         }
 
         println!("time.ticks: {}", outcome.time.tick.0);
-        println!("effect_mode: {}", args.effects.as_str());
-        println!("entry: {}", args.entry);
+        for (label, value) in footer {
+            println!("{label}: {value}");
+        }
+    }
+}
+
+fn verify_witness(args: VerifyWitnessArgs) {
+    let witness_bytes = read_file(&args.witness_path, "witness");
+    let dir_bytes = read_file(&args.dir_path, "DIR");
+
+    let witness = match load_witness(&witness_bytes) {
+        Ok(w) => w,
+        Err(e) => {
+            eprintln!("dustrun: witness load error: {e}");
+            std::process::exit(3);
+        }
+    };
+
+    let dvm = Dvm::new(DvmConfig::default());
+    let program = load_program(&dvm, &args.dir_path, &dir_bytes);
+
+    let proc_ = match program.resolve_proc(None, &args.proc_name) {
+        Ok((f, p)) => &program.forges[f].procs[p],
+        Err(e) => {
+            eprintln!("dustrun: proc not found: {}", e.describe(&args.proc_name));
+            std::process::exit(3);
+        }
+    };
+
+    match dust_dvm::phi_verify_witness(&witness, proc_) {
+        Ok(PhiVerification::Valid) => {
+            println!("witness {}: valid ({:?})", witness.id, witness.kind);
+        }
+        Ok(PhiVerification::Invalid { reason }) => {
+            println!("witness {}: invalid: {reason}", witness.id);
+            std::process::exit(10);
+        }
+        Err(e) => {
+            eprintln!("dustrun: {e}");
+            std::process::exit(10);
+        }
+    }
+}
+
+/// Decode a witness in its native encoding or as a `PhiWitness` value (e.g. an
+/// emitted payload of a witness a K proc received from a Φ call).
+fn load_witness(bytes: &[u8]) -> Result<PhiWitness, String> {
+    match serde_json::from_slice::<Value>(bytes) {
+        Ok(value) => PhiWitness::from_value(&value).map_err(|e| e.to_string()),
+        Err(_) => serde_json::from_slice(bytes).map_err(|e| e.to_string()),
+    }
+}
+
+fn asm(args: AsmArgs) {
+    let bytes = read_file(&args.input, "assembly");
+    let program = match std::str::from_utf8(&bytes)
+        .map_err(|e| format!("assembly is not UTF-8: {e}"))
+        .and_then(|text| dir_asm::parse(text).map_err(|e| e.to_string()))
+    {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("dustrun: {}: {e}", args.input);
+            std::process::exit(3);
+        }
+    };
+    let artifact = dir_format::to_json(&program, Some(&DirProducer::dustrun()));
+    let json = match serde_json::to_string_pretty(&artifact) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("dustrun: failed to serialize DIR: {e}");
+            std::process::exit(4);
+        }
+    };
+    write_output(args.output.as_deref(), &(json + "\n"));
+}
+
+fn disasm(args: AsmArgs) {
+    let bytes = read_file(&args.input, "DIR");
+    let dvm = Dvm::new(DvmConfig::default());
+    let program = load_program(&dvm, &args.input, &bytes);
+    let text = match dir_asm::print(&program) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("dustrun: {e}");
+            std::process::exit(4);
+        }
+    };
+    write_output(args.output.as_deref(), &text);
+}
+
+fn convert(args: ConvertArgs) {
+    let bytes = read_file(&args.input, "DIR");
+    let artifact = match dir_format::read(&args.input, &bytes) {
+        Ok(a) => a,
+        Err(e) => {
+            eprintln!("dustrun: DIR load error: {e}");
+            std::process::exit(3);
+        }
+    };
+    let encoding = match args.to {
+        Some(DirFormatArg::Json) => DirEncoding::Json,
+        Some(DirFormatArg::Binary) => DirEncoding::Binary,
+        Some(DirFormatArg::Asm) => DirEncoding::Asm,
+        None => DirEncoding::for_path(&args.output),
+    };
+
+    // Keep the original producer; assembly and unversioned inputs carry none.
+    let producer = artifact.producer.unwrap_or_else(DirProducer::dustrun);
+    let out = match dir_format::write(&artifact.program, Some(&producer), encoding) {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("dustrun: {e}");
+            std::process::exit(4);
+        }
+    };
+    if let Err(e) = fs::write(&args.output, &out) {
+        eprintln!("dustrun: failed to write '{}': {e}", args.output);
+        std::process::exit(2);
+    }
+    println!(
+        "{}: {} DIR, {} bytes (from {} bytes)",
+        args.output,
+        encoding.as_str(),
+        out.len(),
+        bytes.len()
+    );
+}
+
+fn link(args: LinkArgs) {
+    let dvm = Dvm::new(DvmConfig::default());
+    let program = load_programs(&dvm, &args.inputs);
+    let producer = DirProducer::dustrun();
+    let Some(path) = &args.output else {
+        let artifact = dir_format::to_json(&program, Some(&producer));
+        match serde_json::to_string_pretty(&artifact) {
+            Ok(json) => println!("{json}"),
+            Err(e) => {
+                eprintln!("dustrun: failed to serialize DIR: {e}");
+                std::process::exit(4);
+            }
+        }
+        return;
+    };
+    let encoding = DirEncoding::for_path(path);
+    let out = match dir_format::write(&program, Some(&producer), encoding) {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("dustrun: {e}");
+            std::process::exit(4);
+        }
+    };
+    if let Err(e) = fs::write(path, &out) {
+        eprintln!("dustrun: failed to write '{path}': {e}");
+        std::process::exit(2);
+    }
+    let procs: usize = program.forges.iter().map(|f| f.procs.len()).sum();
+    println!(
+        "{path}: {} DIR, {} forge(s), {procs} proc(s) from {} artifact(s)",
+        encoding.as_str(),
+        program.forges.len(),
+        args.inputs.len()
+    );
+}
+
+/// Report every static issue (`Dvm::check_dir`), with its source location when
+/// the artifact carries spans. Warnings (Q leaks) do not fail the check.
+fn check(args: CheckArgs) {
+    let dvm = Dvm::new(DvmConfig::default());
+    let program = load_programs(&dvm, &args.dir_paths);
+    let issues = dvm.check_dir(&program);
+    for issue in &issues {
+        let span = match (&issue.forge, &issue.proc) {
+            (Some(forge), Some(proc_)) => program
+                .locate_proc(&format!("{forge}::{proc_}"))
+                .and_then(|(f, p)| {
+                    let proc_ = &program.forges[f].procs[p];
+                    match issue.index {
+                        Some(index) => proc_.stmt_span(index),
+                        None => proc_.span.as_ref(),
+                    }
+                }),
+            _ => None,
+        };
+        let level = match issue.severity {
+            Severity::Error => "",
+            Severity::Warning => "warning: ",
+        };
+        match span {
+            Some(span) => eprintln!("dustrun: {level}{span}: {issue}"),
+            None => eprintln!("dustrun: {level}{issue}"),
+        }
+    }
+    let errors = issues
+        .iter()
+        .filter(|i| i.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        eprintln!("dustrun: {errors} issue(s)");
+        std::process::exit(10);
+    }
+    println!("{}: ok", args.dir_paths.join(", "));
+}
+
+/// Write a command's result to `path`, or to stdout.
+fn write_output(path: Option<&str>, contents: &str) {
+    let Some(path) = path else {
+        print!("{contents}");
+        return;
+    };
+    if let Err(e) = fs::write(path, contents) {
+        eprintln!("dustrun: failed to write '{path}': {e}");
+        std::process::exit(2);
+    }
+}
+
+fn diff(args: DiffArgs) {
+    let (left, _) = read_trace(&args.left);
+    let (right, _) = read_trace(&args.right);
+
+    let d = dust_dvm::trace::diff(&left.trace, &right.trace);
+    if d.is_empty() {
+        println!("traces are equivalent");
+        return;
+    }
+    print!("{d}");
+    std::process::exit(1);
+}
+
+fn trace_upgrade(args: TraceUpgradeArgs) {
+    let mut outdated = false;
+
+    for path in &args.paths {
+        let (upgraded, bytes) = read_trace(path);
+
+        let current = dust_dvm::trace::TRACE_SCHEMA_VERSION;
+        if !upgraded.changed() {
+            println!("{path}: up to date (schema_version {current})");
+            continue;
+        }
+        outdated = true;
+        if args.check {
+            println!("{path}: outdated (schema_version {})", upgraded.from);
+            continue;
+        }
+
+        // Keep the file's trailing-newline convention.
+        let mut out = match serde_json::to_string_pretty(&upgraded.trace) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("dustrun: failed to serialize trace: {e}");
+                std::process::exit(4);
+            }
+        };
+        if bytes.ends_with(b"\n") {
+            out.push('\n');
+        }
+        if let Err(e) = fs::write(path, out) {
+            eprintln!("dustrun: failed to write trace file '{path}': {e}");
+            std::process::exit(2);
+        }
+        println!(
+            "{path}: upgraded schema_version {} -> {current}",
+            upgraded.from
+        );
+    }
+
+    if args.check && outdated {
+        std::process::exit(1);
+    }
+}
+
+fn print_trace(trace: &DvmTrace) {
+    match serde_json::to_string_pretty(trace) {
+        Ok(s) => {
+            println!("{s}");
+        }
+        Err(e) => {
+            eprintln!("dustrun: failed to serialize trace: {e}");
+            std::process::exit(4);
+        }
+    }
+}
+
+/// Read a trace file of any supported schema version, exiting with the load failure
+/// code if it is not a valid trace. Also returns the raw bytes.
+///
+/// `.jsonl` files are streamed traces and are reassembled (current schema only).
+fn read_trace(path: &str) -> (Upgraded, Vec<u8>) {
+    let bytes = read_file(path, "trace");
+    let upgraded = if path.ends_with(".jsonl") {
+        jsonl::read(bytes.as_slice())
+            .and_then(|c| c.into_trace())
+            .map(|trace| Upgraded {
+                trace,
+                from: u64::from(dust_dvm::trace::TRACE_SCHEMA_VERSION),
+            })
+            .map_err(|e| e.to_string())
+    } else {
+        serde_json::from_slice(&bytes)
+            .map_err(|e| e.to_string())
+            .and_then(|v| upgrade_trace(v).map_err(|e| e.to_string()))
+    };
+    match upgraded {
+        Ok(u) => (u, bytes),
+        Err(e) => {
+            eprintln!("dustrun: {path}: {e}");
+            std::process::exit(3);
+        }
+    }
+}
+
+/// Load one DIR artifact, or link several (`dir::link`) into one program.
+fn load_programs(dvm: &Dvm, paths: &[String]) -> DirProgram {
+    if let [path] = paths {
+        return load_program(dvm, path, &read_file(path, "DIR"));
+    }
+    let artifacts: Vec<_> = paths
+        .iter()
+        .map(|path| {
+            (
+                path.clone(),
+                load_program(dvm, path, &read_file(path, "DIR")),
+            )
+        })
+        .collect();
+    match dir_link::link(artifacts) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("dustrun: {e}");
+            std::process::exit(3);
+        }
+    }
+}
+
+/// Load a DIR artifact (JSON, binary or assembly), exiting with the load
+/// failure code if it is malformed.
+fn load_program(dvm: &Dvm, path: &str, bytes: &[u8]) -> DirProgram {
+    match dvm.load_dir(path, bytes) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("dustrun: DIR load error: {e}");
+            std::process::exit(3);
+        }
+    }
+}
+
+/// Read an input file, exiting with the IO failure code if it cannot be read.
+fn read_file(path: &str, what: &str) -> Vec<u8> {
+    match fs::read(path) {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("dustrun: failed to read {what} file '{path}': {e}");
+            std::process::exit(2);
+        }
     }
 }
 
@@ -108,18 +654,47 @@ fn init_logging() {
     let _ = builder.try_init();
 }
 
-fn format_value(v: &dust_dvm::Value) -> String {
-    match v {
-        dust_dvm::Value::Int(n) => n.to_string(),
-        dust_dvm::Value::Bool(b) => b.to_string(),
-        dust_dvm::Value::String(s) => format!("{:?}", s),
-        dust_dvm::Value::Struct { ty, fields } => {
-            let mut parts = Vec::new();
-            for (k, vv) in fields.iter() {
-                parts.push(format!("{k}:{}", format_value(vv)));
-            }
-            format!("{ty}{{{}}}", parts.join(","))
-        }
-        dust_dvm::Value::Unit => "unit".into(),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `main` binds `w = root(2)`, a NonExistent witness, and emits it.
+    fn call_regimes_witness() -> (DirProgram, Vec<u8>) {
+        let dvm = Dvm::new(DvmConfig::default());
+        let program = dvm
+            .load_dir(
+                "call_regimes.dir.json",
+                include_bytes!("../../../tests/fixtures/dir/call_regimes.dir.json"),
+            )
+            .unwrap();
+        let outcome = dvm.run_entrypoint(&program, "main").unwrap();
+        let payload = outcome.effects.events.last().unwrap().payload.clone();
+        (program, payload.into_bytes())
+    }
+
+    #[test]
+    fn verify_witness_accepts_witness_from_k_to_phi_call() {
+        let (program, bytes) = call_regimes_witness();
+        let witness = load_witness(&bytes).unwrap();
+        assert_eq!(witness.params.get("sq"), Some(&Value::Int(2)));
+
+        let (f, p) = program.resolve_proc(None, "root").unwrap();
+        let root = &program.forges[f].procs[p];
+        assert_eq!(
+            dust_dvm::phi_verify_witness(&witness, root).unwrap(),
+            PhiVerification::Valid
+        );
+
+        // The refutation only holds for the recorded argument.
+        let mut forged = witness.clone();
+        forged.params.insert("sq".into(), Value::Int(9));
+        assert!(matches!(
+            dust_dvm::phi_verify_witness(&forged, root).unwrap(),
+            PhiVerification::Invalid { .. }
+        ));
+
+        // The native encoding loads too.
+        let native = serde_json::to_vec(&witness).unwrap();
+        assert_eq!(load_witness(&native).unwrap(), witness);
     }
 }
@@ -0,0 +1,451 @@
+// File: crates/dustrun/src/repl.rs
+//
+// `dustrun repl`: evaluate DIR statements and expressions one line at a time.
+//
+// The session is a single proc (`repl::main`) in the K or Q regime. Each
+// accepted statement is appended to its body; the body is re-executed by the
+// DVM for every new line, so bindings, `QState` and the effect log persist
+// with exactly the semantics of a DIR program. A statement that fails is not
+// kept. Bare expressions are evaluated without changing the session.
+//
+// Statement syntax (as printed by `dustrun debug`):
+//   let NAME = EXPR | constrain EXPR | prove NAME from EXPR | effect KIND EXPR
+
+use crate::debug::{resource_state, show_effects, show_q};
+use dust_dvm::dir::format::{self, DirProducer};
+use dust_dvm::dir::validate;
+use dust_dvm::{
+    DirForge, DirProc, DirProgram, DirStmt, Dvm, DvmConfig, DvmError, DvmFault, EffectLog,
+    QSnapshot, StepStatus, Value,
+};
+use std::fs;
+use std::io::{self, BufRead, Write};
+
+/// Regime of the session proc.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Regime {
+    K,
+    Q,
+}
+
+impl Regime {
+    fn as_str(self) -> &'static str {
+        match self {
+            Regime::K => "K",
+            Regime::Q => "Q",
+        }
+    }
+}
+
+/// State observed after the last accepted statement.
+#[derive(Debug, Clone, Default)]
+struct Observed {
+    env: Vec<(String, Value)>,
+    q: Option<QSnapshot>,
+    effects: EffectLog,
+}
+
+/// A REPL session.
+#[derive(Debug)]
+pub struct Repl {
+    regime: Regime,
+    body: Vec<DirStmt>,
+    state: Observed,
+}
+
+impl Repl {
+    pub fn new(regime: Regime) -> Self {
+        Self {
+            regime,
+            body: Vec::new(),
+            state: Observed::default(),
+        }
+    }
+
+    /// The session as a runnable DIR program (entry `main`).
+    pub fn program(&self) -> DirProgram {
+        self.program_with(&[])
+    }
+
+    fn program_with(&self, extra: &[DirStmt]) -> DirProgram {
+        DirProgram {
+            forges: vec![DirForge {
+                name: "repl".into(),
+                shapes: Vec::new(),
+                procs: vec![DirProc {
+                    regime: self.regime.as_str().into(),
+                    name: "main".into(),
+                    params: Vec::new(),
+                    uses: Vec::new(),
+                    ret: None,
+                    qualifiers: Vec::new(),
+                    body: self.body.iter().chain(extra).cloned().collect(),
+                    span: None,
+                    body_spans: Vec::new(),
+                }],
+                binds: Vec::new(),
+            }],
+        }
+    }
+
+    /// Run the session body followed by `stmt`, returning the state after it.
+    fn execute(&self, stmt: &DirStmt) -> Result<Observed, Box<DvmFault>> {
+        // The trailing statement is never executed; it keeps the frame (and
+        // its environment) alive after `stmt`.
+        let hold = DirStmt::Return { expr: "0".into() };
+        let program = self.program_with(&[stmt.clone(), hold]);
+
+        // Only the new line can be at fault; its location in the session
+        // program means nothing to the user.
+        let issues = validate::check(&program);
+        if !issues.is_empty() {
+            let messages: Vec<_> = issues.into_iter().map(|i| i.message).collect();
+            return Err(Box::new(DvmError::DirValidate(messages.join("; ")).into()));
+        }
+        let dvm = Dvm::new(DvmConfig::default());
+        let mut stepper = dvm.stepper(&program, "main").map_err(Box::new)?;
+        for _ in 0..=self.body.len() {
+            if stepper.step().map_err(Box::new)? == StepStatus::Finished {
+                break;
+            }
+        }
+        let env = stepper
+            .env()
+            .map(|env| env.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+            .unwrap_or_default();
+        Ok(Observed {
+            env,
+            q: (self.regime == Regime::Q).then(|| stepper.q()),
+            effects: stepper.effects().clone(),
+        })
+    }
+
+    /// Evaluate one line of input.
+    fn eval(&mut self, line: &str, out: &mut impl Write) -> io::Result<()> {
+        let (stmt, keep) = match parse_line(line) {
+            Ok(Some(stmt)) => (stmt, true),
+            Ok(None) => (
+                DirStmt::Let {
+                    name: "_".into(),
+                    expr: line.to_string(),
+                },
+                false,
+            ),
+            Err(e) => return writeln!(out, "error: {e}"),
+        };
+
+        let observed = match self.execute(&stmt) {
+            Ok(o) => o,
+            Err(fault) => {
+                writeln!(out, "error: {}", fault.error)?;
+                if let Some(x) = &fault.explanation {
+                    writeln!(out, "explanation:")?;
+                    for line in x.to_string().lines() {
+                        writeln!(out, "  {line}")?;
+                    }
+                }
+                return Ok(());
+            }
+        };
+
+        match &stmt {
+            DirStmt::Let { name, .. } | DirStmt::Prove { name, .. } => {
+                let shown = if keep { name.as_str() } else { "" };
+                show_binding(&observed, name, shown, out)?;
+            }
+            DirStmt::Constrain { .. } => writeln!(out, "ok")?,
+            _ => {}
+        }
+        for e in &observed.effects.events[self.state.effects.events.len()..] {
+            writeln!(out, "effect {} {}", e.kind, e.payload)?;
+        }
+
+        if keep {
+            self.body.push(stmt);
+            self.state = observed;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a statement; `Ok(None)` if the line is an expression.
+fn parse_line(line: &str) -> Result<Option<DirStmt>, String> {
+    let line = line.trim();
+    let (word, rest) = line
+        .split_once(char::is_whitespace)
+        .map_or((line, ""), |(w, r)| (w, r.trim()));
+
+    let stmt = match word {
+        "let" => {
+            let (name, expr) = rest.split_once('=').ok_or("expected `let NAME = EXPR`")?;
+            DirStmt::Let {
+                name: ident(name.trim())?,
+                expr: nonempty(expr, "let")?,
+            }
+        }
+        "constrain" => DirStmt::Constrain {
+            predicate: nonempty(rest, "constrain")?,
+        },
+        "prove" => {
+            let (name, from) = rest
+                .split_once(" from ")
+                .ok_or("expected `prove NAME from EXPR`")?;
+            DirStmt::Prove {
+                name: ident(name.trim())?,
+                from: nonempty(from, "prove")?,
+            }
+        }
+        "effect" => {
+            let (kind, payload) = rest
+                .split_once(char::is_whitespace)
+                .ok_or("expected `effect KIND EXPR`")?;
+            DirStmt::Effect {
+                kind: kind.to_string(),
+                payload: nonempty(payload, "effect")?,
+            }
+        }
+        "return" | "call" => return Err(format!("`{word}` is not available in the REPL")),
+        _ => return Ok(None),
+    };
+    Ok(Some(stmt))
+}
+
+fn ident(name: &str) -> Result<String, String> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_');
+    if valid {
+        Ok(name.to_string())
+    } else {
+        Err(format!("invalid binding name '{name}'"))
+    }
+}
+
+fn nonempty(expr: &str, what: &str) -> Result<String, String> {
+    match expr.trim() {
+        "" => Err(format!("`{what}` needs an expression")),
+        e => Ok(e.to_string()),
+    }
+}
+
+/// Print `label = value` for `name` (`label` empty for bare expressions); Q
+/// bindings print their resource and its state.
+fn show_binding(
+    observed: &Observed,
+    name: &str,
+    label: &str,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let prefix = if label.is_empty() {
+        String::new()
+    } else {
+        format!("{label} = ")
+    };
+    if let Some(q) = &observed.q {
+        if let Some(b) = q.env.get(name) {
+            let (ty, state) = q.resources.get(&b.res).map_or(("?", "missing"), |m| {
+                (m.ty.as_str(), resource_state(&m.state))
+            });
+            let moved = if b.moved { ", moved" } else { "" };
+            return writeln!(out, "{prefix}{} ({ty}, {state}{moved})", b.res.0);
+        }
+    }
+    match observed.env.iter().find(|(n, _)| n == name) {
+        Some((_, v)) => writeln!(out, "{prefix}{v}"),
+        None => writeln!(out, "{prefix}<unbound>"),
+    }
+}
+
+const HELP: &str = "\
+statements (kept in the session):
+  let NAME = EXPR          bind a value (or a Q intrinsic in Q mode)
+  constrain EXPR           check a predicate
+  prove NAME from EXPR     check a predicate and bind its proof
+  effect KIND EXPR         emit an effect
+any other line is an expression, evaluated without changing the session
+commands:
+  :env                     show bindings
+  :qstate                  show Q bindings and resources (Q mode)
+  :effects                 show the effect log
+  :regime K|Q              start a new session in another regime
+  :reset                   start a new session
+  :dump [FILE]             print (or write) the session as a DIR program
+  :help                    show this help
+  :quit                    end the session
+";
+
+/// Run a REPL session: one line of `input` at a time, output to `out`.
+///
+/// With `prompt`, a `K> ` / `Q> ` prompt precedes each line (interactive use).
+pub fn session(
+    repl: &mut Repl,
+    input: impl BufRead,
+    mut out: impl Write,
+    prompt: bool,
+) -> io::Result<()> {
+    let mut lines = input.lines();
+    loop {
+        if prompt {
+            write!(out, "{}> ", repl.regime.as_str())?;
+            out.flush()?;
+        }
+        let Some(line) = lines.next().transpose()? else {
+            break;
+        };
+        let line = line.trim();
+        let Some(command) = line.strip_prefix(':') else {
+            if !line.is_empty() {
+                repl.eval(line, &mut out)?;
+            }
+            continue;
+        };
+
+        let (cmd, arg) = command
+            .split_once(char::is_whitespace)
+            .map_or((command, ""), |(c, a)| (c, a.trim()));
+        match cmd {
+            "quit" | "q" => break,
+            "help" | "h" => write!(out, "{HELP}")?,
+            "env" => {
+                if repl.state.env.is_empty() {
+                    writeln!(out, "no bindings")?;
+                }
+                for (name, _) in &repl.state.env {
+                    show_binding(&repl.state, name, name, &mut out)?;
+                }
+            }
+            "qstate" => match &repl.state.q {
+                Some(q) => show_q(q, &mut out)?,
+                None if repl.regime == Regime::Q => {
+                    writeln!(out, "q bindings: <none>")?;
+                    writeln!(out, "q resources: <none>")?;
+                }
+                None => writeln!(out, "error: no QState in K mode (try :regime Q)")?,
+            },
+            "effects" => show_effects(&repl.state.effects, &mut out)?,
+            "reset" => {
+                *repl = Repl::new(repl.regime);
+                writeln!(out, "new {} session", repl.regime.as_str())?;
+            }
+            "regime" => match arg.to_ascii_uppercase().as_str() {
+                "K" => {
+                    *repl = Repl::new(Regime::K);
+                    writeln!(out, "new K session")?;
+                }
+                "Q" => {
+                    *repl = Repl::new(Regime::Q);
+                    writeln!(out, "new Q session")?;
+                }
+                _ => writeln!(out, "error: expected `:regime K` or `:regime Q`")?,
+            },
+            "dump" => {
+                let artifact = format::to_json(&repl.program(), Some(&DirProducer::dustrun()));
+                let json = serde_json::to_string_pretty(&artifact).map_err(io::Error::other)?;
+                if arg.is_empty() {
+                    writeln!(out, "{json}")?;
+                } else {
+                    match fs::write(arg, json + "\n") {
+                        Ok(()) => writeln!(out, "wrote {arg}")?,
+                        Err(e) => writeln!(out, "error: failed to write '{arg}': {e}")?,
+                    }
+                }
+            }
+            other => writeln!(out, "error: unknown command ':{other}' (try :help)")?,
+        }
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn transcript(regime: Regime, input: &str) -> (String, Repl) {
+        let mut repl = Repl::new(regime);
+        let mut out = Vec::new();
+        session(&mut repl, input.as_bytes(), &mut out, false).unwrap();
+        (String::from_utf8(out).unwrap(), repl)
+    }
+
+    #[test]
+    fn k_session_keeps_bindings_and_effects() {
+        let (out, repl) = transcript(
+            Regime::K,
+            "\
+let x = 2 Add 3
+x Mul 2
+let y = x Gt 4
+constrain x Lt 3
+effect emit x
+let bad = nope
+:env
+:effects
+",
+        );
+        assert_eq!(
+            out,
+            "\
+x = 5
+10
+y = true
+error: inadmissible program: constraint failed: x Lt 3
+explanation:
+  statement 2: x Lt 3
+    x Lt 3 = false
+      x = 5
+    x = 5 (let at 0: 2 Add 3)
+effect emit 5
+error: DIR validation error: unbound identifier 'nope'
+x = 5
+y = true
+effects:
+  1. emit 5
+"
+        );
+        assert_eq!(repl.body.len(), 3);
+    }
+
+    #[test]
+    fn q_session_tracks_linear_state_and_dumps_a_program() {
+        let (out, repl) = transcript(
+            Regime::Q,
+            "\
+let a = q_alloc(QBit)
+let b = q_move(a)
+let c = q_use(a)
+:qstate
+",
+        );
+        assert_eq!(
+            out,
+            "\
+a = qres:a:1 (QBit, live)
+b = qres:a:1 (QBit, live)
+error: inadmissible program: Q use failed: binding already moved: a (op=q_use)
+q bindings:
+  a -> qres:a:1 (moved)
+  b -> qres:a:1
+q resources:
+  qres:a:1 QBit live
+"
+        );
+
+        // The dumped session runs to the same state.
+        let program = repl.program();
+        let dvm = Dvm::new(DvmConfig::default());
+        let outcome = dvm.run_entrypoint_with_fault(&program, "main").unwrap();
+        assert_eq!(outcome.time.tick.0, 2);
+    }
+
+    #[test]
+    fn statement_syntax() {
+        assert!(matches!(
+            parse_line("prove p from x Gt 1"),
+            Ok(Some(DirStmt::Prove { .. }))
+        ));
+        assert!(matches!(parse_line("1 Add 2"), Ok(None)));
+        assert!(parse_line("let 1x = 2").is_err());
+        assert!(parse_line("return x").is_err());
+    }
+}
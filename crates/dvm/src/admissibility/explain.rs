@@ -0,0 +1,330 @@
+//! Explanations for failed constraint predicates.
+//!
+//! `explain` re-evaluates a predicate's AST under the bindings in scope at the
+//! failing statement and records:
+//! - the value (or evaluation error) of every subexpression,
+//! - which conjuncts of a top-level `And` chain did not hold,
+//! - where each referenced variable was bound (`Let`, `Prove`, `Call`, or parameter).
+//!
+//! Explanations are diagnostic only: they never change the outcome of execution.
+
+use crate::{
+    dir::DirStmt,
+    expr::{self, BinOp, Expr},
+    DirProc, Value,
+};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Structured account of why a `Constrain` / `Prove` predicate did not hold.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Explanation {
+    /// Body index of the failing statement.
+    pub index: usize,
+
+    /// The predicate as written in DIR.
+    pub predicate: String,
+
+    /// Evaluation tree of the predicate.
+    pub tree: ExplainNode,
+
+    /// Conjuncts of a top-level `And` chain that did not evaluate to true.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed_conjuncts: Vec<String>,
+
+    /// Variables referenced by the predicate, in first-use order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bindings: Vec<ExplainBinding>,
+}
+
+/// One evaluated subexpression.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExplainNode {
+    pub expr: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ExplainNode>,
+}
+
+/// A variable referenced by the predicate and where it came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExplainBinding {
+    pub name: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+
+    pub origin: BindingOrigin,
+}
+
+/// The statement (or parameter) that bound a variable.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BindingOrigin {
+    Let { index: usize, expr: String },
+    Prove { index: usize },
+    Call { index: usize, callee: String },
+    Param,
+    Unbound,
+}
+
+/// Explain the failure of `predicate` at body index `index` of `proc_`, under `env`
+/// (the bindings in scope when the statement ran).
+pub fn explain(
+    proc_: &DirProc,
+    index: usize,
+    predicate: &str,
+    env: &IndexMap<String, Value>,
+) -> Explanation {
+    let parsed = match expr::parse(predicate) {
+        Ok(e) => e,
+        Err(e) => {
+            return Explanation {
+                index,
+                predicate: predicate.to_string(),
+                tree: ExplainNode {
+                    expr: predicate.trim().to_string(),
+                    value: None,
+                    error: Some(e.to_string()),
+                    children: Vec::new(),
+                },
+                failed_conjuncts: Vec::new(),
+                bindings: Vec::new(),
+            };
+        }
+    };
+
+    let tree = eval_node(&parsed, env);
+
+    let mut conjuncts = Vec::new();
+    if matches!(parsed, Expr::Binary { op: BinOp::And, .. }) {
+        flatten_and(&parsed, &mut conjuncts);
+    }
+    let failed_conjuncts = conjuncts
+        .into_iter()
+        .filter(|c| eval_node(c, env).value != Some(Value::Bool(true)))
+        .map(|c| c.to_string())
+        .collect();
+
+    let bindings = parsed
+        .idents()
+        .into_iter()
+        .map(|name| ExplainBinding {
+            value: env.get(name).cloned(),
+            origin: origin_of(proc_, index, name),
+            name: name.to_string(),
+        })
+        .collect();
+
+    Explanation {
+        index,
+        predicate: predicate.to_string(),
+        tree,
+        failed_conjuncts,
+        bindings,
+    }
+}
+
+fn eval_node(e: &Expr, env: &IndexMap<String, Value>) -> ExplainNode {
+    let (value, error, children) = match e {
+        Expr::Binary { op, lhs, rhs } => {
+            let l = eval_node(lhs, env);
+            let r = eval_node(rhs, env);
+            let res = match (&l.value, &r.value) {
+                (Some(a), Some(b)) => expr::apply(*op, a, b).map_err(|e| e.to_string()),
+                _ => Err("operand could not be evaluated".to_string()),
+            };
+            let (value, error) = split(res);
+            (value, error, vec![l, r])
+        }
+        _ => {
+            let (value, error) = split(expr::eval_expr(e, env).map_err(|e| e.to_string()));
+            (value, error, Vec::new())
+        }
+    };
+    ExplainNode {
+        expr: e.to_string(),
+        value,
+        error,
+        children,
+    }
+}
+
+fn split(res: Result<Value, String>) -> (Option<Value>, Option<String>) {
+    match res {
+        Ok(v) => (Some(v), None),
+        Err(e) => (None, Some(e)),
+    }
+}
+
+fn flatten_and<'a>(e: &'a Expr, out: &mut Vec<&'a Expr>) {
+    match e {
+        Expr::Binary {
+            op: BinOp::And,
+            lhs,
+            rhs,
+        } => {
+            flatten_and(lhs, out);
+            flatten_and(rhs, out);
+        }
+        other => out.push(other),
+    }
+}
+
+/// The last statement before `index` that binds `name`, else a parameter.
+fn origin_of(proc_: &DirProc, index: usize, name: &str) -> BindingOrigin {
+    for (i, stmt) in proc_.body.iter().enumerate().take(index).rev() {
+        match stmt {
+            DirStmt::Let { name: n, expr } if n == name => {
+                return BindingOrigin::Let {
+                    index: i,
+                    expr: expr.clone(),
+                };
+            }
+            DirStmt::Prove { name: n, .. } if n == name => {
+                return BindingOrigin::Prove { index: i };
+            }
+            DirStmt::Call {
+                name: Some(n),
+                callee,
+                ..
+            } if n == name => {
+                return BindingOrigin::Call {
+                    index: i,
+                    callee: callee.clone(),
+                };
+            }
+            _ => {}
+        }
+    }
+    if proc_.params.iter().any(|p| p.name == name) {
+        BindingOrigin::Param
+    } else {
+        BindingOrigin::Unbound
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "statement {}: {}", self.index, self.predicate.trim())?;
+        write_node(f, &self.tree, 1)?;
+        for c in &self.failed_conjuncts {
+            writeln!(f, "  failed conjunct: {c}")?;
+        }
+        for b in &self.bindings {
+            let value = match &b.value {
+                Some(v) => v.to_string(),
+                None => "<unbound>".into(),
+            };
+            match &b.origin {
+                BindingOrigin::Let { index, expr } => {
+                    writeln!(f, "  {} = {value} (let at {index}: {expr})", b.name)?
+                }
+                BindingOrigin::Prove { index } => {
+                    writeln!(f, "  {} = {value} (prove at {index})", b.name)?
+                }
+                BindingOrigin::Call { index, callee } => {
+                    writeln!(f, "  {} = {value} (call at {index}: {callee})", b.name)?
+                }
+                BindingOrigin::Param => writeln!(f, "  {} = {value} (param)", b.name)?,
+                BindingOrigin::Unbound => writeln!(f, "  {} is unbound", b.name)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_node(f: &mut fmt::Formatter<'_>, node: &ExplainNode, depth: usize) -> fmt::Result {
+    let pad = "  ".repeat(depth);
+    match (&node.value, &node.error) {
+        (Some(v), _) => writeln!(f, "{pad}{} = {v}", node.expr)?,
+        (None, Some(e)) => writeln!(f, "{pad}{} : {e}", node.expr)?,
+        (None, None) => writeln!(f, "{pad}{}", node.expr)?,
+    }
+    for child in &node.children {
+        // Literals evaluate to themselves; only show computed subexpressions.
+        if !expr::parse(&child.expr).is_ok_and(|e| e.is_literal()) {
+            write_node(f, child, depth + 1)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proc_with(body: Vec<DirStmt>) -> DirProc {
+        DirProc {
+            regime: "K".into(),
+            name: "main".into(),
+            params: vec![],
+            uses: vec![],
+            ret: None,
+            qualifiers: vec![],
+            body,
+            span: None,
+            body_spans: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn reports_subexpression_values_conjuncts_and_origins() {
+        let body = vec![
+            DirStmt::Let {
+                name: "x".into(),
+                expr: "2 Add 3".into(),
+            },
+            DirStmt::Let {
+                name: "y".into(),
+                expr: "1".into(),
+            },
+            DirStmt::Constrain {
+                predicate: "y Eq 1 And x Lt 3".into(),
+            },
+        ];
+        let proc_ = proc_with(body);
+        let mut env = IndexMap::new();
+        env.insert("x".to_string(), Value::Int(5));
+        env.insert("y".to_string(), Value::Int(1));
+
+        let ex = explain(&proc_, 2, "y Eq 1 And x Lt 3", &env);
+        assert_eq!(ex.tree.value, Some(Value::Bool(false)));
+        assert_eq!(ex.tree.children[1].expr, "x Lt 3");
+        assert_eq!(ex.tree.children[1].children[0].value, Some(Value::Int(5)));
+        assert_eq!(ex.failed_conjuncts, vec!["x Lt 3".to_string()]);
+        assert_eq!(ex.bindings[0].name, "y");
+        assert_eq!(
+            ex.bindings[1].origin,
+            BindingOrigin::Let {
+                index: 0,
+                expr: "2 Add 3".into()
+            }
+        );
+
+        let text = ex.to_string();
+        assert!(text.contains("x Lt 3 = false"));
+        assert!(text.contains("x = 5 (let at 0: 2 Add 3)"));
+    }
+
+    #[test]
+    fn evaluation_errors_are_reported_per_node() {
+        let proc_ = proc_with(vec![]);
+        let ex = explain(&proc_, 0, "z Lt 3", &IndexMap::new());
+        assert!(ex.tree.value.is_none());
+        assert_eq!(
+            ex.tree.children[0].error.as_deref(),
+            Some("runtime error: unknown identifier: z")
+        );
+        assert_eq!(ex.bindings[0].origin, BindingOrigin::Unbound);
+    }
+}
@@ -0,0 +1,25 @@
+//! v0.1 admissibility model:
+//! - Constrain predicates must evaluate to true in evaluation context over classical env.
+//! - Φ-regime host-mode semantics will evolve to match the spec.
+//! - Failed predicates can be re-evaluated into a structured `Explanation`.
+
+pub mod explain;
+
+pub use explain::{explain, BindingOrigin, ExplainBinding, ExplainNode, Explanation};
+
+use crate::{expr, DvmError, Value};
+use indexmap::IndexMap;
+
+pub fn check_predicate(predicate: &str, env: &IndexMap<String, Value>) -> Result<(), DvmError> {
+    let v = expr::eval(predicate, env)?;
+    let ok = v.as_bool().ok_or_else(|| {
+        DvmError::ConstraintFailure("constraint predicate did not evaluate to bool".into())
+    })?;
+    if ok {
+        Ok(())
+    } else {
+        Err(DvmError::Inadmissible(format!(
+            "constraint failed: {predicate}"
+        )))
+    }
+}
@@ -0,0 +1,852 @@
+//! DIR assembly: a line-oriented text form of `DirProgram`.
+//!
+//! JSON stays the canonical DIR encoding; assembly (`*.dasm`) is for writing and
+//! reading programs by hand. `parse` produces a `DirProgram` and `print` writes
+//! one back, so `parse(&print(p)?)` reproduces `p`.
+//!
+//! ```text
+//! // Comments take a whole line.
+//! forge core {
+//!   shape Point {
+//!     x: Int
+//!   }
+//!
+//!   proc K main(a: Int) -> Int {
+//!     uses Clock(rate: 1, label: "fast")
+//!     qualifier pure
+//!     let n = a Add 1
+//!     constrain n Gt 0
+//!     prove p from n Gt 0
+//!     effect emit "Q ok"
+//!     call m = measure(n, 2)
+//!     return m
+//!   }
+//!
+//!   bind core -> io {
+//!     rate Le 10
+//!   }
+//!
+//!   @ main.ds:12:1
+//!   proc Q measure() -> Int {
+//!     @ main.ds:13:5
+//!     return 7
+//!   }
+//! }
+//! ```
+//!
+//! Expressions are written verbatim to the end of the line (no escaping), so
+//! they must fit on one line; surrounding whitespace is not preserved. An
+//! `@ FILE:LINE:COLUMN` line gives the source span of the proc or statement on
+//! the next line.
+
+use super::{
+    DirBind, DirClause, DirField, DirForge, DirLit, DirParam, DirProc, DirProgram, DirShape,
+    DirSpan, DirStmt, DirUses,
+};
+use crate::DvmError;
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// File extension of DIR assembly files.
+pub const EXTENSION: &str = "dasm";
+
+/// Whether `path` names a DIR assembly file (by extension).
+pub fn is_asm_path(path: impl AsRef<Path>) -> bool {
+    path.as_ref()
+        .extension()
+        .is_some_and(|ext| ext == EXTENSION)
+}
+
+/// The block being parsed inside a forge.
+enum Item {
+    Shape(DirShape),
+    Proc(DirProc),
+    Bind(DirBind),
+}
+
+/// Parse DIR assembly into a program.
+pub fn parse(text: &str) -> Result<DirProgram, DvmError> {
+    let mut forges = Vec::new();
+    let mut forge: Option<DirForge> = None;
+    let mut item: Option<Item> = None;
+    let mut span: Option<DirSpan> = None;
+
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("//") {
+            continue;
+        }
+        let at = |msg: String| DvmError::DirLoad(format!("line {}: {msg}", n + 1));
+
+        let Some(current) = forge.as_mut() else {
+            let name = block(line, "forge")
+                .ok_or_else(|| at(format!("expected `forge NAME {{`, found `{line}`")))?;
+            forge = Some(DirForge {
+                name: word(name, "forge name").map_err(at)?,
+                shapes: Vec::new(),
+                procs: Vec::new(),
+                binds: Vec::new(),
+            });
+            continue;
+        };
+
+        if let Some(rest) = line.strip_prefix('@') {
+            if span.is_some() {
+                return Err(at("two spans in a row".into()));
+            }
+            span = Some(parse_span(rest.trim()).map_err(at)?);
+            continue;
+        }
+
+        if line == "}" {
+            match item.take() {
+                Some(Item::Shape(shape)) => current.shapes.push(shape),
+                Some(Item::Proc(mut proc_)) => {
+                    if proc_.body_spans.iter().all(Option::is_none) {
+                        proc_.body_spans.clear();
+                    }
+                    current.procs.push(proc_)
+                }
+                Some(Item::Bind(bind)) => current.binds.push(bind),
+                None => forges.extend(forge.take()),
+            }
+        } else {
+            match item.as_mut() {
+                None => {
+                    let mut new = parse_item(line).map_err(at)?;
+                    if let Item::Proc(proc_) = &mut new {
+                        proc_.span = span.take();
+                    }
+                    item = Some(new);
+                }
+                Some(Item::Shape(shape)) => shape.fields.push(parse_field(line).map_err(at)?),
+                Some(Item::Proc(proc_)) => parse_proc_line(proc_, line, &mut span).map_err(at)?,
+                Some(Item::Bind(bind)) => bind.contract.push(parse_clause(line).map_err(at)?),
+            }
+        }
+        if span.is_some() {
+            return Err(at("a span must be followed by a proc or a statement".into()));
+        }
+    }
+
+    if let Some(forge) = forge {
+        let open = match item {
+            Some(Item::Shape(s)) => format!("shape '{}'", s.name),
+            Some(Item::Proc(p)) => format!("proc '{}'", p.name),
+            Some(Item::Bind(b)) => format!("bind '{} -> {}'", b.source, b.target),
+            None => format!("forge '{}'", forge.name),
+        };
+        return Err(DvmError::DirLoad(format!(
+            "unexpected end of input: {open} is not closed"
+        )));
+    }
+    Ok(DirProgram { forges })
+}
+
+/// The header of a `KEYWORD ... {` line, without the keyword and brace.
+fn block<'a>(line: &'a str, keyword: &str) -> Option<&'a str> {
+    let rest = line.strip_prefix(keyword)?;
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(rest.strip_suffix('{')?.trim())
+}
+
+fn parse_item(line: &str) -> Result<Item, String> {
+    if let Some(name) = block(line, "shape") {
+        return Ok(Item::Shape(DirShape {
+            name: word(name, "shape name")?,
+            fields: Vec::new(),
+        }));
+    }
+    if let Some(header) = block(line, "proc") {
+        return parse_proc_header(header).map(Item::Proc);
+    }
+    if let Some(header) = block(line, "bind") {
+        let (source, target) = header
+            .split_once(" -> ")
+            .ok_or("expected `bind SOURCE -> TARGET {`")?;
+        return Ok(Item::Bind(DirBind {
+            source: word(source.trim(), "bind source")?,
+            target: word(target.trim(), "bind target")?,
+            contract: Vec::new(),
+        }));
+    }
+    Err(format!(
+        "expected `shape`, `proc`, `bind` or `}}`, found `{line}`"
+    ))
+}
+
+/// `REGIME NAME(PARAMS) [-> RET]`
+fn parse_proc_header(header: &str) -> Result<DirProc, String> {
+    const EXPECTED: &str = "expected `proc REGIME NAME(PARAMS) [-> RET] {`";
+    let (regime, rest) = header.split_once(char::is_whitespace).ok_or(EXPECTED)?;
+    let (name, rest) = rest.trim().split_once('(').ok_or(EXPECTED)?;
+    let close = closing_paren(rest).ok_or(EXPECTED)?;
+    let params = split_list(&rest[..close])
+        .into_iter()
+        .map(|param| {
+            let (name, ty) = typed(param, "param")?;
+            Ok(DirParam { name, ty })
+        })
+        .collect::<Result<_, String>>()?;
+    let ret = match rest[close + 1..].trim() {
+        "" => None,
+        tail => {
+            let ty = tail.strip_prefix("->").ok_or(EXPECTED)?.trim();
+            if ty.is_empty() {
+                return Err("expected a return type after `->`".into());
+            }
+            Some(ty.to_string())
+        }
+    };
+    Ok(DirProc {
+        regime: word(regime, "regime")?,
+        name: word(name.trim(), "proc name")?,
+        params,
+        uses: Vec::new(),
+        ret,
+        qualifiers: Vec::new(),
+        body: Vec::new(),
+        span: None,
+        body_spans: Vec::new(),
+    })
+}
+
+fn parse_field(line: &str) -> Result<DirField, String> {
+    let (name, ty) = typed(line, "field")?;
+    Ok(DirField { name, ty })
+}
+
+/// `NAME: TYPE`
+fn typed(text: &str, what: &str) -> Result<(String, String), String> {
+    let (name, ty) = text
+        .split_once(':')
+        .ok_or_else(|| format!("expected `NAME: TYPE` for {what}, found `{text}`"))?;
+    let ty = ty.trim();
+    if ty.is_empty() {
+        return Err(format!("{what} '{}' has no type", name.trim()));
+    }
+    Ok((word(name.trim(), &format!("{what} name"))?, ty.to_string()))
+}
+
+/// A `uses` / `qualifier` line or a statement (which takes the pending `span`).
+fn parse_proc_line(
+    proc_: &mut DirProc,
+    line: &str,
+    span: &mut Option<DirSpan>,
+) -> Result<(), String> {
+    let (keyword, rest) = line
+        .split_once(char::is_whitespace)
+        .map_or((line, ""), |(k, r)| (k, r.trim()));
+    match keyword {
+        "uses" => proc_.uses.push(parse_uses(rest)?),
+        "qualifier" => proc_.qualifiers.push(word(rest, "qualifier")?),
+        _ => {
+            proc_.body.push(parse_stmt(keyword, rest)?);
+            proc_.body_spans.push(span.take());
+        }
+    }
+    Ok(())
+}
+
+/// `FILE:LINE:COLUMN` (the file name may itself contain `:`).
+fn parse_span(text: &str) -> Result<DirSpan, String> {
+    let invalid = || format!("expected `@ FILE:LINE:COLUMN`, found `@ {text}`");
+    let mut parts = text.rsplitn(3, ':');
+    let (Some(column), Some(line), Some(file)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(invalid());
+    };
+    if file.is_empty() {
+        return Err(invalid());
+    }
+    Ok(DirSpan {
+        file: file.to_string(),
+        line: line.parse().map_err(|_| invalid())?,
+        column: column.parse().map_err(|_| invalid())?,
+    })
+}
+
+/// `RESOURCE[(KEY: LIT, ...)]`
+fn parse_uses(text: &str) -> Result<DirUses, String> {
+    let Some((resource, rest)) = text.split_once('(') else {
+        return Ok(DirUses {
+            resource: word(text, "resource")?,
+            args: Vec::new(),
+        });
+    };
+    let close = closing_paren(rest)
+        .filter(|&close| rest[close + 1..].trim().is_empty())
+        .ok_or("expected `uses RESOURCE(KEY: VALUE, ...)`")?;
+    let args = split_list(&rest[..close])
+        .into_iter()
+        .map(|arg| {
+            let (key, lit) = arg
+                .split_once(':')
+                .ok_or_else(|| format!("expected `KEY: VALUE`, found `{arg}`"))?;
+            Ok((word(key.trim(), "uses key")?, parse_lit(lit.trim())?))
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(DirUses {
+        resource: word(resource.trim(), "resource")?,
+        args,
+    })
+}
+
+fn parse_lit(text: &str) -> Result<DirLit, String> {
+    if text.starts_with('"') {
+        return serde_json::from_str(text)
+            .map(DirLit::String)
+            .map_err(|e| format!("invalid string literal {text}: {e}"));
+    }
+    match text {
+        "true" => Ok(DirLit::Bool(true)),
+        "false" => Ok(DirLit::Bool(false)),
+        _ => text
+            .parse()
+            .map(DirLit::Int)
+            .map_err(|_| format!("expected an integer, boolean or string literal, found `{text}`")),
+    }
+}
+
+fn parse_stmt(keyword: &str, rest: &str) -> Result<DirStmt, String> {
+    let stmt = match keyword {
+        "let" => {
+            let (name, expr) = rest.split_once('=').ok_or("expected `let NAME = EXPR`")?;
+            DirStmt::Let {
+                name: word(name.trim(), "binding name")?,
+                expr: expr.trim().to_string(),
+            }
+        }
+        "constrain" => DirStmt::Constrain {
+            predicate: rest.to_string(),
+        },
+        "prove" => {
+            let (name, tail) = rest
+                .split_once(char::is_whitespace)
+                .ok_or("expected `prove NAME from EXPR`")?;
+            let from = tail
+                .trim_start()
+                .strip_prefix("from")
+                .filter(|from| from.is_empty() || from.starts_with(char::is_whitespace))
+                .ok_or("expected `prove NAME from EXPR`")?;
+            DirStmt::Prove {
+                name: word(name, "binding name")?,
+                from: from.trim().to_string(),
+            }
+        }
+        "effect" => {
+            let (kind, payload) = rest
+                .split_once(char::is_whitespace)
+                .map_or((rest, ""), |(k, p)| (k, p.trim()));
+            DirStmt::Effect {
+                kind: word(kind, "effect kind")?,
+                payload: payload.to_string(),
+            }
+        }
+        "return" => DirStmt::Return {
+            expr: rest.to_string(),
+        },
+        "call" => {
+            const EXPECTED: &str = "expected `call [NAME =] PROC(ARGS)`";
+            let (target, args) = rest.split_once('(').ok_or(EXPECTED)?;
+            let close = closing_paren(args)
+                .filter(|&close| args[close + 1..].trim().is_empty())
+                .ok_or(EXPECTED)?;
+            let (name, callee) = match target.split_once('=') {
+                Some((name, callee)) => (Some(word(name.trim(), "binding name")?), callee),
+                None => (None, target),
+            };
+            DirStmt::Call {
+                name,
+                callee: callee_name(callee.trim())?,
+                args: split_list(&args[..close])
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect(),
+            }
+        }
+        _ => {
+            return Err(format!(
+                "expected `uses`, `qualifier`, a statement or `}}`, found `{keyword}`"
+            ))
+        }
+    };
+    Ok(stmt)
+}
+
+fn parse_clause(line: &str) -> Result<DirClause, String> {
+    let mut parts = line.splitn(3, char::is_whitespace);
+    let (Some(key), Some(op)) = (parts.next(), parts.next()) else {
+        return Err(format!("expected `KEY OP VALUE`, found `{line}`"));
+    };
+    Ok(DirClause {
+        key: key.to_string(),
+        op: op.to_string(),
+        value: parts.next().unwrap_or_default().trim().to_string(),
+    })
+}
+
+/// Position of the `)` closing a list that starts right after an `(`.
+fn closing_paren(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut scan = Scan::default();
+    for (i, c) in text.char_indices() {
+        if !scan.code(c) {
+            continue;
+        }
+        match c {
+            '(' | '[' => depth += 1,
+            ')' if depth == 0 => return Some(i),
+            ')' | ']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split a comma-separated list at top level (outside brackets and strings).
+fn split_list(text: &str) -> Vec<&str> {
+    if text.trim().is_empty() {
+        return Vec::new();
+    }
+    let mut items = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut scan = Scan::default();
+    for (i, c) in text.char_indices() {
+        if !scan.code(c) {
+            continue;
+        }
+        match c {
+            '(' | '[' | '<' => depth += 1,
+            ')' | ']' | '>' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                items.push(text[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(text[start..].trim());
+    items
+}
+
+/// Tracks string literals while scanning expression text.
+#[derive(Default)]
+struct Scan {
+    in_string: bool,
+    escaped: bool,
+}
+
+impl Scan {
+    /// Whether `c` is outside a string literal (quotes themselves count as inside).
+    fn code(&mut self, c: char) -> bool {
+        if self.in_string {
+            match c {
+                _ if self.escaped => self.escaped = false,
+                '\\' => self.escaped = true,
+                '"' => self.in_string = false,
+                _ => {}
+            }
+            return false;
+        }
+        if c == '"' {
+            self.in_string = true;
+            return false;
+        }
+        true
+    }
+}
+
+/// A name: non-empty and free of whitespace and assembly punctuation.
+fn word(text: &str, what: &str) -> Result<String, String> {
+    let valid = !text.is_empty()
+        && !text
+            .chars()
+            .any(|c| c.is_whitespace() || "(){},:=\"".contains(c));
+    if valid {
+        Ok(text.to_string())
+    } else {
+        Err(format!("invalid {what} '{text}'"))
+    }
+}
+
+/// Print a program as DIR assembly.
+///
+/// Fails if a name or expression cannot be written so that it parses back to
+/// the same program (e.g. an expression spanning several lines).
+pub fn print(program: &DirProgram) -> Result<String, DvmError> {
+    let mut out = String::new();
+    for (i, forge) in program.forges.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        print_forge(&mut out, forge).map_err(|e| {
+            DvmError::DirValidate(format!(
+                "cannot print forge '{}' as assembly: {e}",
+                forge.name
+            ))
+        })?;
+    }
+    Ok(out)
+}
+
+fn print_forge(out: &mut String, forge: &DirForge) -> Result<(), String> {
+    let _ = writeln!(out, "forge {} {{", name(&forge.name, "forge name")?);
+    let mut first = true;
+    let mut separate = |out: &mut String| {
+        if !std::mem::take(&mut first) {
+            out.push('\n');
+        }
+    };
+
+    for shape in &forge.shapes {
+        separate(out);
+        let _ = writeln!(out, "  shape {} {{", name(&shape.name, "shape name")?);
+        for field in &shape.fields {
+            let _ = writeln!(
+                out,
+                "    {}: {}",
+                name(&field.name, "field name")?,
+                text(&field.ty, "field type")?
+            );
+        }
+        out.push_str("  }\n");
+    }
+
+    for proc_ in &forge.procs {
+        separate(out);
+        print_proc(out, proc_).map_err(|e| format!("proc '{}': {e}", proc_.name))?;
+    }
+
+    for bind in &forge.binds {
+        separate(out);
+        let _ = writeln!(
+            out,
+            "  bind {} -> {} {{",
+            name(&bind.source, "bind source")?,
+            name(&bind.target, "bind target")?
+        );
+        for clause in &bind.contract {
+            let line = format!(
+                "{} {} {}",
+                name(&clause.key, "clause key")?,
+                name(&clause.op, "clause op")?,
+                text(&clause.value, "clause value")?
+            );
+            let _ = writeln!(out, "    {}", line.trim_end());
+        }
+        out.push_str("  }\n");
+    }
+
+    out.push_str("}\n");
+    Ok(())
+}
+
+fn print_proc(out: &mut String, proc_: &DirProc) -> Result<(), String> {
+    let params = proc_
+        .params
+        .iter()
+        .map(|p| {
+            Ok(format!(
+                "{}: {}",
+                name(&p.name, "param name")?,
+                text(&p.ty, "param type")?
+            ))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    if let Some(span) = &proc_.span {
+        let _ = writeln!(out, "  @ {}", span_text(span)?);
+    }
+    let _ = write!(
+        out,
+        "  proc {} {}({})",
+        name(&proc_.regime, "regime")?,
+        name(&proc_.name, "proc name")?,
+        list(&params, "a param type")?
+    );
+    if let Some(ret) = &proc_.ret {
+        let _ = write!(out, " -> {}", text(ret, "return type")?);
+    }
+    out.push_str(" {\n");
+
+    for uses in &proc_.uses {
+        let _ = write!(out, "    uses {}", name(&uses.resource, "resource")?);
+        if !uses.args.is_empty() {
+            let args = uses
+                .args
+                .iter()
+                .map(|(key, lit)| Ok(format!("{}: {}", name(key, "uses key")?, literal(lit))))
+                .collect::<Result<Vec<_>, String>>()?;
+            let _ = write!(out, "({})", list(&args, "a uses argument")?);
+        }
+        out.push('\n');
+    }
+    for qualifier in &proc_.qualifiers {
+        let _ = writeln!(out, "    qualifier {}", name(qualifier, "qualifier")?);
+    }
+    if !proc_.body_spans.is_empty() && proc_.body_spans.len() != proc_.body.len() {
+        return Err(format!(
+            "{} body spans for {} statements",
+            proc_.body_spans.len(),
+            proc_.body.len()
+        ));
+    }
+    for (i, stmt) in proc_.body.iter().enumerate() {
+        if let Some(Some(span)) = proc_.body_spans.get(i) {
+            let _ = writeln!(out, "    @ {}", span_text(span)?);
+        }
+        let line = statement(stmt).map_err(|e| format!("statement {i}: {e}"))?;
+        let _ = writeln!(out, "    {}", line.trim_end());
+    }
+
+    out.push_str("  }\n");
+    Ok(())
+}
+
+fn statement(stmt: &DirStmt) -> Result<String, String> {
+    Ok(match stmt {
+        DirStmt::Let { name: n, expr } => {
+            format!(
+                "let {} = {}",
+                name(n, "binding name")?,
+                text(expr, "expression")?
+            )
+        }
+        DirStmt::Constrain { predicate } => format!("constrain {}", text(predicate, "predicate")?),
+        DirStmt::Prove { name: n, from } => format!(
+            "prove {} from {}",
+            name(n, "binding name")?,
+            text(from, "expression")?
+        ),
+        DirStmt::Effect { kind, payload } => format!(
+            "effect {} {}",
+            name(kind, "effect kind")?,
+            text(payload, "payload")?
+        ),
+        DirStmt::Return { expr } => format!("return {}", text(expr, "expression")?),
+        DirStmt::Call {
+            name: n,
+            callee,
+            args,
+        } => {
+            let target = match n {
+                Some(n) => format!("{} = ", name(n, "binding name")?),
+                None => String::new(),
+            };
+            let args = args
+                .iter()
+                .map(|arg| match text(arg, "argument")? {
+                    "" => Err("empty argument".to_string()),
+                    arg => Ok(arg.to_string()),
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            format!(
+                "call {target}{}({})",
+                callee_name(callee)?,
+                list(&args, "an argument")?
+            )
+        }
+    })
+}
+
+/// Join a parenthesized list, checking that it splits back into the same items.
+fn list(items: &[String], what: &str) -> Result<String, String> {
+    let joined = items.join(", ");
+    let closes = closing_paren(&format!("{joined})")) == Some(joined.len());
+    if closes && split_list(&joined) == items {
+        Ok(joined)
+    } else {
+        Err(format!("{what} contains an unbalanced `,` or bracket"))
+    }
+}
+
+fn span_text(span: &DirSpan) -> Result<String, String> {
+    text(&span.file, "span file")?;
+    if span.file.is_empty() {
+        return Err("span has an empty file name".into());
+    }
+    Ok(span.to_string())
+}
+
+fn literal(lit: &DirLit) -> String {
+    match lit {
+        DirLit::Int(i) => i.to_string(),
+        DirLit::Bool(b) => b.to_string(),
+        DirLit::String(s) => serde_json::Value::from(s.as_str()).to_string(),
+    }
+}
+
+/// A callee: a proc name, optionally qualified as `forge::proc`.
+fn callee_name(text: &str) -> Result<String, String> {
+    match text.split_once("::") {
+        Some((forge, proc_)) if word(forge, "").is_ok() && word(proc_, "").is_ok() => {
+            Ok(text.to_string())
+        }
+        Some(_) => Err(format!("invalid callee '{text}'")),
+        None => word(text, "callee"),
+    }
+}
+
+fn name<'a>(text: &'a str, what: &str) -> Result<&'a str, String> {
+    word(text, what).map(|_| text)
+}
+
+/// Free text (types, expressions): one line, without surrounding whitespace.
+fn text<'a>(text: &'a str, what: &str) -> Result<&'a str, String> {
+    if text.contains(['\n', '\r']) {
+        Err(format!(
+            "{what} `{}` spans several lines",
+            text.escape_debug()
+        ))
+    } else if text.trim() != text {
+        Err(format!("{what} `{text}` has surrounding whitespace"))
+    } else {
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+
+    const SOURCE: &str = r#"// K calls Q, which allocates and moves a resource.
+forge core {
+  shape Point {
+    x: Int
+    y: core::Num
+  }
+
+  proc K main() -> Int {
+    uses Clock(rate: 1, label: "a, b)", strict: true)
+    qualifier pure
+    let n = 2
+    call m = measure(n Add 1, f(1, 2))
+    call log()
+    effect emit "Q ok"
+    prove p from n Gt 0
+    return m
+  }
+
+  proc Q measure(k: Int) -> Int {
+    let a = q_alloc(QBit)
+    let b = q_move(a)
+    return 7
+  }
+
+  bind core -> io {
+    rate Le 10
+  }
+}
+"#;
+
+    fn json(program: &DirProgram) -> serde_json::Value {
+        serde_json::to_value(program).unwrap()
+    }
+
+    #[test]
+    fn assembly_parses_and_prints_back_verbatim() {
+        let program = parse(SOURCE).unwrap();
+        let forge = &program.forges[0];
+        assert_eq!(forge.shapes[0].fields[1].ty, "core::Num");
+        let main = &forge.procs[0];
+        assert_eq!(
+            json(&program)["forges"][0]["procs"][0]["body"][1],
+            serde_json::json!({ "Call": {
+                "name": "m", "callee": "measure", "args": ["n Add 1", "f(1, 2)"]
+            } })
+        );
+        assert!(matches!(
+            &main.body[3],
+            DirStmt::Effect { payload, .. } if payload == "\"Q ok\""
+        ));
+        assert!(matches!(&main.uses[0].args[1].1, DirLit::String(s) if s == "a, b)"));
+        assert_eq!(forge.binds[0].contract[0].value, "10");
+
+        let printed = print(&program).unwrap();
+        assert_eq!(printed, SOURCE.split_once('\n').unwrap().1);
+    }
+
+    #[test]
+    fn fixtures_round_trip_through_assembly() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../tests/fixtures/dir");
+        let mut seen = 0;
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let bytes = fs::read(&path).unwrap();
+            let program = if is_asm_path(&path) {
+                parse(std::str::from_utf8(&bytes).unwrap()).unwrap()
+            } else {
+                crate::dir::format::read_json(&bytes).unwrap().program
+            };
+            let text = print(&program).unwrap();
+            let parsed = parse(&text).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+            assert_eq!(json(&parsed), json(&program), "{}", path.display());
+            seen += 1;
+        }
+        assert!(seen > 0);
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let err = |text: &str| parse(text).unwrap_err().to_string();
+        assert_eq!(
+            err("forge core {\n  proc K main() {\n    jump 3\n  }\n}\n"),
+            "DIR load error: line 3: expected `uses`, `qualifier`, a statement or `}`, found `jump`"
+        );
+        assert_eq!(
+            err("forge core {\n  proc K main( {\n"),
+            "DIR load error: line 2: expected `proc REGIME NAME(PARAMS) [-> RET] {`"
+        );
+        assert_eq!(
+            err("forge core {\n  shape P {\n    x: Int\n"),
+            "DIR load error: unexpected end of input: shape 'P' is not closed"
+        );
+        assert_eq!(
+            err("forge core {\n  proc K main() {\n    call lib::x::y()\n  }\n}\n"),
+            "DIR load error: line 3: invalid callee 'lib::x::y'"
+        );
+        let qualified = "forge core {\n  proc K main() {\n    call n = lib::twice(1)\n  }\n}\n";
+        assert_eq!(print(&parse(qualified).unwrap()).unwrap(), qualified);
+        assert!(is_asm_path("tests/hello.dasm"));
+        assert!(!is_asm_path("tests/hello.dir.json"));
+    }
+
+    #[test]
+    fn spans_attach_to_the_next_proc_or_statement() {
+        let text = "forge core {\n  @ main.ds:1:1\n  proc K main() -> Int {\n    let n = 1\n    \
+                    @ lib/main.ds:3:5\n    return n\n  }\n}\n";
+        let program = parse(text).unwrap();
+        let main = &program.forges[0].procs[0];
+        assert_eq!(main.span.as_ref().unwrap().to_string(), "main.ds:1:1");
+        assert_eq!(main.stmt_span(0).unwrap().to_string(), "main.ds:1:1");
+        assert_eq!(main.stmt_span(1).unwrap().to_string(), "lib/main.ds:3:5");
+        assert_eq!(print(&program).unwrap(), text);
+
+        let err = |text: &str| parse(text).unwrap_err().to_string();
+        assert_eq!(
+            err("forge core {\n  proc K main() {\n    @ main.ds:2:5\n  }\n}\n"),
+            "DIR load error: line 4: a span must be followed by a proc or a statement"
+        );
+        assert_eq!(
+            err("forge core {\n  @ main.ds:1:1\n  @ main.ds:2:1\n"),
+            "DIR load error: line 3: two spans in a row"
+        );
+    }
+
+    #[test]
+    fn unprintable_programs_are_rejected() {
+        let mut program = parse(SOURCE).unwrap();
+        program.forges[0].procs[1].body[0] = DirStmt::Return {
+            expr: "1\nAdd 2".into(),
+        };
+        assert_eq!(
+            print(&program).unwrap_err().to_string(),
+            "DIR validation error: cannot print forge 'core' as assembly: proc 'measure': \
+             statement 0: expression `1\\nAdd 2` spans several lines"
+        );
+    }
+}
@@ -0,0 +1,588 @@
+//! Compact binary DIR encoding (`*.dirb`).
+//!
+//! Layout (integers are LEB128 varints, signed ones zigzag-encoded):
+//! - magic `DIRB` and the encoding revision byte (1)
+//! - `dir_version` (the current one when encoding; see `dir::format`)
+//! - string table: count, then each string as byte length + UTF-8
+//! - producer: 0, or 1 followed by name and version
+//! - program: forges as counted lists of their shapes, procs and binds; a proc
+//!   ends with its optional span and its statement spans (from `dir_version` 3)
+//!
+//! Version 2 bodies (no spans) are decoded and then upgraded through the same
+//! `dir::format` chain as JSON artifacts.
+//!
+//! Every string after the table is a table index. Strings are interned in
+//! first-use order, so identical programs always encode to identical bytes.
+
+use super::format::{self, DirArtifact, DirProducer, DIR_VERSION};
+use super::{
+    DirBind, DirClause, DirField, DirForge, DirLit, DirParam, DirProc, DirProgram, DirShape,
+    DirSpan, DirStmt, DirUses,
+};
+use crate::DvmError;
+use indexmap::IndexSet;
+
+/// Leading bytes of every binary artifact.
+pub const MAGIC: &[u8; 4] = b"DIRB";
+
+/// File extension of binary DIR artifacts.
+pub const EXTENSION: &str = "dirb";
+
+/// Revision of the byte layout described in the module docs.
+const REVISION: u8 = 1;
+
+/// Whether `bytes` hold a binary artifact (by magic).
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// Encode a program (and its producer) at the current `dir_version`.
+pub fn encode(program: &DirProgram, producer: Option<&DirProducer>) -> Vec<u8> {
+    let mut body = Writer::default();
+    match producer {
+        None => body.byte(0),
+        Some(p) => {
+            body.byte(1);
+            body.str(&p.name);
+            body.str(&p.version);
+        }
+    }
+    body.list(&program.forges, Writer::forge);
+
+    let mut out = MAGIC.to_vec();
+    out.push(REVISION);
+    varint(&mut out, u64::from(DIR_VERSION));
+    varint(&mut out, body.strings.len() as u64);
+    for s in &body.strings {
+        varint(&mut out, s.len() as u64);
+        out.extend_from_slice(s.as_bytes());
+    }
+    out.extend(body.bytes);
+    out
+}
+
+/// Oldest `dir_version` with a binary encoding.
+const FIRST_VERSION: u64 = 2;
+
+/// Decode a binary artifact, upgrading older versions.
+pub fn decode(bytes: &[u8]) -> Result<DirArtifact, DvmError> {
+    let mut r = Reader {
+        bytes,
+        pos: 0,
+        strings: Vec::new(),
+        version: u64::from(DIR_VERSION),
+    };
+    if !is_binary(bytes) {
+        return Err(r.error("missing DIRB magic"));
+    }
+    r.pos = MAGIC.len();
+    let revision = r.byte()?;
+    if revision != REVISION {
+        return Err(DvmError::DirLoad(format!(
+            "unsupported binary DIR revision {revision} (this build reads {REVISION})"
+        )));
+    }
+    let from = r.varint()?;
+    if !(FIRST_VERSION..=u64::from(DIR_VERSION)).contains(&from) {
+        return Err(DvmError::DirLoad(format!(
+            "unsupported dir_version {from} in binary DIR; \
+             this build reads versions {FIRST_VERSION} to {DIR_VERSION}"
+        )));
+    }
+    r.version = from;
+
+    let count = r.len()?;
+    for _ in 0..count {
+        let len = r.len()?;
+        let raw = r.take(len)?;
+        let s = std::str::from_utf8(raw).map_err(|_| r.error("string is not UTF-8"))?;
+        r.strings.push(s.to_string());
+    }
+
+    let producer = match r.byte()? {
+        0 => None,
+        1 => Some(DirProducer {
+            name: r.str()?,
+            version: r.str()?,
+        }),
+        tag => return Err(r.error(&format!("invalid producer tag {tag}"))),
+    };
+    let forges = r.list(Reader::forge)?;
+    if r.pos != bytes.len() {
+        return Err(r.error("trailing bytes"));
+    }
+    let mut program = DirProgram { forges };
+    if from < u64::from(DIR_VERSION) {
+        program = upgrade(&program, from)?;
+    }
+    Ok(DirArtifact {
+        program,
+        producer,
+        from,
+        dropped: Vec::new(),
+    })
+}
+
+/// Run a program decoded from an older body through the JSON upgrade chain.
+fn upgrade(program: &DirProgram, from: u64) -> Result<DirProgram, DvmError> {
+    let mut v = serde_json::json!(program);
+    if let Some(obj) = v.as_object_mut() {
+        format::upgrade(obj, from);
+        obj.remove("dir_version");
+    }
+    serde_json::from_value(v).map_err(|e| DvmError::DirLoad(format!("binary DIR: {e}")))
+}
+
+fn varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+    strings: IndexSet<String>,
+}
+
+impl Writer {
+    fn byte(&mut self, b: u8) {
+        self.bytes.push(b);
+    }
+
+    fn varint(&mut self, n: u64) {
+        varint(&mut self.bytes, n);
+    }
+
+    fn str(&mut self, s: &str) {
+        let index = match self.strings.get_index_of(s) {
+            Some(i) => i,
+            None => self.strings.insert_full(s.to_string()).0,
+        };
+        self.varint(index as u64);
+    }
+
+    fn opt_str(&mut self, s: Option<&str>) {
+        match s {
+            None => self.byte(0),
+            Some(s) => {
+                self.byte(1);
+                self.str(s);
+            }
+        }
+    }
+
+    fn list<T>(&mut self, items: &[T], mut item: impl FnMut(&mut Self, &T)) {
+        self.varint(items.len() as u64);
+        for x in items {
+            item(self, x);
+        }
+    }
+
+    fn forge(&mut self, forge: &DirForge) {
+        self.str(&forge.name);
+        self.list(&forge.shapes, |w, shape: &DirShape| {
+            w.str(&shape.name);
+            w.list(&shape.fields, |w, f: &DirField| {
+                w.str(&f.name);
+                w.str(&f.ty);
+            });
+        });
+        self.list(&forge.procs, Self::proc_);
+        self.list(&forge.binds, |w, bind: &DirBind| {
+            w.str(&bind.source);
+            w.str(&bind.target);
+            w.list(&bind.contract, |w, c: &DirClause| {
+                w.str(&c.key);
+                w.str(&c.op);
+                w.str(&c.value);
+            });
+        });
+    }
+
+    fn proc_(&mut self, proc_: &DirProc) {
+        self.str(&proc_.regime);
+        self.str(&proc_.name);
+        self.list(&proc_.params, |w, p: &DirParam| {
+            w.str(&p.name);
+            w.str(&p.ty);
+        });
+        self.list(&proc_.uses, |w, u: &DirUses| {
+            w.str(&u.resource);
+            w.list(&u.args, |w, (key, lit): &(String, DirLit)| {
+                w.str(key);
+                match lit {
+                    DirLit::Int(i) => {
+                        w.byte(0);
+                        w.varint(((i << 1) ^ (i >> 63)) as u64);
+                    }
+                    DirLit::Bool(b) => {
+                        w.byte(1);
+                        w.byte(u8::from(*b));
+                    }
+                    DirLit::String(s) => {
+                        w.byte(2);
+                        w.str(s);
+                    }
+                }
+            });
+        });
+        self.opt_str(proc_.ret.as_deref());
+        self.list(&proc_.qualifiers, |w, q: &String| w.str(q));
+        self.list(&proc_.body, Self::stmt);
+        self.opt_span(proc_.span.as_ref());
+        self.list(&proc_.body_spans, |w, span: &Option<DirSpan>| {
+            w.opt_span(span.as_ref())
+        });
+    }
+
+    fn opt_span(&mut self, span: Option<&DirSpan>) {
+        match span {
+            None => self.byte(0),
+            Some(span) => {
+                self.byte(1);
+                self.str(&span.file);
+                self.varint(u64::from(span.line));
+                self.varint(u64::from(span.column));
+            }
+        }
+    }
+
+    fn stmt(&mut self, stmt: &DirStmt) {
+        match stmt {
+            DirStmt::Let { name, expr } => {
+                self.byte(0);
+                self.str(name);
+                self.str(expr);
+            }
+            DirStmt::Constrain { predicate } => {
+                self.byte(1);
+                self.str(predicate);
+            }
+            DirStmt::Prove { name, from } => {
+                self.byte(2);
+                self.str(name);
+                self.str(from);
+            }
+            DirStmt::Effect { kind, payload } => {
+                self.byte(3);
+                self.str(kind);
+                self.str(payload);
+            }
+            DirStmt::Return { expr } => {
+                self.byte(4);
+                self.str(expr);
+            }
+            DirStmt::Call { name, callee, args } => {
+                self.byte(5);
+                self.opt_str(name.as_deref());
+                self.str(callee);
+                self.list(args, |w, a: &String| w.str(a));
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    strings: Vec<String>,
+
+    /// `dir_version` of the body being read.
+    version: u64,
+}
+
+impl<'a> Reader<'a> {
+    fn error(&self, msg: &str) -> DvmError {
+        DvmError::DirLoad(format!("binary DIR: {msg} at byte {}", self.pos))
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], DvmError> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| self.error("unexpected end of input"))?;
+        let out = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(out)
+    }
+
+    fn byte(&mut self) -> Result<u8, DvmError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<u64, DvmError> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.byte()?;
+            n |= u64::from(b & 0x7f) << shift;
+            if b & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(self.error("varint too long"))
+    }
+
+    /// A count or length; each counted item takes at least one byte, which
+    /// bounds allocations by the input size.
+    fn len(&mut self) -> Result<usize, DvmError> {
+        let n = self.varint()?;
+        if n > (self.bytes.len() - self.pos) as u64 {
+            return Err(self.error(&format!("length {n} exceeds the input")));
+        }
+        Ok(n as usize)
+    }
+
+    fn str(&mut self) -> Result<String, DvmError> {
+        let index = self.varint()?;
+        usize::try_from(index)
+            .ok()
+            .and_then(|i| self.strings.get(i))
+            .cloned()
+            .ok_or_else(|| self.error(&format!("string index {index} out of range")))
+    }
+
+    fn opt_str(&mut self) -> Result<Option<String>, DvmError> {
+        match self.byte()? {
+            0 => Ok(None),
+            1 => self.str().map(Some),
+            tag => Err(self.error(&format!("invalid option tag {tag}"))),
+        }
+    }
+
+    fn list<T>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T, DvmError>,
+    ) -> Result<Vec<T>, DvmError> {
+        let n = self.len()?;
+        (0..n).map(|_| item(self)).collect()
+    }
+
+    fn forge(&mut self) -> Result<DirForge, DvmError> {
+        Ok(DirForge {
+            name: self.str()?,
+            shapes: self.list(|r| {
+                Ok(DirShape {
+                    name: r.str()?,
+                    fields: r.list(|r| {
+                        Ok(DirField {
+                            name: r.str()?,
+                            ty: r.str()?,
+                        })
+                    })?,
+                })
+            })?,
+            procs: self.list(Self::proc_)?,
+            binds: self.list(|r| {
+                Ok(DirBind {
+                    source: r.str()?,
+                    target: r.str()?,
+                    contract: r.list(|r| {
+                        Ok(DirClause {
+                            key: r.str()?,
+                            op: r.str()?,
+                            value: r.str()?,
+                        })
+                    })?,
+                })
+            })?,
+        })
+    }
+
+    fn proc_(&mut self) -> Result<DirProc, DvmError> {
+        let mut proc_ = DirProc {
+            regime: self.str()?,
+            name: self.str()?,
+            params: self.list(|r| {
+                Ok(DirParam {
+                    name: r.str()?,
+                    ty: r.str()?,
+                })
+            })?,
+            uses: self.list(|r| {
+                Ok(DirUses {
+                    resource: r.str()?,
+                    args: r.list(|r| Ok((r.str()?, r.lit()?)))?,
+                })
+            })?,
+            ret: self.opt_str()?,
+            qualifiers: self.list(Self::str)?,
+            body: self.list(Self::stmt)?,
+            span: None,
+            body_spans: Vec::new(),
+        };
+        if self.version >= 3 {
+            proc_.span = self.opt_span()?;
+            proc_.body_spans = self.list(Self::opt_span)?;
+        }
+        Ok(proc_)
+    }
+
+    fn opt_span(&mut self) -> Result<Option<DirSpan>, DvmError> {
+        match self.byte()? {
+            0 => Ok(None),
+            1 => Ok(Some(DirSpan {
+                file: self.str()?,
+                line: self.u32()?,
+                column: self.u32()?,
+            })),
+            tag => Err(self.error(&format!("invalid option tag {tag}"))),
+        }
+    }
+
+    fn u32(&mut self) -> Result<u32, DvmError> {
+        let n = self.varint()?;
+        u32::try_from(n).map_err(|_| self.error(&format!("{n} does not fit in 32 bits")))
+    }
+
+    fn lit(&mut self) -> Result<DirLit, DvmError> {
+        match self.byte()? {
+            0 => {
+                let z = self.varint()?;
+                Ok(DirLit::Int((z >> 1) as i64 ^ -((z & 1) as i64)))
+            }
+            1 => match self.byte()? {
+                0 => Ok(DirLit::Bool(false)),
+                1 => Ok(DirLit::Bool(true)),
+                b => Err(self.error(&format!("invalid boolean {b}"))),
+            },
+            2 => self.str().map(DirLit::String),
+            tag => Err(self.error(&format!("invalid literal tag {tag}"))),
+        }
+    }
+
+    fn stmt(&mut self) -> Result<DirStmt, DvmError> {
+        Ok(match self.byte()? {
+            0 => DirStmt::Let {
+                name: self.str()?,
+                expr: self.str()?,
+            },
+            1 => DirStmt::Constrain {
+                predicate: self.str()?,
+            },
+            2 => DirStmt::Prove {
+                name: self.str()?,
+                from: self.str()?,
+            },
+            3 => DirStmt::Effect {
+                kind: self.str()?,
+                payload: self.str()?,
+            },
+            4 => DirStmt::Return { expr: self.str()? },
+            5 => DirStmt::Call {
+                name: self.opt_str()?,
+                callee: self.str()?,
+                args: self.list(Self::str)?,
+            },
+            tag => return Err(self.error(&format!("invalid statement tag {tag}"))),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dir::format;
+    use pretty_assertions::assert_eq;
+    use std::{fs, path::Path};
+
+    fn fixtures() -> Vec<(String, DirProgram)> {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../tests/fixtures/dir");
+        let mut out: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| {
+                let path = entry.unwrap().path();
+                let artifact = format::read(&path, &fs::read(&path).unwrap()).unwrap();
+                (path.display().to_string(), artifact.program)
+            })
+            .collect();
+        out.sort_by(|a, b| a.0.cmp(&b.0));
+        out
+    }
+
+    #[test]
+    fn fixtures_round_trip_with_stable_bytes() {
+        let producer = DirProducer::dustrun();
+        for (path, program) in fixtures() {
+            let bytes = encode(&program, Some(&producer));
+            let decoded = decode(&bytes).unwrap_or_else(|e| panic!("{path}: {e}"));
+            assert_eq!(decoded.producer.as_ref(), Some(&producer));
+            assert_eq!(
+                serde_json::to_value(&decoded.program).unwrap(),
+                serde_json::to_value(&program).unwrap(),
+                "{path}"
+            );
+            assert_eq!(encode(&decoded.program, Some(&producer)), bytes, "{path}");
+
+            let json = format::write(&program, None, format::DirEncoding::Json).unwrap();
+            assert!(
+                bytes.len() < json.len() / 2,
+                "{path}: {} bytes",
+                bytes.len()
+            );
+        }
+    }
+
+    #[test]
+    fn encoding_is_pinned() {
+        let program = crate::dir::asm::parse(
+            "forge core {\n  proc K main() -> Int {\n    let n = -2\n    return n\n  }\n}\n",
+        )
+        .unwrap();
+        let bytes = encode(&program, None);
+        let expected: &[u8] = b"DIRB\x01\x03\x06\
+            \x04core\x01K\x04main\x03Int\x01n\x02-2\
+            \x00\x01\x00\x00\x01\x01\x02\x00\x00\x01\x03\x00\x02\x00\x04\x05\x04\x04\x00\x00\x00";
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn version_2_bodies_are_upgraded() {
+        // Written by a version 2 build: no span fields after a proc body.
+        let v2: &[u8] = b"DIRB\x01\x02\x06\
+            \x04core\x01K\x04main\x03Int\x01n\x02-2\
+            \x00\x01\x00\x00\x01\x01\x02\x00\x00\x01\x03\x00\x02\x00\x04\x05\x04\x04\x00";
+        let decoded = decode(v2).unwrap();
+        assert_eq!(decoded.from, 2);
+        assert!(decoded.upgraded());
+        let program = crate::dir::asm::parse(
+            "forge core {\n  proc K main() -> Int {\n    let n = -2\n    return n\n  }\n}\n",
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::to_value(&decoded.program).unwrap(),
+            serde_json::to_value(&program).unwrap()
+        );
+        assert_eq!(&encode(&decoded.program, None)[5..6], b"\x03");
+    }
+
+    #[test]
+    fn corrupt_input_is_rejected() {
+        let (_, program) = fixtures().pop().unwrap();
+        let bytes = encode(&program, None);
+        for end in 0..bytes.len() {
+            assert!(decode(&bytes[..end]).is_err(), "prefix of {end} bytes");
+        }
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            decode(&trailing).unwrap_err().to_string(),
+            format!(
+                "DIR load error: binary DIR: trailing bytes at byte {}",
+                bytes.len()
+            )
+        );
+
+        let mut future = bytes;
+        future[5] = 9;
+        assert_eq!(
+            decode(&future).unwrap_err().to_string(),
+            "DIR load error: unsupported dir_version 9 in binary DIR; \
+             this build reads versions 2 to 3"
+        );
+    }
+}
@@ -0,0 +1,416 @@
+//! DIR artifact format: the versioned JSON envelope around a `DirProgram`.
+//!
+//! An artifact is the program's fields plus a `dir_version` and, optionally,
+//! `producer` metadata naming the tool that wrote it:
+//!
+//! ```json
+//! { "dir_version": 3, "producer": { "name": "dustc", "version": "0.3.0" }, "forges": [] }
+//! ```
+//!
+//! `read_json` detects the version, rejects ones this build does not know, and
+//! upgrades older artifacts step by step (`n` to `n + 1`) before reading the
+//! result strictly.
+//!
+//! Versions:
+//! - 1: unversioned artifacts (a bare program; no `dir_version` field).
+//! - 2: adds `dir_version` and `producer`; unknown fields are rejected.
+//! - 3: adds optional source spans (`DirProc::span`, `DirProc::body_spans`).
+//!
+//! Version 1 artifacts were read leniently, so their upgrade drops fields this
+//! build does not know instead of failing on them; the dropped fields are
+//! reported (`DirArtifact::dropped`, and as a logged warning).
+//!
+//! Besides JSON, a program can be encoded as binary (`dir::binary`, detected by
+//! its magic) or written as assembly (`dir::asm`, detected by extension); `read`
+//! and `write` dispatch on `DirEncoding`.
+
+use super::{asm, binary, DirProgram};
+use crate::DvmError;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as Json};
+use std::path::Path;
+
+/// Version of the DIR format produced and read by this build.
+pub const DIR_VERSION: u32 = 3;
+
+/// The tool that wrote an artifact.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DirProducer {
+    pub name: String,
+    pub version: String,
+}
+
+impl DirProducer {
+    /// This build of the DVM, for artifacts written by dustrun itself.
+    pub fn dustrun() -> Self {
+        Self {
+            name: "dustrun".into(),
+            version: env!("CARGO_PKG_VERSION").into(),
+        }
+    }
+}
+
+/// A program read through the version layer.
+#[derive(Debug, Clone)]
+pub struct DirArtifact {
+    pub program: DirProgram,
+    pub producer: Option<DirProducer>,
+
+    /// `dir_version` of the input (before upgrading).
+    pub from: u64,
+
+    /// Unknown fields of a version 1 input dropped on upgrade, as paths
+    /// (e.g. `forges[0].procs[1].comment`).
+    pub dropped: Vec<String>,
+}
+
+impl DirArtifact {
+    /// Whether the input was older than the current version.
+    pub fn upgraded(&self) -> bool {
+        self.from != u64::from(DIR_VERSION)
+    }
+}
+
+/// How a DIR artifact is encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirEncoding {
+    Json,
+    Binary,
+    Asm,
+}
+
+impl DirEncoding {
+    /// The encoding of an input: assembly by extension, binary by magic, JSON
+    /// otherwise.
+    pub fn detect(path: impl AsRef<Path>, bytes: &[u8]) -> Self {
+        if asm::is_asm_path(path) {
+            DirEncoding::Asm
+        } else if binary::is_binary(bytes) {
+            DirEncoding::Binary
+        } else {
+            DirEncoding::Json
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DirEncoding::Json => "json",
+            DirEncoding::Binary => "binary",
+            DirEncoding::Asm => "asm",
+        }
+    }
+
+    /// The encoding implied by a file name (`*.dasm`, `*.dirb`, JSON otherwise).
+    pub fn for_path(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some(asm::EXTENSION) => DirEncoding::Asm,
+            Some(binary::EXTENSION) => DirEncoding::Binary,
+            _ => DirEncoding::Json,
+        }
+    }
+}
+
+/// Read a DIR artifact from `path`'s contents, in whichever encoding it uses.
+pub fn read(path: impl AsRef<Path>, bytes: &[u8]) -> Result<DirArtifact, DvmError> {
+    match DirEncoding::detect(path, bytes) {
+        DirEncoding::Json => read_json(bytes),
+        DirEncoding::Binary => binary::decode(bytes),
+        DirEncoding::Asm => {
+            let text = std::str::from_utf8(bytes)
+                .map_err(|e| DvmError::DirLoad(format!("assembly is not UTF-8: {e}")))?;
+            Ok(DirArtifact {
+                program: asm::parse(text)?,
+                producer: None,
+                from: u64::from(DIR_VERSION),
+                dropped: Vec::new(),
+            })
+        }
+    }
+}
+
+/// Write a program in `encoding` (producer metadata is not kept in assembly).
+pub fn write(
+    program: &DirProgram,
+    producer: Option<&DirProducer>,
+    encoding: DirEncoding,
+) -> Result<Vec<u8>, DvmError> {
+    match encoding {
+        DirEncoding::Json => {
+            let json = serde_json::to_string_pretty(&to_json(program, producer))
+                .map_err(|e| DvmError::Runtime(format!("failed to serialize DIR: {e}")))?;
+            Ok((json + "\n").into_bytes())
+        }
+        DirEncoding::Binary => Ok(binary::encode(program, producer)),
+        DirEncoding::Asm => asm::print(program).map(String::into_bytes),
+    }
+}
+
+/// Read a JSON DIR artifact of any supported version.
+pub fn read_json(bytes: &[u8]) -> Result<DirArtifact, DvmError> {
+    let mut v: Json = serde_json::from_slice(bytes).map_err(load_error)?;
+    let obj = v
+        .as_object_mut()
+        .ok_or_else(|| DvmError::DirLoad("DIR artifact must be a JSON object".into()))?;
+
+    let from = dir_version(obj)?;
+    if from == 0 || from > u64::from(DIR_VERSION) {
+        let producer = match obj.get("producer").map(DirProducer::deserialize) {
+            Some(Ok(p)) => format!(" (written by {} {})", p.name, p.version),
+            _ => String::new(),
+        };
+        return Err(DvmError::DirLoad(format!(
+            "unsupported dir_version {from}{producer}; this build reads versions 1 to {DIR_VERSION}"
+        )));
+    }
+    let dropped = upgrade(obj, from);
+
+    obj.remove("dir_version");
+    let producer = obj
+        .remove("producer")
+        .map(serde_json::from_value)
+        .transpose()
+        .map_err(|e| DvmError::DirLoad(format!("invalid producer: {e}")))?;
+    let program = serde_json::from_value(v).map_err(load_error)?;
+    Ok(DirArtifact {
+        program,
+        producer,
+        from,
+        dropped,
+    })
+}
+
+/// The current-version envelope of `program`.
+pub fn to_json(program: &DirProgram, producer: Option<&DirProducer>) -> Json {
+    let mut obj = Map::new();
+    obj.insert("dir_version".into(), Json::from(DIR_VERSION));
+    if let Some(producer) = producer {
+        obj.insert("producer".into(), serde_json::json!(producer));
+    }
+    if let Json::Object(fields) = serde_json::json!(program) {
+        obj.extend(fields);
+    }
+    Json::Object(obj)
+}
+
+/// The artifact's `dir_version`; unversioned artifacts are version 1.
+fn dir_version(obj: &Map<String, Json>) -> Result<u64, DvmError> {
+    match obj.get("dir_version") {
+        None => Ok(1),
+        Some(n) => n
+            .as_u64()
+            .ok_or_else(|| DvmError::DirLoad(format!("dir_version must be an integer, got {n}"))),
+    }
+}
+
+/// Upgrade an artifact object of version `from` to the current version, one
+/// step at a time. Returns the fields dropped from a version 1 artifact.
+pub(super) fn upgrade(obj: &mut Map<String, Json>, from: u64) -> Vec<String> {
+    let mut dropped = Vec::new();
+    if from < 2 {
+        dropped = v1_to_v2(obj);
+        if !dropped.is_empty() {
+            log::warn!(
+                "dir_version 1 artifact: dropped unknown field(s): {}",
+                dropped.join(", ")
+            );
+        }
+    }
+    if from < 3 {
+        v2_to_v3(obj);
+    }
+    dropped
+}
+
+/// Version 1 artifacts were read leniently: drop the fields version 2 would
+/// reject, returning their paths.
+fn v1_to_v2(obj: &mut Map<String, Json>) -> Vec<String> {
+    let mut dropped = Vec::new();
+    let mut retain = |obj: &mut Map<String, Json>, path: &str, known: &[&str]| {
+        obj.retain(|key, _| {
+            let keep = known.contains(&key.as_str());
+            if !keep {
+                dropped.push(format!("{path}{key}"));
+            }
+            keep
+        });
+    };
+
+    retain(obj, "", &["producer", "forges"]);
+    for (f, forge) in objects(obj.get_mut("forges")) {
+        let at = format!("forges[{f}].");
+        retain(forge, &at, &["name", "shapes", "procs", "binds"]);
+        for (s, shape) in objects(forge.get_mut("shapes")) {
+            let at = format!("{at}shapes[{s}].");
+            retain(shape, &at, &["name", "fields"]);
+            for (i, field) in objects(shape.get_mut("fields")) {
+                retain(field, &format!("{at}fields[{i}]."), &["name", "ty"]);
+            }
+        }
+        for (p, proc_) in objects(forge.get_mut("procs")) {
+            let at = format!("{at}procs[{p}].");
+            retain(
+                proc_,
+                &at,
+                &[
+                    "regime",
+                    "name",
+                    "params",
+                    "uses",
+                    "ret",
+                    "qualifiers",
+                    "body",
+                ],
+            );
+            for (i, param) in objects(proc_.get_mut("params")) {
+                retain(param, &format!("{at}params[{i}]."), &["name", "ty"]);
+            }
+            for (i, uses) in objects(proc_.get_mut("uses")) {
+                retain(uses, &format!("{at}uses[{i}]."), &["resource", "args"]);
+            }
+            for (i, stmt) in objects(proc_.get_mut("body")) {
+                for (kind, fields) in stmt.iter_mut() {
+                    let Some(fields) = fields.as_object_mut() else {
+                        continue;
+                    };
+                    let at = format!("{at}body[{i}].{kind}.");
+                    match kind.as_str() {
+                        "Let" => retain(fields, &at, &["name", "expr"]),
+                        "Constrain" => retain(fields, &at, &["predicate"]),
+                        "Prove" => retain(fields, &at, &["name", "from"]),
+                        "Effect" => retain(fields, &at, &["kind", "payload"]),
+                        "Return" => retain(fields, &at, &["expr"]),
+                        "Call" => retain(fields, &at, &["name", "callee", "args"]),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        for (b, bind) in objects(forge.get_mut("binds")) {
+            let at = format!("{at}binds[{b}].");
+            retain(bind, &at, &["source", "target", "contract"]);
+            for (i, clause) in objects(bind.get_mut("contract")) {
+                retain(
+                    clause,
+                    &format!("{at}contract[{i}]."),
+                    &["key", "op", "value"],
+                );
+            }
+        }
+    }
+
+    obj.insert("dir_version".into(), Json::from(2u32));
+    dropped
+}
+
+/// Version 3 only adds optional fields.
+fn v2_to_v3(obj: &mut Map<String, Json>) {
+    obj.insert("dir_version".into(), Json::from(3u32));
+}
+
+/// The objects in a JSON array field, with their indices (nothing if it is
+/// missing or malformed, which the strict read then reports).
+fn objects(v: Option<&mut Json>) -> impl Iterator<Item = (usize, &mut Map<String, Json>)> {
+    v.and_then(Json::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(Json::as_object_mut)
+        .enumerate()
+}
+
+fn load_error(e: serde_json::Error) -> DvmError {
+    DvmError::DirLoad(format!("{e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn program() -> Json {
+        json!({ "forges": [{
+            "name": "core", "shapes": [], "binds": [],
+            "procs": [{ "regime": "K", "name": "main", "params": [], "uses": [],
+                        "ret": "Int", "qualifiers": [],
+                        "body": [{ "Return": { "expr": "7" } }] }]
+        }] })
+    }
+
+    fn read(v: &Json) -> Result<DirArtifact, DvmError> {
+        read_json(v.to_string().as_bytes())
+    }
+
+    #[test]
+    fn envelopes_round_trip_and_legacy_artifacts_upgrade() {
+        let legacy = read(&program()).unwrap();
+        assert_eq!(legacy.from, 1);
+        assert!(legacy.upgraded());
+        assert_eq!(legacy.producer, None);
+
+        let producer = DirProducer {
+            name: "dustc".into(),
+            version: "0.3.0".into(),
+        };
+        let current = to_json(&legacy.program, Some(&producer));
+        assert_eq!(current["dir_version"], 3);
+        assert_eq!(current["forges"], program()["forges"]);
+
+        let read_back = read(&current).unwrap();
+        assert!(!read_back.upgraded());
+        assert_eq!(read_back.producer, Some(producer));
+        assert_eq!(json!(read_back.program), program());
+    }
+
+    #[test]
+    fn unversioned_artifacts_report_dropped_fields() {
+        let mut legacy = program();
+        legacy["comment"] = "written by an old dustc".into();
+        legacy["forges"][0]["procs"][0]["comment"] = "inline".into();
+        legacy["forges"][0]["procs"][0]["body"][0]["Return"]["note"] = "seven".into();
+        let read_v1 = read(&legacy).unwrap();
+        assert_eq!(read_v1.from, 1);
+        assert_eq!(json!(read_v1.program), program());
+        assert_eq!(
+            read_v1.dropped,
+            [
+                "comment",
+                "forges[0].procs[0].comment",
+                "forges[0].procs[0].body[0].Return.note"
+            ]
+        );
+        assert!(read(&program()).unwrap().dropped.is_empty());
+
+        // Version 2 was read strictly from the start.
+        legacy["dir_version"] = 2.into();
+        let err = read(&legacy).unwrap_err().to_string();
+        assert!(err.contains("unknown field"), "{err}");
+    }
+
+    #[test]
+    fn unsupported_and_malformed_artifacts_are_rejected() {
+        let mut future = program();
+        future["dir_version"] = 4.into();
+        future["producer"] = json!({ "name": "dustc", "version": "0.9.0" });
+        assert_eq!(
+            read(&future).unwrap_err().to_string(),
+            "DIR load error: unsupported dir_version 4 (written by dustc 0.9.0); \
+             this build reads versions 1 to 3"
+        );
+
+        let mut unknown = to_json(&read(&program()).unwrap().program, None);
+        unknown["forges"][0]["procs"][0]["comment"] = "inline".into();
+        let err = read(&unknown).unwrap_err().to_string();
+        assert!(err.contains("unknown field `comment`"), "{err}");
+
+        let mut version = program();
+        version["dir_version"] = "2".into();
+        assert_eq!(
+            read(&version).unwrap_err().to_string(),
+            "DIR load error: dir_version must be an integer, got \"2\""
+        );
+        assert_eq!(
+            read_json(b"[]").unwrap_err().to_string(),
+            "DIR load error: DIR artifact must be a JSON object"
+        );
+    }
+}
@@ -0,0 +1,232 @@
+//! Linking several DIR artifacts into one program.
+//!
+//! dustc emits one artifact per module. `Linker` merges them in the order they
+//! are added:
+//! - forges with distinct names are appended; forges with the same name are
+//!   merged (shapes, procs and binds concatenated) as long as no proc or shape is
+//!   declared by two artifacts;
+//! - every call is then resolved from its caller's forge
+//!   (`DirProgram::resolve_proc`), and bare calls that land in another forge are
+//!   rewritten as `forge::proc`, so the linked program keeps its meaning when it
+//!   is linked again.
+//!
+//! Conflicts and unresolved calls are reported together in one `DirLoad` error.
+
+use super::{DirForge, DirProgram, DirStmt, ResolveError};
+use crate::DvmError;
+use std::collections::HashMap;
+
+/// Merges DIR artifacts into one program.
+#[derive(Debug, Default)]
+pub struct Linker {
+    program: DirProgram,
+
+    /// Artifact (position and origin) declaring each `forge::proc` and
+    /// `forge::Shape`, keyed with a `proc ` / `shape ` prefix.
+    origins: HashMap<String, (usize, String)>,
+
+    /// Artifacts added so far.
+    added: usize,
+
+    errors: Vec<String>,
+}
+
+impl Linker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an artifact's forges; `origin` names it in conflict messages.
+    pub fn add(&mut self, origin: &str, program: DirProgram) {
+        self.added += 1;
+        for forge in program.forges {
+            let at = match self
+                .program
+                .forges
+                .iter()
+                .position(|f| f.name == forge.name)
+            {
+                Some(at) => at,
+                None => {
+                    self.program.forges.push(DirForge {
+                        name: forge.name.clone(),
+                        shapes: Vec::new(),
+                        procs: Vec::new(),
+                        binds: Vec::new(),
+                    });
+                    self.program.forges.len() - 1
+                }
+            };
+            for shape in forge.shapes {
+                if self.claim(origin, "shape", &forge.name, &shape.name) {
+                    self.program.forges[at].shapes.push(shape);
+                }
+            }
+            for proc_ in forge.procs {
+                if self.claim(origin, "proc", &forge.name, &proc_.name) {
+                    self.program.forges[at].procs.push(proc_);
+                }
+            }
+            self.program.forges[at].binds.extend(forge.binds);
+        }
+    }
+
+    /// Resolve calls across forges and return the linked program.
+    pub fn finish(mut self) -> Result<DirProgram, DvmError> {
+        let mut qualify = Vec::new();
+        for (f, forge) in self.program.forges.iter().enumerate() {
+            for (p, proc_) in forge.procs.iter().enumerate() {
+                for (index, stmt) in proc_.body.iter().enumerate() {
+                    let DirStmt::Call { callee, .. } = stmt else {
+                        continue;
+                    };
+                    let at = format!("{}::{}#{index}", forge.name, proc_.name);
+                    match self.program.resolve_proc(Some(f), callee) {
+                        Ok(target) if target.0 != f && !callee.contains("::") => {
+                            qualify.push((f, p, index, self.program.qualified_name(target)));
+                        }
+                        Ok(_) => {}
+                        Err(ResolveError::Unknown) => self
+                            .errors
+                            .push(format!("{at}: unresolved call to '{callee}'")),
+                        Err(ResolveError::Ambiguous(procs)) => self.errors.push(format!(
+                            "{at}: call to '{callee}' is ambiguous ({})",
+                            procs.join(", ")
+                        )),
+                    }
+                }
+            }
+        }
+        if !self.errors.is_empty() {
+            return Err(DvmError::DirLoad(format!(
+                "link failed: {}",
+                self.errors.join("; ")
+            )));
+        }
+
+        for (f, p, index, name) in qualify {
+            if let DirStmt::Call { callee, .. } = &mut self.program.forges[f].procs[p].body[index] {
+                *callee = name;
+            }
+        }
+        Ok(self.program)
+    }
+
+    /// Record `origin` as the declarer of `forge::name`; false (and a conflict)
+    /// if another artifact already declared it.
+    fn claim(&mut self, origin: &str, kind: &str, forge: &str, name: &str) -> bool {
+        let key = format!("{kind} {forge}::{name}");
+        match self.origins.get(&key) {
+            Some((added, first)) if *added != self.added => {
+                self.errors.push(format!(
+                    "{kind} '{forge}::{name}' is declared in both {first} and {origin}"
+                ));
+                false
+            }
+            _ => {
+                self.origins.insert(key, (self.added, origin.to_string()));
+                true
+            }
+        }
+    }
+}
+
+/// Link `(origin, program)` artifacts in order.
+pub fn link(
+    artifacts: impl IntoIterator<Item = (String, DirProgram)>,
+) -> Result<DirProgram, DvmError> {
+    let mut linker = Linker::new();
+    for (origin, program) in artifacts {
+        linker.add(&origin, program);
+    }
+    linker.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dir::asm, Dvm, DvmConfig, DvmError, Value};
+
+    const APP: &str = "forge app {\n  proc K main() -> Int {\n    call n = helper()\n    \
+                       call m = twice(n)\n    return m\n  }\n\n  \
+                       proc K helper() -> Int {\n    return 4\n  }\n}\n";
+    const LIB: &str = "forge lib {\n  proc K twice(n: Int) -> Int {\n    call h = helper()\n    \
+                       return n Mul h\n  }\n\n  proc K helper() -> Int {\n    return 2\n  }\n\n  \
+                       proc K main() -> Int {\n    return 0\n  }\n}\n";
+
+    fn artifact(origin: &str, source: &str) -> (String, DirProgram) {
+        (origin.to_string(), asm::parse(source).unwrap())
+    }
+
+    fn callees(program: &DirProgram, proc_: &str) -> Vec<String> {
+        program
+            .find_proc(proc_)
+            .unwrap()
+            .body
+            .iter()
+            .filter_map(|stmt| match stmt {
+                DirStmt::Call { callee, .. } => Some(callee.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn calls_resolve_in_their_own_forge_first() {
+        let program = link([artifact("app.dasm", APP), artifact("lib.dasm", LIB)]).unwrap();
+        assert_eq!(callees(&program, "app::main"), ["helper", "lib::twice"]);
+        assert_eq!(callees(&program, "lib::twice"), ["helper"]);
+
+        let dvm = Dvm::new(DvmConfig::default());
+        let run = |entry: &str| dvm.run_entrypoint(&program, entry);
+        assert_eq!(run("app::main").unwrap().returned, Some(Value::Int(8)));
+        assert_eq!(run("lib::main").unwrap().returned, Some(Value::Int(0)));
+        assert_eq!(
+            run("main").unwrap_err(),
+            DvmError::EntrypointNotFound(
+                "main is ambiguous (app::main, lib::main); qualify it as forge::proc".into()
+            )
+        );
+        assert_eq!(
+            run("twice::main").unwrap_err().to_string(),
+            "entrypoint not found: twice::main"
+        );
+    }
+
+    #[test]
+    fn conflicts_and_unresolved_calls_are_reported_together() {
+        let extra = "forge lib {\n  proc K helper() -> Int {\n    call x = missing()\n    \
+                     return 1\n  }\n}\n";
+        let third = "forge other {\n  proc K run() -> Int {\n    call h = helper()\n    \
+                     return h\n  }\n}\n";
+        let err = link([
+            artifact("app.dasm", APP),
+            artifact("lib.dasm", LIB),
+            artifact("extra.dasm", extra),
+            artifact("other.dasm", third),
+        ])
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "DIR load error: link failed: \
+             proc 'lib::helper' is declared in both lib.dasm and extra.dasm; \
+             other::run#0: call to 'helper' is ambiguous (app::helper, lib::helper)"
+        );
+
+        let mut twice = Linker::new();
+        twice.add("lib.dasm", asm::parse(LIB).unwrap());
+        twice.add("lib.dasm", asm::parse(LIB).unwrap());
+        let err = twice.finish().unwrap_err().to_string();
+        assert!(
+            err.contains("proc 'lib::twice' is declared in both lib.dasm and lib.dasm"),
+            "{err}"
+        );
+
+        let mut linker = Linker::new();
+        linker.add("extra.dasm", asm::parse(extra).unwrap());
+        assert_eq!(
+            linker.finish().unwrap_err().to_string(),
+            "DIR load error: link failed: lib::helper#0: unresolved call to 'missing'"
+        );
+    }
+}
@@ -0,0 +1,233 @@
+//! Minimal DIR model as currently produced by the Dust compiler.
+//! This mirrors the `dust_dir` crate structures found in the compiler repository.
+//!
+//! NOTE: In this repository, DIR types are defined locally to keep dustrun standalone.
+
+pub mod asm;
+pub mod binary;
+pub mod format;
+pub mod link;
+pub mod validate;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DirProgram {
+    pub forges: Vec<DirForge>,
+}
+
+impl DirProgram {
+    /// Find a proc by `forge::proc` or bare name (see `locate_proc`).
+    pub fn find_proc(&self, name: &str) -> Option<&DirProc> {
+        let (f, p) = self.locate_proc(name)?;
+        Some(&self.forges[f].procs[p])
+    }
+
+    /// Locate a proc as `(forge index, proc index)`: exactly for `forge::proc`,
+    /// and as the first match in forge order for a bare name.
+    pub fn locate_proc(&self, name: &str) -> Option<(usize, usize)> {
+        if let Some((forge, proc_)) = name.split_once("::") {
+            let f = self.forges.iter().position(|f| f.name == forge)?;
+            let p = self.forges[f].procs.iter().position(|p| p.name == proc_)?;
+            return Some((f, p));
+        }
+        self.forges.iter().enumerate().find_map(|(f, forge)| {
+            let p = forge.procs.iter().position(|p| p.name == name)?;
+            Some((f, p))
+        })
+    }
+
+    /// Resolve a proc reference made from forge `from` (`None` for an
+    /// entrypoint). `forge::proc` names resolve exactly; a bare name resolves in
+    /// `from` first, then to the only forge declaring it.
+    pub fn resolve_proc(
+        &self,
+        from: Option<usize>,
+        name: &str,
+    ) -> Result<(usize, usize), ResolveError> {
+        if name.contains("::") {
+            return self.locate_proc(name).ok_or(ResolveError::Unknown);
+        }
+        let in_forge = |f: usize| self.forges[f].procs.iter().position(|p| p.name == name);
+        if let Some((f, Some(p))) = from.map(|f| (f, in_forge(f))) {
+            return Ok((f, p));
+        }
+        let found: Vec<_> = (0..self.forges.len())
+            .filter_map(|f| Some((f, in_forge(f)?)))
+            .collect();
+        match found.as_slice() {
+            [] => Err(ResolveError::Unknown),
+            [at] => Ok(*at),
+            _ => Err(ResolveError::Ambiguous(
+                found.iter().map(|&at| self.qualified_name(at)).collect(),
+            )),
+        }
+    }
+
+    /// `forge::proc` name of a located proc.
+    pub fn qualified_name(&self, (f, p): (usize, usize)) -> String {
+        format!("{}::{}", self.forges[f].name, self.forges[f].procs[p].name)
+    }
+}
+
+/// Why a proc reference did not resolve.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolveError {
+    Unknown,
+
+    /// A bare name declared by several forges (their qualified names).
+    Ambiguous(Vec<String>),
+}
+
+impl ResolveError {
+    /// Describe the failed reference to `name`: the name itself when unknown,
+    /// the candidates and a hint when ambiguous.
+    pub fn describe(&self, name: &str) -> String {
+        match self {
+            ResolveError::Unknown => name.to_string(),
+            ResolveError::Ambiguous(procs) => format!(
+                "{name} is ambiguous ({}); qualify it as forge::proc",
+                procs.join(", ")
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DirForge {
+    pub name: String,
+    pub shapes: Vec<DirShape>,
+    pub procs: Vec<DirProc>,
+    pub binds: Vec<DirBind>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DirShape {
+    pub name: String,
+    pub fields: Vec<DirField>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DirField {
+    pub name: String,
+    pub ty: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DirProc {
+    pub regime: String, // "K" | "Q" | "Φ"
+    pub name: String,
+    pub params: Vec<DirParam>,
+    pub uses: Vec<DirUses>,
+    pub ret: Option<String>,
+    pub qualifiers: Vec<String>,
+    pub body: Vec<DirStmt>, // v0.1 lowered statements
+
+    /// Source location of the proc's declaration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span: Option<DirSpan>,
+
+    /// Source locations of `body` statements, by index (empty, or one per statement).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub body_spans: Vec<Option<DirSpan>>,
+}
+
+impl DirProc {
+    /// Source location of statement `index`, falling back to the proc's own.
+    pub fn stmt_span(&self, index: usize) -> Option<&DirSpan> {
+        match self.body_spans.get(index) {
+            Some(Some(span)) => Some(span),
+            _ => self.span.as_ref(),
+        }
+    }
+}
+
+/// A position in DPL source (1-based line and column).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DirSpan {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl std::fmt::Display for DirSpan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DirParam {
+    pub name: String,
+    pub ty: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DirUses {
+    pub resource: String,
+    pub args: Vec<(String, DirLit)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum DirLit {
+    Int(i64),
+    Bool(bool),
+    String(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum DirStmt {
+    Let {
+        name: String,
+        expr: String,
+    },
+    Constrain {
+        predicate: String,
+    },
+    Prove {
+        name: String,
+        from: String,
+    },
+    Effect {
+        kind: String,
+        payload: String,
+    },
+    Return {
+        expr: String,
+    },
+
+    /// Call another proc: `args` are expressions bound positionally to the
+    /// callee's params; the returned value (if any) is bound to `name`.
+    Call {
+        #[serde(default)]
+        name: Option<String>,
+        callee: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DirBind {
+    pub source: String,
+    pub target: String,
+    pub contract: Vec<DirClause>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DirClause {
+    pub key: String,
+    pub op: String,
+    pub value: String,
+}
@@ -0,0 +1,429 @@
+//! Static DIR validation: structure, name resolution and well-formedness.
+//!
+//! `check` walks the whole program and returns every issue it finds, each
+//! located at a forge, proc or statement (`core::main#2`):
+//! - empty or duplicate forge / proc / shape names, duplicate shape fields;
+//! - unknown regimes and field / param types (builtins or declared shapes,
+//!   unqualified within the forge or as `forge::Shape`);
+//! - identifiers used before any `Let` / `Call` / `Prove` (or param) binds them;
+//! - statements after a `Return`, and `Prove` names that shadow a binding;
+//! - calls to unknown procs, arity mismatches and illegal regime transitions.
+//!
+//! Expressions are read the way the interpreter reads them, so the arguments of
+//! regime intrinsics (`q_alloc(QBit)`, `phi_unknown(Int, 0, 9)`) are not names.
+
+use super::{DirForge, DirProc, DirProgram, DirStmt, ResolveError};
+use crate::{expr, regime::check_transition, DvmError};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Types every program may use without declaring a shape.
+pub const BUILTIN_TYPES: &[&str] = &["Int", "Bool", "String", "Unit", "QBit", "PhiWitness"];
+
+/// How serious a finding is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The program is rejected, or faults when run.
+    Error,
+
+    /// The program runs as written, but likely not as intended.
+    Warning,
+}
+
+/// One validation finding and where it was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirIssue {
+    pub forge: Option<String>,
+    pub proc: Option<String>,
+    pub index: Option<usize>,
+    pub message: String,
+    pub severity: Severity,
+}
+
+impl fmt::Display for DirIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(forge) = &self.forge else {
+            return write!(f, "{}", self.message);
+        };
+        write!(f, "{forge}")?;
+        if let Some(proc_) = &self.proc {
+            write!(f, "::{proc_}")?;
+        }
+        if let Some(index) = self.index {
+            write!(f, "#{index}")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Every issue in `program`, in program order.
+pub fn check(program: &DirProgram) -> Vec<DirIssue> {
+    let mut issues = Vec::new();
+    if program.forges.is_empty() {
+        issues.push(DirIssue {
+            forge: None,
+            proc: None,
+            index: None,
+            message: "program has no forges".into(),
+            severity: Severity::Error,
+        });
+    }
+    let mut forges = HashSet::new();
+    for (index, forge) in program.forges.iter().enumerate() {
+        let mut at = Issues {
+            forge,
+            index,
+            out: &mut issues,
+        };
+        if forge.name.trim().is_empty() {
+            at.program("forge name is empty".into());
+        } else if !forges.insert(forge.name.as_str()) {
+            at.forge(format!("duplicate forge '{}'", forge.name));
+        }
+        check_shapes(program, &mut at);
+        let mut procs = HashSet::new();
+        for proc_ in &forge.procs {
+            if proc_.name.trim().is_empty() {
+                at.forge("proc name is empty".into());
+                continue;
+            }
+            if !procs.insert(proc_.name.as_str()) {
+                at.forge(format!("duplicate proc '{}'", proc_.name));
+            }
+            check_proc(program, proc_, &mut at);
+        }
+    }
+    issues
+}
+
+/// `check`, as a single `DirValidate` error listing every issue.
+pub fn validate(program: &DirProgram) -> Result<(), DvmError> {
+    let issues = check(program);
+    match issues.as_slice() {
+        [] => Ok(()),
+        [issue] => Err(DvmError::DirValidate(issue.to_string())),
+        _ => Err(DvmError::DirValidate(format!(
+            "{} issues: {}",
+            issues.len(),
+            issues
+                .iter()
+                .map(DirIssue::to_string)
+                .collect::<Vec<_>>()
+                .join("; ")
+        ))),
+    }
+}
+
+/// Whether `ty` names a builtin type or a shape visible from `forge`.
+pub fn type_known(program: &DirProgram, forge: &DirForge, ty: &str) -> bool {
+    let has_shape = |forge: &DirForge, name: &str| forge.shapes.iter().any(|s| s.name == name);
+    if BUILTIN_TYPES.contains(&ty) || has_shape(forge, ty) {
+        return true;
+    }
+    match ty.split_once("::") {
+        Some((f, shape)) => program
+            .forges
+            .iter()
+            .any(|other| other.name == f && has_shape(other, shape)),
+        None => false,
+    }
+}
+
+/// Issue sink for one forge.
+struct Issues<'a> {
+    forge: &'a DirForge,
+    index: usize,
+    out: &'a mut Vec<DirIssue>,
+}
+
+impl Issues<'_> {
+    fn push(&mut self, forge: bool, proc_: Option<&str>, index: Option<usize>, message: String) {
+        self.out.push(DirIssue {
+            forge: forge.then(|| self.forge.name.clone()),
+            proc: proc_.map(str::to_string),
+            index,
+            message,
+            severity: Severity::Error,
+        });
+    }
+
+    fn program(&mut self, message: String) {
+        self.push(false, None, None, message);
+    }
+
+    fn forge(&mut self, message: String) {
+        self.push(true, None, None, message);
+    }
+
+    fn proc(&mut self, proc_: &DirProc, message: String) {
+        self.push(true, Some(&proc_.name), None, message);
+    }
+
+    fn stmt(&mut self, proc_: &DirProc, index: usize, message: String) {
+        self.push(true, Some(&proc_.name), Some(index), message);
+    }
+}
+
+fn check_shapes(program: &DirProgram, at: &mut Issues<'_>) {
+    let mut shapes = HashSet::new();
+    for shape in &at.forge.shapes {
+        if !shapes.insert(shape.name.as_str()) {
+            at.forge(format!("duplicate shape '{}'", shape.name));
+        }
+        let mut fields = HashSet::new();
+        for field in &shape.fields {
+            if !fields.insert(field.name.as_str()) {
+                at.forge(format!(
+                    "shape '{}' has duplicate field '{}'",
+                    shape.name, field.name
+                ));
+            }
+            if !type_known(program, at.forge, &field.ty) {
+                at.forge(format!(
+                    "shape '{}' field '{}' has unknown type '{}'",
+                    shape.name, field.name, field.ty
+                ));
+            }
+        }
+    }
+}
+
+fn check_proc(program: &DirProgram, proc_: &DirProc, at: &mut Issues<'_>) {
+    if !matches!(proc_.regime.as_str(), "K" | "Q" | "Φ") {
+        at.proc(proc_, format!("unknown regime: {}", proc_.regime));
+    }
+    if !proc_.body_spans.is_empty() && proc_.body_spans.len() != proc_.body.len() {
+        at.proc(
+            proc_,
+            format!(
+                "{} body spans for {} statements",
+                proc_.body_spans.len(),
+                proc_.body.len()
+            ),
+        );
+    }
+
+    let mut bound: HashSet<&str> = HashSet::new();
+    for param in &proc_.params {
+        if !type_known(program, at.forge, &param.ty) {
+            at.proc(
+                proc_,
+                format!("param '{}' has unknown type '{}'", param.name, param.ty),
+            );
+        }
+        bound.insert(&param.name);
+    }
+
+    let mut returned = None;
+    for (index, stmt) in proc_.body.iter().enumerate() {
+        if let Some(at_return) = returned.filter(|r| r + 1 == index) {
+            at.stmt(
+                proc_,
+                index,
+                format!("unreachable statement after `return` at #{at_return}"),
+            );
+        }
+
+        match stmt {
+            DirStmt::Let { name, expr: e } => {
+                report_refs(at, proc_, index, &bound, let_refs(&proc_.regime, e));
+                bound.insert(name);
+            }
+            DirStmt::Constrain { predicate } => {
+                report_refs(at, proc_, index, &bound, expr_refs(predicate))
+            }
+            DirStmt::Prove { name, from } => {
+                report_refs(at, proc_, index, &bound, expr_refs(from));
+                if bound.contains(name.as_str()) {
+                    at.stmt(proc_, index, format!("prove '{name}' shadows a binding"));
+                }
+                bound.insert(name);
+            }
+            DirStmt::Effect { payload, .. } => {
+                report_refs(at, proc_, index, &bound, expr_refs(payload))
+            }
+            DirStmt::Return { expr: e } => {
+                report_refs(at, proc_, index, &bound, expr_refs(e));
+                returned.get_or_insert(index);
+            }
+            DirStmt::Call { name, callee, args } => {
+                for arg in args {
+                    report_refs(at, proc_, index, &bound, expr_refs(arg));
+                }
+                check_call(program, proc_, index, callee, args.len(), at);
+                if let Some(name) = name {
+                    bound.insert(name);
+                }
+            }
+        }
+    }
+}
+
+/// Report unbound names among `refs`, or why they could not be read.
+fn report_refs(
+    at: &mut Issues<'_>,
+    proc_: &DirProc,
+    index: usize,
+    bound: &HashSet<&str>,
+    refs: Result<Vec<String>, String>,
+) {
+    match refs {
+        Ok(names) => {
+            for name in names.iter().filter(|n| !bound.contains(n.as_str())) {
+                at.stmt(proc_, index, format!("unbound identifier '{name}'"));
+            }
+        }
+        Err(message) => at.stmt(proc_, index, message),
+    }
+}
+
+/// A call must name an existing proc, match its arity, and respect the regime
+/// transition rules (`regime::call`).
+fn check_call(
+    program: &DirProgram,
+    caller: &DirProc,
+    index: usize,
+    callee: &str,
+    args: usize,
+    at: &mut Issues<'_>,
+) {
+    let target = match program.resolve_proc(Some(at.index), callee) {
+        Ok((f, p)) => &program.forges[f].procs[p],
+        Err(ResolveError::Unknown) => {
+            return at.stmt(caller, index, format!("calls unknown proc '{callee}'"));
+        }
+        Err(ResolveError::Ambiguous(procs)) => {
+            let procs = procs.join(", ");
+            return at.stmt(
+                caller,
+                index,
+                format!("call to '{callee}' is ambiguous ({procs}); qualify it as forge::proc"),
+            );
+        }
+    };
+    if args != target.params.len() {
+        at.stmt(
+            caller,
+            index,
+            format!(
+                "calls '{}' with {args} argument(s), expected {}",
+                target.name,
+                target.params.len()
+            ),
+        );
+    }
+    if let Err(e) = check_transition(caller, target) {
+        at.stmt(caller, index, detail(e));
+    }
+}
+
+/// Names read by a `Let` expression in `regime`, recognizing its intrinsics.
+fn let_refs(regime: &str, e: &str) -> Result<Vec<String>, String> {
+    let intrinsic = |name| call_arg(e, name).filter(|arg| !arg.is_empty());
+    match regime {
+        "Q" if intrinsic("q_alloc").is_some() => Ok(Vec::new()),
+        "Q" => match ["q_move", "q_use", "q_consume"]
+            .into_iter()
+            .find_map(intrinsic)
+        {
+            Some(binding) => Ok(vec![binding.to_string()]),
+            None => expr_refs(e),
+        },
+        "Φ" if call_arg(e, "phi_unknown").is_some() => Ok(Vec::new()),
+        "Φ" => match call_arg(e, "phi_witness") {
+            Some("") => Ok(Vec::new()),
+            Some(label) => expr_refs(label),
+            None => expr_refs(e),
+        },
+        _ => expr_refs(e),
+    }
+}
+
+fn expr_refs(e: &str) -> Result<Vec<String>, String> {
+    let parsed =
+        expr::parse(e).map_err(|err| format!("invalid expression `{e}`: {}", detail(err)))?;
+    Ok(parsed.idents().into_iter().map(str::to_string).collect())
+}
+
+/// The argument of a single-argument call `name(arg)`, trimmed.
+fn call_arg<'e>(e: &'e str, name: &str) -> Option<&'e str> {
+    let inner = e.trim().strip_prefix(name)?.strip_prefix('(')?;
+    Some(inner.strip_suffix(')')?.trim())
+}
+
+/// The message of an error, without its kind prefix.
+pub(crate) fn detail(e: DvmError) -> String {
+    match e {
+        DvmError::DirLoad(s)
+        | DvmError::DirValidate(s)
+        | DvmError::EntrypointNotFound(s)
+        | DvmError::UnsupportedRegime(s)
+        | DvmError::Inadmissible(s)
+        | DvmError::ConstraintFailure(s)
+        | DvmError::EffectViolation(s)
+        | DvmError::TimeViolation(s)
+        | DvmError::Runtime(s) => s,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dir::asm;
+
+    fn issues(source: &str) -> Vec<String> {
+        check(&asm::parse(source).unwrap())
+            .iter()
+            .map(DirIssue::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn names_resolve_in_statement_order() {
+        assert_eq!(
+            issues(
+                "forge core {\n  proc K main(n: Int) -> Int {\n    let a = n Add later\n    \
+                 let later = 1\n    constrain a Lt later\n    return a\n  }\n}\n"
+            ),
+            ["core::main#0: unbound identifier 'later'"]
+        );
+    }
+
+    #[test]
+    fn intrinsic_arguments_are_not_names() {
+        let source = "forge core {\n  shape P {\n    v: Int\n  }\n\n  \
+                      proc Q measure(p: core::P) -> Int {\n    let a = q_alloc(QBit)\n    \
+                      let b = q_move(a)\n    let u = q_use(c)\n    return 7\n  }\n\n  \
+                      proc Φ pick() -> Int {\n    let x = phi_unknown(Int, 0, 5)\n    \
+                      constrain x Gt 3\n    let w = phi_witness(label)\n    return x\n  }\n}\n";
+        assert_eq!(
+            issues(source),
+            [
+                "core::measure#2: unbound identifier 'c'",
+                "core::pick#2: unbound identifier 'label'"
+            ]
+        );
+    }
+
+    #[test]
+    fn issues_are_reported_together() {
+        let mut program =
+            asm::parse("forge core {\n  proc K main() -> Int {\n    return 1 Add\n  }\n}\n")
+                .unwrap();
+        program.forges.push(program.forges[0].clone());
+        program.forges[1].procs[0].regime = "X".into();
+        assert_eq!(
+            validate(&program).unwrap_err().to_string(),
+            "DIR validation error: 4 issues: \
+             core::main#0: invalid expression `1 Add`: unexpected token in expression: Eof; \
+             core: duplicate forge 'core'; core::main: unknown regime: X; \
+             core::main#0: invalid expression `1 Add`: unexpected token in expression: Eof"
+        );
+        assert_eq!(
+            validate(&DirProgram { forges: Vec::new() })
+                .unwrap_err()
+                .to_string(),
+            "DIR validation error: program has no forges"
+        );
+    }
+}
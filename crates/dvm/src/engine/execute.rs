@@ -0,0 +1,611 @@
+//! The statement interpreter shared by every regime.
+//!
+//! A run is an explicit stack of frames, one per active proc. `Machine::step`
+//! executes exactly one statement of the innermost frame; regime-specific
+//! behaviour (intrinsics, constraint checks, returns) is supplied by
+//! `RegimeSemantics` (see `semantics.rs`).
+//!
+//! Tick rule: every completed statement except `Return` advances logical time by
+//! one. A `Call` completes, and ticks, when its callee returns.
+
+use super::observer::{ExecutionObserver, QTransition, StmtLocation};
+use super::semantics::{is_non_existence, semantics_for, Returned};
+use super::steps::StepRecorder;
+use super::{DvmConfig, DvmFault, DvmOutcome};
+use crate::{
+    dir::{DirStmt, ResolveError},
+    effects::{EffectLog, EffectMode},
+    expr,
+    regime::{PhiWitnessBuilder, QState, MAX_CALL_DEPTH},
+    time::TimeState,
+    DirProc, DirProgram, DvmError, Value,
+};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+
+pub(crate) type Observers = RefCell<Vec<Box<dyn ExecutionObserver>>>;
+
+/// Execution state of one run.
+pub(crate) struct Machine<'p> {
+    pub(crate) program: &'p DirProgram,
+    effect_mode: EffectMode,
+    trace: bool,
+
+    pub(crate) effects: EffectLog,
+    pub(crate) time: TimeState,
+    pub(crate) q: QState,
+    pub(crate) witnesses: PhiWitnessBuilder,
+
+    /// Active frames, innermost last.
+    frames: Vec<Frame>,
+
+    /// Set once the entry frame has returned.
+    returned: Option<Option<Value>>,
+
+    observers: &'p Observers,
+
+    /// Step records, when `DvmConfig::steps` is set.
+    steps: Option<RefCell<StepRecorder>>,
+}
+
+/// One active proc invocation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Frame {
+    pub(crate) forge: usize,
+    pub(crate) proc: usize,
+
+    /// Body index of the next statement.
+    pub(crate) pc: usize,
+
+    pub(crate) env: IndexMap<String, Value>,
+
+    /// Resolved Φ assignment (empty outside the Φ regime).
+    pub(crate) assignment: IndexMap<String, Value>,
+
+    /// Param bindings a Φ frame was resolved under (empty outside the Φ regime).
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub(crate) params: IndexMap<String, Value>,
+
+    /// How to hand the result back to the caller (`None` for the entry frame).
+    call: Option<CallSite>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CallSite {
+    /// Caller binding receiving the result.
+    name: Option<String>,
+
+    /// Whether the callee runs in its own Q frame.
+    q_frame: bool,
+}
+
+/// Whether the run can make progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Step {
+    Running,
+    Finished,
+}
+
+/// What a statement asks the interpreter to do next.
+enum Flow {
+    Next,
+    Enter(Box<Frame>),
+    Return(Option<Returned>),
+}
+
+/// The serializable part of a machine between two statements (see `DvmSnapshot`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct MachineState {
+    pub(crate) effect_mode: EffectMode,
+    pub(crate) effects: EffectLog,
+    pub(crate) time: TimeState,
+    q: QState,
+    witnesses: PhiWitnessBuilder,
+    pub(crate) frames: Vec<Frame>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    steps: Option<StepRecorder>,
+}
+
+impl<'p> Machine<'p> {
+    /// Prepare a run of the proc at `entry` (`(forge index, proc index)`).
+    pub(crate) fn start(
+        program: &'p DirProgram,
+        cfg: &DvmConfig,
+        observers: &'p Observers,
+        entry: (usize, usize),
+    ) -> Result<Self, DvmFault> {
+        let mut m = Self {
+            program,
+            effect_mode: cfg.effect_mode,
+            trace: cfg.trace,
+            effects: EffectLog::default(),
+            time: TimeState::default(),
+            q: QState::new(),
+            witnesses: PhiWitnessBuilder::new(),
+            frames: Vec::new(),
+            returned: None,
+            observers,
+            steps: cfg.steps.then(RefCell::default),
+        };
+        let frame = m.enter(entry, IndexMap::new(), None, 0).map_err(|f| {
+            let mut fault = m.fail(f, None);
+            fault.span = program.forges[entry.0].procs[entry.1]
+                .span
+                .clone()
+                .map(Box::new);
+            fault
+        })?;
+        m.settle(frame)?;
+        Ok(m)
+    }
+
+    /// Continue a run from captured state. Logging follows `cfg.trace`; everything
+    /// else (effect mode, step recording) comes from the state.
+    pub(crate) fn restore(
+        program: &'p DirProgram,
+        cfg: &DvmConfig,
+        observers: &'p Observers,
+        state: MachineState,
+    ) -> Result<Self, DvmFault> {
+        let invalid = |msg: String| DvmError::Runtime(format!("invalid snapshot: {msg}"));
+
+        if state.frames.is_empty() {
+            return Err(invalid("no active frames".into()).into());
+        }
+        for (depth, frame) in state.frames.iter().enumerate() {
+            let proc_ = program
+                .forges
+                .get(frame.forge)
+                .and_then(|f| f.procs.get(frame.proc))
+                .ok_or_else(|| invalid(format!("frame {depth} names no proc")))?;
+            if frame.pc >= proc_.body.len() {
+                return Err(invalid(format!(
+                    "frame {depth} is past the end of proc '{}'",
+                    proc_.name
+                ))
+                .into());
+            }
+            if frame.call.is_some() != (depth > 0) {
+                return Err(invalid(format!("frame {depth} has an inconsistent call site")).into());
+            }
+        }
+
+        Ok(Self {
+            program,
+            effect_mode: state.effect_mode,
+            trace: cfg.trace,
+            effects: state.effects,
+            time: state.time,
+            q: state.q,
+            witnesses: state.witnesses,
+            frames: state.frames,
+            returned: None,
+            observers,
+            steps: state.steps.map(RefCell::new),
+        })
+    }
+
+    /// Capture the state of an unfinished run.
+    pub(crate) fn capture(&self) -> MachineState {
+        MachineState {
+            effect_mode: self.effect_mode,
+            effects: self.effects.clone(),
+            time: self.time.clone(),
+            q: self.q.clone(),
+            witnesses: self.witnesses.clone(),
+            frames: self.frames.clone(),
+            steps: self.steps.as_ref().map(|r| r.borrow().clone()),
+        }
+    }
+
+    /// Step until the entry frame returns.
+    pub(crate) fn run(&mut self) -> Result<(), DvmFault> {
+        while self.step()? == Step::Running {}
+        Ok(())
+    }
+
+    /// Execute up to `statements` statements.
+    pub(crate) fn run_for(&mut self, statements: usize) -> Result<Step, DvmFault> {
+        for _ in 0..statements {
+            if self.step()? == Step::Finished {
+                return Ok(Step::Finished);
+            }
+        }
+        Ok(if self.frames.is_empty() {
+            Step::Finished
+        } else {
+            Step::Running
+        })
+    }
+
+    pub(crate) fn into_outcome(mut self) -> DvmOutcome {
+        self.take_outcome()
+    }
+
+    /// The outcome of a finished run, leaving the machine empty.
+    pub(crate) fn take_outcome(&mut self) -> DvmOutcome {
+        DvmOutcome {
+            returned: self.returned.take().flatten(),
+            effects: std::mem::take(&mut self.effects),
+            time: std::mem::take(&mut self.time),
+            steps: self
+                .steps
+                .take()
+                .map(|r| r.into_inner().steps)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The entrypoint's result, once it has returned.
+    pub(crate) fn returned(&self) -> Option<&Value> {
+        self.returned.as_ref()?.as_ref()
+    }
+
+    /// Active frames, innermost last.
+    pub(crate) fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// The proc a frame executes.
+    pub(crate) fn proc_of(&self, frame: &Frame) -> &'p DirProc {
+        &self.program.forges[frame.forge].procs[frame.proc]
+    }
+
+    /// Location of a frame's current statement.
+    pub(crate) fn location(&self, frame: &Frame) -> StmtLocation<'p> {
+        StmtLocation {
+            forge: &self.program.forges[frame.forge].name,
+            proc: &self.proc_of(frame).name,
+            index: frame.pc,
+        }
+    }
+
+    pub(crate) fn observed(&self) -> bool {
+        self.steps.is_some() || !self.observers.borrow().is_empty()
+    }
+
+    pub(crate) fn notify(&self, mut f: impl FnMut(&mut dyn ExecutionObserver)) {
+        if let Some(recorder) = &self.steps {
+            f(&mut *recorder.borrow_mut());
+        }
+        for o in self.observers.borrow_mut().iter_mut() {
+            f(o.as_mut());
+        }
+    }
+
+    /// Write a binding in `frame`.
+    pub(crate) fn bind(&self, frame: &mut Frame, name: &str, v: Value) {
+        if self.observed() {
+            let loc = self.location(frame);
+            self.notify(|o| o.on_binding(loc, name, &v));
+        }
+        frame.env.insert(name.to_string(), v);
+    }
+
+    /// Report a Q transition at `frame`'s current statement.
+    pub(crate) fn q_transition(&self, frame: &Frame, transition: QTransition) {
+        if self.observed() {
+            let loc = self.location(frame);
+            let state = self.q.snapshot();
+            self.notify(|o| o.on_q_transition(loc, &transition, &state));
+        }
+    }
+
+    /// Execute one statement of the innermost frame.
+    ///
+    /// On failure the faulting frame stays on the stack and the fault carries the
+    /// effects and time accumulated so far.
+    pub(crate) fn step(&mut self) -> Result<Step, DvmFault> {
+        let Some(mut frame) = self.frames.pop() else {
+            return Ok(Step::Finished);
+        };
+
+        match self.exec_stmt(&mut frame) {
+            Ok(Flow::Next) => {
+                let loc = self.location(&frame);
+                frame.pc += 1;
+                self.time.step();
+                self.notify(|o| o.on_stmt_end(loc, self.time.tick));
+                self.settle(frame)
+            }
+            Ok(Flow::Enter(callee)) => {
+                self.frames.push(frame);
+                self.settle(*callee)
+            }
+            Ok(Flow::Return(ret)) => {
+                let loc = self.location(&frame);
+                self.notify(|o| o.on_stmt_end(loc, self.time.tick));
+                self.leave(frame, ret)
+            }
+            Err(fault) => {
+                let fault = self.fail(fault, Some(&frame));
+                self.frames.push(frame);
+                Err(fault)
+            }
+        }
+    }
+
+    /// Push a frame that still has statements to run, or return from it.
+    fn settle(&mut self, frame: Frame) -> Result<Step, DvmFault> {
+        if frame.pc < self.proc_of(&frame).body.len() {
+            self.frames.push(frame);
+            Ok(Step::Running)
+        } else {
+            self.leave(frame, None)
+        }
+    }
+
+    /// Pop a finished frame, delivering its result to the caller.
+    fn leave(&mut self, frame: Frame, ret: Option<Returned>) -> Result<Step, DvmFault> {
+        let depth = self.frames.len();
+        let (forge, proc_) = (&self.program.forges[frame.forge].name, self.proc_of(&frame));
+        self.notify(|o| o.on_frame_exit(forge, &proc_.name, depth));
+
+        let Some(site) = &frame.call else {
+            self.returned = Some(ret.map(Returned::into_value));
+            return Ok(Step::Finished);
+        };
+
+        let mut caller = self
+            .frames
+            .pop()
+            .ok_or_else(|| DvmError::Runtime("call site without caller frame".into()))?;
+
+        if let Err(fault) = self.deliver(&mut caller, &frame, site, ret) {
+            let fault = self.fail(fault, Some(&caller));
+            self.frames.push(caller);
+            return Err(fault);
+        }
+
+        // The caller's `Call` statement is now complete.
+        let loc = self.location(&caller);
+        caller.pc += 1;
+        self.time.step();
+        self.notify(|o| o.on_stmt_end(loc, self.time.tick));
+        self.settle(caller)
+    }
+
+    fn exec_stmt(&mut self, frame: &mut Frame) -> Result<Flow, DvmFault> {
+        let proc_ = self.proc_of(frame);
+        let stmt = &proc_.body[frame.pc];
+        let sem = semantics_for(&proc_.regime)?;
+
+        if self.trace {
+            log::info!("tick={} stmt={:?}", self.time.tick.0, stmt);
+        }
+        let loc = self.location(frame);
+        self.notify(|o| o.on_stmt_start(loc, stmt, self.time.tick));
+
+        match stmt {
+            DirStmt::Let { name, expr: e } => {
+                let v = match sem.intrinsic(self, frame, name, e)? {
+                    Some(v) => v,
+                    None => expr::eval(e, &frame.env)?,
+                };
+                self.bind(frame, name, v);
+            }
+            DirStmt::Constrain { predicate } => {
+                sem.constrain(self, frame, predicate)?;
+            }
+            DirStmt::Prove { name, from } => {
+                let v = sem.prove(self, frame, from)?;
+                self.bind(frame, name, v);
+            }
+            DirStmt::Effect { kind, payload } => {
+                let rendered = render_payload(payload, &frame.env)?;
+                self.effects.push(kind.clone(), rendered);
+                if let Some(event) = self.effects.events.last() {
+                    self.notify(|o| o.on_effect(loc, event));
+                }
+                match self.effect_mode {
+                    EffectMode::Simulate => {}
+                    EffectMode::Realize => {}
+                }
+            }
+            DirStmt::Return { expr: e } => {
+                return Ok(Flow::Return(Some(sem.ret(self, frame, e)?)));
+            }
+            DirStmt::Call { name, callee, args } => {
+                return self.call(frame, name.as_deref(), callee, args);
+            }
+        }
+
+        Ok(Flow::Next)
+    }
+
+    /// Start a `Call` (see `regime::call` for the rules).
+    fn call(
+        &mut self,
+        caller: &mut Frame,
+        name: Option<&str>,
+        callee_name: &str,
+        args: &[String],
+    ) -> Result<Flow, DvmFault> {
+        let at = self
+            .program
+            .resolve_proc(Some(caller.forge), callee_name)
+            .map_err(|e| match e {
+                ResolveError::Unknown => {
+                    DvmError::Runtime(format!("call to unknown proc: {callee_name}"))
+                }
+                ResolveError::Ambiguous(procs) => DvmError::Runtime(format!(
+                    "call to ambiguous proc: {callee_name} ({})",
+                    procs.join(", ")
+                )),
+            })?;
+        let callee = &self.program.forges[at.0].procs[at.1];
+        let caller_proc = self.proc_of(caller);
+
+        if args.len() != callee.params.len() {
+            return Err(DvmError::Runtime(format!(
+                "call to {} expects {} argument(s), got {}",
+                callee.name,
+                callee.params.len(),
+                args.len()
+            ))
+            .into());
+        }
+        // Active frames: the suspended ones plus the caller.
+        if self.frames.len() + 1 >= MAX_CALL_DEPTH {
+            return Err(DvmError::Runtime(format!(
+                "call depth limit exceeded ({MAX_CALL_DEPTH}) calling {}",
+                callee.name
+            ))
+            .into());
+        }
+
+        // Bind arguments: linear bindings are moved, everything else is evaluated.
+        let caller_sem = semantics_for(&caller_proc.regime)?;
+        let mut env = IndexMap::new();
+        let mut transfers = Vec::new();
+        for (param, arg) in callee.params.iter().zip(args) {
+            let arg = arg.trim();
+            if caller_sem.is_linear(self, arg) {
+                if callee.regime != "Q" {
+                    return Err(DvmError::Inadmissible(format!(
+                        "Q binding cannot cross into {} regime: {arg} (call {})",
+                        callee.regime, callee.name
+                    ))
+                    .into());
+                }
+                transfers.push((arg.to_string(), param.name.clone()));
+                env.insert(param.name.clone(), Value::Unit);
+            } else {
+                env.insert(param.name.clone(), expr::eval(arg, &caller.env)?);
+            }
+        }
+
+        let q_frame = callee.regime == "Q";
+        if q_frame {
+            self.q.enter_call(&transfers)?;
+            self.q_transition(caller, QTransition::CallEnter { transfers });
+        }
+
+        let site = CallSite {
+            name: name.map(str::to_string),
+            q_frame,
+        };
+        let depth = self.frames.len() + 1;
+        match self.enter(at, env, Some(site), depth) {
+            Ok(frame) => Ok(Flow::Enter(Box::new(frame))),
+            // K observes Φ non-existence as a value: the NonExistent witness.
+            Err(fault) if caller_proc.regime == "K" && is_non_existence(&fault) => {
+                if let (Some(dst), Some(w)) = (name, fault.witness.as_deref()) {
+                    self.bind(caller, dst, w.to_value());
+                }
+                Ok(Flow::Next)
+            }
+            Err(fault) => Err(fault),
+        }
+    }
+
+    /// Create a frame for the proc at `at`, running its regime's entry hook.
+    fn enter(
+        &mut self,
+        at: (usize, usize),
+        env: IndexMap<String, Value>,
+        call: Option<CallSite>,
+        depth: usize,
+    ) -> Result<Frame, DvmFault> {
+        let mut frame = Frame {
+            forge: at.0,
+            proc: at.1,
+            pc: 0,
+            env,
+            assignment: IndexMap::new(),
+            params: IndexMap::new(),
+            call,
+        };
+        let proc_ = self.proc_of(&frame);
+        let sem = semantics_for(&proc_.regime)?;
+        sem.enter(self, &mut frame)?;
+
+        let forge = &self.program.forges[at.0].name;
+        self.notify(|o| o.on_frame_enter(forge, &proc_.name, depth));
+        Ok(frame)
+    }
+
+    /// Bind a callee's result in its caller.
+    fn deliver(
+        &mut self,
+        caller: &mut Frame,
+        callee: &Frame,
+        site: &CallSite,
+        ret: Option<Returned>,
+    ) -> Result<(), DvmFault> {
+        let caller_regime = &self.proc_of(caller).regime;
+        let callee_name = &self.proc_of(callee).name;
+
+        match ret {
+            Some(Returned::QBinding(src)) => {
+                if caller_regime != "Q" {
+                    return Err(DvmError::Inadmissible(format!(
+                        "Q resource cannot be returned into {caller_regime} regime: {src} (call {callee_name})"
+                    ))
+                    .into());
+                }
+                let dst = site.name.as_deref().ok_or_else(|| {
+                    DvmError::Inadmissible(format!(
+                        "Q call result discarded: {callee_name} returns a linear resource"
+                    ))
+                })?;
+                self.q.exit_call(Some((&src, dst)))?;
+                self.q_transition(
+                    caller,
+                    QTransition::CallExit {
+                        returned: Some((src.clone(), dst.to_string())),
+                    },
+                );
+                self.bind(caller, dst, Value::Unit);
+            }
+            other => {
+                if site.q_frame {
+                    self.q.exit_call(None)?;
+                    self.q_transition(caller, QTransition::CallExit { returned: None });
+                }
+                if let Some(dst) = &site.name {
+                    let v = match other {
+                        Some(Returned::Value(v)) => v,
+                        _ => Value::Unit,
+                    };
+                    self.bind(caller, dst, v);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Faults carry the run's effects and time as of the failure, and the source
+    /// span of `frame`'s statement.
+    fn fail(&self, mut fault: DvmFault, frame: Option<&Frame>) -> DvmFault {
+        let loc = frame.map(|f| self.location(f));
+        fault.effects = self.effects.clone();
+        fault.time = self.time.clone();
+        fault.span = frame
+            .and_then(|f| self.proc_of(f).stmt_span(f.pc).cloned())
+            .map(Box::new);
+        self.notify(|o| o.on_fault(loc, &fault));
+        if let Some(recorder) = &self.steps {
+            fault.steps = recorder.borrow().steps.clone();
+        }
+        fault
+    }
+}
+
+fn render_payload(payload_expr: &str, env: &IndexMap<String, Value>) -> Result<String, DvmError> {
+    let v = expr::eval(payload_expr, env)?;
+    Ok(match v {
+        Value::String(s) => s,
+        Value::Int(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Struct { .. } => serde_json::to_string(&v).map_err(|e| {
+            DvmError::Runtime(format!("failed to render struct payload as json: {e}"))
+        })?,
+        Value::Unit => "unit".into(),
+    })
+}
@@ -0,0 +1,306 @@
+//! Execution driver: configuration, outcomes/faults, DIR load + validation, and
+//! the run APIs over the shared interpreter (`execute.rs`, `semantics.rs`).
+
+mod execute;
+mod observer;
+pub(crate) mod semantics;
+mod snapshot;
+mod stepper;
+mod steps;
+
+pub use observer::{ExecutionObserver, QTransition, StmtLocation};
+pub use snapshot::{Checkpoint, DvmSnapshot, SNAPSHOT_VERSION};
+pub use stepper::{StackFrame, StepStatus, Stepper};
+pub use steps::StepRecord;
+
+use crate::{
+    admissibility::Explanation,
+    dir::{format, validate, validate::DirIssue, DirSpan},
+    effects::{EffectLog, EffectMode},
+    regime::{linearity, PhiWitness},
+    time::TimeState,
+    DirProgram, DvmError, Value,
+};
+use execute::{Machine, Observers, Step};
+use std::path::Path;
+
+#[derive(Debug, Clone)]
+pub struct DvmConfig {
+    pub effect_mode: EffectMode,
+    pub trace: bool,
+
+    /// Record a `StepRecord` per executed statement.
+    pub steps: bool,
+}
+
+impl Default for DvmConfig {
+    fn default() -> Self {
+        Self {
+            effect_mode: EffectMode::Simulate,
+            trace: false,
+            steps: false,
+        }
+    }
+}
+
+/// Successful outcome (no refusal/failure).
+#[derive(Debug, Clone)]
+pub struct DvmOutcome {
+    pub returned: Option<Value>,
+    pub effects: EffectLog,
+    pub time: TimeState,
+
+    /// Executed statements (empty unless `DvmConfig::steps` is set).
+    pub steps: Vec<StepRecord>,
+}
+
+/// Fault/refusal with deterministic partial context.
+#[derive(Debug, Clone)]
+pub struct DvmFault {
+    pub error: DvmError,
+    pub effects: EffectLog,
+    pub time: TimeState,
+
+    /// Φ non-existence witness, when the fault is a Φ resolution failure.
+    pub witness: Option<Box<PhiWitness>>,
+
+    /// Why the failing `Constrain` / `Prove` predicate did not hold.
+    pub explanation: Option<Box<Explanation>>,
+
+    /// Statements executed up to and including the faulting one (empty unless
+    /// `DvmConfig::steps` is set).
+    pub steps: Vec<StepRecord>,
+
+    /// Source span of the faulting statement (or proc), when the DIR carries one.
+    pub span: Option<Box<DirSpan>>,
+}
+
+impl DvmFault {
+    pub fn new(error: DvmError, effects: EffectLog, time: TimeState) -> Self {
+        Self {
+            error,
+            effects,
+            time,
+            witness: None,
+            explanation: None,
+            steps: Vec::new(),
+            span: None,
+        }
+    }
+
+    /// Attach the witness attesting this fault.
+    pub fn with_witness(mut self, witness: PhiWitness) -> Self {
+        self.witness = Some(Box::new(witness));
+        self
+    }
+
+    /// Attach the explanation of the failed predicate.
+    pub fn with_explanation(mut self, explanation: Explanation) -> Self {
+        self.explanation = Some(Box::new(explanation));
+        self
+    }
+}
+
+impl From<DvmError> for DvmFault {
+    fn from(error: DvmError) -> Self {
+        DvmFault::new(error, EffectLog::default(), TimeState::default())
+    }
+}
+
+pub struct Dvm {
+    cfg: DvmConfig,
+    observers: Observers,
+}
+
+impl std::fmt::Debug for Dvm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Dvm")
+            .field("cfg", &self.cfg)
+            .field("observers", &self.observers.borrow().len())
+            .finish()
+    }
+}
+
+impl Dvm {
+    pub fn new(cfg: DvmConfig) -> Self {
+        Self {
+            cfg,
+            observers: Observers::default(),
+        }
+    }
+
+    /// Attach an observer notified during every subsequent run.
+    ///
+    /// To read an observer's results afterwards, attach an `Rc<RefCell<T>>` and keep a clone.
+    pub fn attach_observer(&mut self, observer: impl ExecutionObserver + 'static) {
+        self.observers.get_mut().push(Box::new(observer));
+    }
+
+    /// Load a DIR program from JSON bytes (any supported `dir_version`; see
+    /// `dir::format`).
+    pub fn load_dir_json(&self, bytes: &[u8]) -> Result<DirProgram, DvmError> {
+        format::read_json(bytes).map(|artifact| artifact.program)
+    }
+
+    /// Load a DIR program read from `path`: assembly for `*.dasm` files, binary
+    /// DIR by its magic, JSON otherwise (`dir::format::DirEncoding::detect`).
+    pub fn load_dir(&self, path: impl AsRef<Path>, bytes: &[u8]) -> Result<DirProgram, DvmError> {
+        format::read(path, bytes).map(|artifact| artifact.program)
+    }
+
+    /// Validate DIR structure and names (`dir::validate`), reporting every issue
+    /// in one error.
+    pub fn validate_dir(&self, program: &DirProgram) -> Result<(), DvmError> {
+        validate::validate(program)
+    }
+
+    /// Every static issue in `program` without executing it: DIR validation
+    /// followed by Q linearity (`regime::linearity`). Only `Severity::Error`
+    /// issues make the program fail; Q leaks are warnings.
+    pub fn check_dir(&self, program: &DirProgram) -> Vec<DirIssue> {
+        let mut issues = validate::check(program);
+        issues.extend(linearity::check(program));
+        issues
+    }
+
+    /// Compatibility API: prior callers expect `Result<Outcome, DvmError>`.
+    ///
+    /// This now drops partial context on failure. Prefer `run_entrypoint_trace` in new code.
+    pub fn run_entrypoint(
+        &self,
+        program: &DirProgram,
+        entry: &str,
+    ) -> Result<DvmOutcome, DvmError> {
+        match self.run_entrypoint_with_fault(program, entry) {
+            Ok(ok) => Ok(ok),
+            Err(fault) => Err(fault.error),
+        }
+    }
+
+    /// New API: returns a structured fault carrying deterministic partial context.
+    pub fn run_entrypoint_with_fault(
+        &self,
+        program: &DirProgram,
+        entry: &str,
+    ) -> Result<DvmOutcome, DvmFault> {
+        let mut machine = self.start(program, entry)?;
+        machine.run()?;
+        Ok(machine.into_outcome())
+    }
+
+    /// Run at most `statements` statements, pausing with a snapshot if the run
+    /// has not finished.
+    pub fn run_for(
+        &self,
+        program: &DirProgram,
+        entry: &str,
+        statements: usize,
+    ) -> Result<Checkpoint, DvmFault> {
+        let mut machine = self.start(program, entry)?;
+        Self::checkpoint(&mut machine, statements)
+    }
+
+    /// Start a run to be executed one statement at a time.
+    pub fn stepper<'p>(
+        &'p self,
+        program: &'p DirProgram,
+        entry: &str,
+    ) -> Result<Stepper<'p>, DvmFault> {
+        Ok(Stepper::new(self.start(program, entry)?))
+    }
+
+    /// Continue a paused run one statement at a time.
+    pub fn resume_stepper<'p>(
+        &'p self,
+        snapshot: &'p DvmSnapshot,
+    ) -> Result<Stepper<'p>, DvmFault> {
+        Ok(Stepper::new(self.restore(snapshot)?))
+    }
+
+    /// Resume a paused run to completion.
+    ///
+    /// The effect mode and step recording are those of the paused run; attached
+    /// observers see the remaining statements only.
+    pub fn resume(&self, snapshot: &DvmSnapshot) -> Result<DvmOutcome, DvmFault> {
+        let mut machine = self.restore(snapshot)?;
+        machine.run()?;
+        Ok(machine.into_outcome())
+    }
+
+    /// Resume a paused run for at most `statements` statements.
+    pub fn resume_for(
+        &self,
+        snapshot: &DvmSnapshot,
+        statements: usize,
+    ) -> Result<Checkpoint, DvmFault> {
+        let mut machine = self.restore(snapshot)?;
+        Self::checkpoint(&mut machine, statements)
+    }
+
+    /// Trace API counterpart of `resume`.
+    pub fn resume_trace(&self, snapshot: &DvmSnapshot) -> crate::DvmTrace {
+        match self.resume(snapshot) {
+            Ok(ok) => crate::DvmTrace::Success(ok.into()),
+            Err(fault) => crate::DvmTrace::Failure(fault.into()),
+        }
+    }
+
+    fn restore<'p>(&'p self, snapshot: &'p DvmSnapshot) -> Result<Machine<'p>, DvmFault> {
+        // A snapshot is read from a file: check its program as `start` does.
+        self.validate_dir(snapshot.program())
+            .map_err(|e| DvmFault::new(e, EffectLog::default(), TimeState::default()))?;
+        Machine::restore(
+            snapshot.program(),
+            &self.cfg,
+            &self.observers,
+            snapshot.state()?,
+        )
+    }
+
+    fn checkpoint(machine: &mut Machine<'_>, statements: usize) -> Result<Checkpoint, DvmFault> {
+        Ok(match machine.run_for(statements)? {
+            Step::Running => Checkpoint::Paused(Box::new(DvmSnapshot::new(
+                machine.program.clone(),
+                machine.capture(),
+            ))),
+            Step::Finished => Checkpoint::Finished(machine.take_outcome()),
+        })
+    }
+
+    /// Validate the program and enter `entry`.
+    fn start<'p>(&'p self, program: &'p DirProgram, entry: &str) -> Result<Machine<'p>, DvmFault> {
+        // validation failures have no prior context
+        self.validate_dir(program)
+            .map_err(|e| DvmFault::new(e, EffectLog::default(), TimeState::default()))?;
+
+        let entry_at = program.resolve_proc(None, entry).map_err(|e| {
+            DvmFault::new(
+                DvmError::EntrypointNotFound(e.describe(entry)),
+                EffectLog::default(),
+                TimeState::default(),
+            )
+        })?;
+        let proc_ = &program.forges[entry_at.0].procs[entry_at.1];
+
+        if !proc_.params.is_empty() {
+            return Err(DvmFault::new(
+                DvmError::Runtime(format!(
+                    "entrypoint has params in v0.1 host-runner: {}:{}",
+                    proc_.name, proc_.regime
+                )),
+                EffectLog::default(),
+                TimeState::default(),
+            ));
+        }
+
+        Machine::start(program, &self.cfg, &self.observers, entry_at)
+    }
+
+    // Trace API: produce a single trace value for conformance and tooling.
+    pub fn run_entrypoint_trace(&self, program: &DirProgram, entry: &str) -> crate::DvmTrace {
+        match self.run_entrypoint_with_fault(program, entry) {
+            Ok(ok) => crate::DvmTrace::Success(ok.into()),
+            Err(fault) => crate::DvmTrace::Failure(fault.into()),
+        }
+    }
+}
@@ -0,0 +1,222 @@
+//! Execution observers: a stable hook interface for tools embedding the DVM
+//! (profilers, coverage, debuggers).
+//!
+//! Observers are notified synchronously, in execution order, and cannot change
+//! the outcome of a run. Every callback has a no-op default.
+//!
+//! Callbacks run while the `Dvm` is executing: an observer must not start
+//! another run on the same `Dvm`.
+
+use super::DvmFault;
+use crate::{dir::DirStmt, effects::EffectEvent, regime::QSnapshot, time::LogicalTick, Value};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Position of a statement: forge name, proc name, body index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StmtLocation<'a> {
+    pub forge: &'a str,
+    pub proc: &'a str,
+    pub index: usize,
+}
+
+/// A Q-regime state transition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QTransition {
+    Alloc {
+        name: String,
+        ty: String,
+    },
+    Move {
+        from: String,
+        to: String,
+    },
+    Use {
+        name: String,
+    },
+    Consume {
+        name: String,
+    },
+
+    /// Bindings moved into a Q callee: `(caller binding, callee param)`.
+    CallEnter {
+        transfers: Vec<(String, String)>,
+    },
+
+    /// Leaving a Q callee, optionally moving `(callee binding, caller binding)` back.
+    CallExit {
+        returned: Option<(String, String)>,
+    },
+}
+
+pub trait ExecutionObserver {
+    /// A proc frame was entered (`depth` 0 is the entrypoint).
+    fn on_frame_enter(&mut self, _forge: &str, _proc: &str, _depth: usize) {}
+
+    /// A proc frame returned.
+    fn on_frame_exit(&mut self, _forge: &str, _proc: &str, _depth: usize) {}
+
+    /// A statement is about to execute at logical time `tick`.
+    fn on_stmt_start(&mut self, _loc: StmtLocation<'_>, _stmt: &DirStmt, _tick: LogicalTick) {}
+
+    /// A statement completed; `tick` is the logical time after it. A `Call`
+    /// completes when its callee returns.
+    fn on_stmt_end(&mut self, _loc: StmtLocation<'_>, _tick: LogicalTick) {}
+
+    /// A binding was written in the frame executing `loc`.
+    fn on_binding(&mut self, _loc: StmtLocation<'_>, _name: &str, _value: &Value) {}
+
+    /// An effect was recorded.
+    fn on_effect(&mut self, _loc: StmtLocation<'_>, _event: &EffectEvent) {}
+
+    /// Q state changed; `state` is the current frame's state after the transition.
+    fn on_q_transition(
+        &mut self,
+        _loc: StmtLocation<'_>,
+        _transition: &QTransition,
+        _state: &QSnapshot,
+    ) {
+    }
+
+    /// The run faulted at `loc` (`None` if no statement was executing).
+    fn on_fault(&mut self, _loc: Option<StmtLocation<'_>>, _fault: &DvmFault) {}
+}
+
+/// Shared observers let the embedding tool read results after the run.
+impl<T: ExecutionObserver> ExecutionObserver for Rc<RefCell<T>> {
+    fn on_frame_enter(&mut self, forge: &str, proc: &str, depth: usize) {
+        self.borrow_mut().on_frame_enter(forge, proc, depth)
+    }
+    fn on_frame_exit(&mut self, forge: &str, proc: &str, depth: usize) {
+        self.borrow_mut().on_frame_exit(forge, proc, depth)
+    }
+    fn on_stmt_start(&mut self, loc: StmtLocation<'_>, stmt: &DirStmt, tick: LogicalTick) {
+        self.borrow_mut().on_stmt_start(loc, stmt, tick)
+    }
+    fn on_stmt_end(&mut self, loc: StmtLocation<'_>, tick: LogicalTick) {
+        self.borrow_mut().on_stmt_end(loc, tick)
+    }
+    fn on_binding(&mut self, loc: StmtLocation<'_>, name: &str, value: &Value) {
+        self.borrow_mut().on_binding(loc, name, value)
+    }
+    fn on_effect(&mut self, loc: StmtLocation<'_>, event: &EffectEvent) {
+        self.borrow_mut().on_effect(loc, event)
+    }
+    fn on_q_transition(&mut self, loc: StmtLocation<'_>, t: &QTransition, state: &QSnapshot) {
+        self.borrow_mut().on_q_transition(loc, t, state)
+    }
+    fn on_fault(&mut self, loc: Option<StmtLocation<'_>>, fault: &DvmFault) {
+        self.borrow_mut().on_fault(loc, fault)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Dvm, DvmConfig};
+
+    #[derive(Default)]
+    struct Recorder {
+        log: Vec<String>,
+    }
+
+    impl ExecutionObserver for Recorder {
+        fn on_frame_enter(&mut self, _forge: &str, proc: &str, depth: usize) {
+            self.log.push(format!("enter {proc} {depth}"));
+        }
+        fn on_frame_exit(&mut self, _forge: &str, proc: &str, depth: usize) {
+            self.log.push(format!("exit {proc} {depth}"));
+        }
+        fn on_stmt_start(&mut self, loc: StmtLocation<'_>, _stmt: &DirStmt, tick: LogicalTick) {
+            self.log
+                .push(format!("start {}#{} t{}", loc.proc, loc.index, tick.0));
+        }
+        fn on_stmt_end(&mut self, loc: StmtLocation<'_>, tick: LogicalTick) {
+            self.log
+                .push(format!("end {}#{} t{}", loc.proc, loc.index, tick.0));
+        }
+        fn on_binding(&mut self, _loc: StmtLocation<'_>, name: &str, value: &Value) {
+            match value {
+                // Witness ids and digests are covered by the Φ tests.
+                Value::Struct { ty, .. } => self.log.push(format!("bind {name}={ty}{{..}}")),
+                _ => self.log.push(format!("bind {name}={value}")),
+            }
+        }
+        fn on_effect(&mut self, _loc: StmtLocation<'_>, event: &EffectEvent) {
+            self.log
+                .push(format!("effect {} {}", event.kind, event.payload));
+        }
+        fn on_q_transition(&mut self, _loc: StmtLocation<'_>, t: &QTransition, _s: &QSnapshot) {
+            self.log.push(format!("q {t:?}"));
+        }
+        fn on_fault(&mut self, loc: Option<StmtLocation<'_>>, fault: &DvmFault) {
+            let at = loc.map(|l| format!("{}#{}", l.proc, l.index));
+            self.log.push(format!("fault {at:?} {}", fault.error));
+        }
+    }
+
+    const PROGRAM: &str = include_str!("../../../../tests/fixtures/dir/regime_tour.dir.json");
+
+    #[test]
+    fn observer_sees_execution_in_order() {
+        let recorder = Rc::new(RefCell::new(Recorder::default()));
+        let mut dvm = Dvm::new(DvmConfig::default());
+        dvm.attach_observer(recorder.clone());
+
+        let program = dvm
+            .load_dir_json(PROGRAM.replace("m Gt n", "m Lt n").as_bytes())
+            .unwrap();
+        assert!(dvm.run_entrypoint_with_fault(&program, "main").is_err());
+
+        let log = recorder.borrow().log.clone();
+        assert_eq!(
+            log,
+            vec![
+                "enter main 0",
+                "start main#0 t0",
+                "bind n=2",
+                "end main#0 t1",
+                "start main#1 t1",
+                "q CallEnter { transfers: [] }",
+                "enter measure 1",
+                "start measure#0 t1",
+                "q Alloc { name: \"a\", ty: \"QBit\" }",
+                "bind a=unit",
+                "end measure#0 t2",
+                "start measure#1 t2",
+                "q Consume { name: \"a\" }",
+                "bind r=unit",
+                "end measure#1 t3",
+                "start measure#2 t3",
+                "end measure#2 t3",
+                "exit measure 1",
+                "q CallExit { returned: None }",
+                "bind m=7",
+                "end main#1 t4",
+                "start main#2 t4",
+                "enter pick 1",
+                "start pick#0 t4",
+                "bind x=4",
+                "end pick#0 t5",
+                "start pick#1 t5",
+                "end pick#1 t6",
+                "start pick#2 t6",
+                "bind w=PhiWitness{..}",
+                "end pick#2 t7",
+                "start pick#3 t7",
+                "end pick#3 t7",
+                "exit pick 1",
+                "bind w=PhiWitness{..}",
+                "end main#2 t8",
+                "start main#3 t8",
+                "effect emit 9",
+                "end main#3 t9",
+                "start main#4 t9",
+                "effect seal 2",
+                "end main#4 t10",
+                "start main#5 t10",
+                "fault Some(\"main#5\") inadmissible program: constraint failed: m Lt n",
+            ]
+        );
+    }
+}
@@ -0,0 +1,322 @@
+//! Regime hooks for the shared statement interpreter.
+//!
+//! The interpreter (`execute.rs`) owns the statement loop, effects, ticks and
+//! calls. Each regime supplies a `RegimeSemantics` implementation for the parts
+//! that differ: frame entry, `Let` intrinsics, `Constrain` / `Prove` checks,
+//! `Return`, and which bindings are linear.
+
+use super::execute::{Frame, Machine};
+use super::observer::QTransition;
+use super::DvmFault;
+use crate::{
+    admissibility::{self, Explanation},
+    dir::DirStmt,
+    expr,
+    regime::{
+        phi::{parse_unknown, replay as phi_replay},
+        phi_resolve_proc,
+        phi_witness::{
+            constraint_digest as phi_constraint_digest, prove_digest as phi_prove_digest,
+        },
+        PhiRefutation, PhiResolution, PhiWitnessKind,
+    },
+    DirProc, DvmError, Value,
+};
+use indexmap::IndexMap;
+
+/// How a proc body finished via `Return`.
+pub(crate) enum Returned {
+    Value(Value),
+
+    /// A Q callee returned one of its bindings (ownership goes to the caller).
+    QBinding(String),
+}
+
+impl Returned {
+    /// The value observed by the host. Q bindings are opaque to the host (`Unit`).
+    pub(crate) fn into_value(self) -> Value {
+        match self {
+            Returned::Value(v) => v,
+            Returned::QBinding(_) => Value::Unit,
+        }
+    }
+}
+
+/// Regime-specific behaviour plugged into the interpreter.
+///
+/// Defaults give the classical (K) behaviour.
+pub(crate) trait RegimeSemantics: Sync {
+    /// Prepare a frame before its first statement (e.g. Φ resolution).
+    fn enter(&self, _m: &mut Machine<'_>, _frame: &mut Frame) -> Result<(), DvmFault> {
+        Ok(())
+    }
+
+    /// Evaluate a regime intrinsic in `Let` position; `None` for ordinary expressions.
+    fn intrinsic(
+        &self,
+        _m: &mut Machine<'_>,
+        _frame: &Frame,
+        _name: &str,
+        _expr: &str,
+    ) -> Result<Option<Value>, DvmFault> {
+        Ok(None)
+    }
+
+    /// Check a `Constrain` predicate.
+    fn constrain(&self, m: &Machine<'_>, frame: &Frame, predicate: &str) -> Result<(), DvmFault> {
+        check_explained(m.proc_of(frame), frame.pc, predicate, &frame.env)
+    }
+
+    /// Check a `Prove` predicate; returns the value bound to the proof name.
+    fn prove(&self, m: &mut Machine<'_>, frame: &Frame, from: &str) -> Result<Value, DvmFault> {
+        check_explained(m.proc_of(frame), frame.pc, from, &frame.env)?;
+        Ok(Value::Unit)
+    }
+
+    /// Evaluate a `Return` expression.
+    fn ret(&self, _m: &Machine<'_>, frame: &Frame, expr: &str) -> Result<Returned, DvmFault> {
+        Ok(Returned::Value(expr::eval(expr, &frame.env)?))
+    }
+
+    /// Whether `name` is a linear binding (moved, not copied, into callees).
+    fn is_linear(&self, _m: &Machine<'_>, _name: &str) -> bool {
+        false
+    }
+}
+
+/// Semantics for a regime name.
+pub(crate) fn semantics_for(regime: &str) -> Result<&'static dyn RegimeSemantics, DvmError> {
+    match regime {
+        "K" => Ok(&KSemantics),
+        "Q" => Ok(&QSemantics),
+        "Φ" => Ok(&PhiSemantics),
+        other => Err(DvmError::UnsupportedRegime(format!(
+            "unknown regime: {other}"
+        ))),
+    }
+}
+
+/// K-regime: deterministic classical execution (the trait defaults).
+pub(crate) struct KSemantics;
+
+impl RegimeSemantics for KSemantics {}
+
+/// Q-regime: linear resource intrinsics over the run's `QState`.
+pub(crate) struct QSemantics;
+
+impl RegimeSemantics for QSemantics {
+    fn intrinsic(
+        &self,
+        m: &mut Machine<'_>,
+        frame: &Frame,
+        name: &str,
+        e: &str,
+    ) -> Result<Option<Value>, DvmFault> {
+        let q = &mut m.q;
+        let transition = if let Some(ty) = parse_q_alloc(e) {
+            q.alloc(name, &ty)?;
+            QTransition::Alloc {
+                name: name.to_string(),
+                ty,
+            }
+        } else if let Some(src) = parse_q_move(e) {
+            q.mov(&src, name)?;
+            QTransition::Move {
+                from: src,
+                to: name.to_string(),
+            }
+        } else if let Some(src) = parse_q_use(e) {
+            let _ = q.require_usable(&src, "q_use")?;
+            QTransition::Use { name: src }
+        } else if let Some(src) = parse_q_consume(e) {
+            q.consume(&src, "q_consume")?;
+            QTransition::Consume { name: src }
+        } else {
+            return Ok(None);
+        };
+        m.q_transition(frame, transition);
+
+        // Q bindings are opaque to the classical environment.
+        Ok(Some(Value::Unit))
+    }
+
+    fn ret(&self, m: &Machine<'_>, frame: &Frame, e: &str) -> Result<Returned, DvmFault> {
+        // Returning a Q binding hands its resource to the caller.
+        let e = e.trim();
+        if m.q.is_bound(e) {
+            return Ok(Returned::QBinding(e.to_string()));
+        }
+        Ok(Returned::Value(expr::eval(e, &frame.env)?))
+    }
+
+    fn is_linear(&self, m: &Machine<'_>, name: &str) -> bool {
+        m.q.is_bound(name)
+    }
+}
+
+/// Φ-regime: resolve unknowns against the constraint set (bounded host-mode
+/// search) on entry, then execute the body under the resolved assignment. Φ
+/// witnesses are constructed as a host intrinsic.
+pub(crate) struct PhiSemantics;
+
+impl RegimeSemantics for PhiSemantics {
+    fn enter(&self, m: &mut Machine<'_>, frame: &mut Frame) -> Result<(), DvmFault> {
+        // On entry the frame env holds exactly the params.
+        let proc_ = m.proc_of(frame);
+        frame.params = frame.env.clone();
+        match phi_resolve_proc(proc_, &frame.env)? {
+            PhiResolution::Admissible { assignment } => {
+                frame.assignment = assignment;
+                Ok(())
+            }
+            PhiResolution::NonExistent {
+                refutation,
+                message,
+            } => {
+                // Non-existence is a first-class outcome: attest it with a witness.
+                let digest = phi_constraint_digest(proc_, &frame.params)?;
+                let explanation = explain_sole_candidate(proc_, &frame.env, &refutation);
+                let w =
+                    m.witnesses
+                        .non_existent(&digest, &message, frame.params.clone(), refutation);
+                let fault = DvmFault::from(DvmError::Inadmissible(message)).with_witness(w);
+                Err(match explanation {
+                    Some(x) => fault.with_explanation(x),
+                    None => fault,
+                })
+            }
+        }
+    }
+
+    fn intrinsic(
+        &self,
+        m: &mut Machine<'_>,
+        frame: &Frame,
+        name: &str,
+        e: &str,
+    ) -> Result<Option<Value>, DvmFault> {
+        if let Some(arg_expr) = parse_phi_witness(e) {
+            // The witness attests the proc's constraint set; the optional
+            // argument is a caller label and must evaluate to a String.
+            let label = if arg_expr.is_empty() {
+                None
+            } else {
+                match expr::eval(&arg_expr, &frame.env)? {
+                    Value::String(s) => Some(s),
+                    other => {
+                        return Err(DvmError::Runtime(format!(
+                            "phi_witness expects a String label, got {:?}",
+                            other
+                        ))
+                        .into());
+                    }
+                }
+            };
+
+            let digest = phi_constraint_digest(m.proc_of(frame), &frame.params)?;
+            let mut w =
+                m.witnesses
+                    .admissible(&digest, frame.params.clone(), frame.assignment.clone());
+            w.label = label;
+
+            // Integrate witness as a first-class Value (struct) rather than a JSON string.
+            Ok(Some(w.to_value()))
+        } else if parse_unknown(e).is_some() {
+            // Unknowns take their resolved value.
+            Ok(Some(
+                frame.assignment.get(name).cloned().unwrap_or(Value::Unit),
+            ))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn constrain(&self, m: &Machine<'_>, frame: &Frame, predicate: &str) -> Result<(), DvmFault> {
+        // Established by resolution on entry; re-checked against the resolved
+        // bindings so execution never proceeds past a false constraint.
+        check_explained(m.proc_of(frame), frame.pc, predicate, &frame.env)
+    }
+
+    fn prove(&self, m: &mut Machine<'_>, frame: &Frame, from: &str) -> Result<Value, DvmFault> {
+        // Require predicate to hold in host-mode.
+        let proc_ = m.proc_of(frame);
+        check_explained(proc_, frame.pc, from, &frame.env)?;
+
+        // Digest of the constraint set extended with the proved predicate.
+        let digest = phi_prove_digest(proc_, &frame.params, from)?;
+
+        // Produce a witness and inject as a first-class Struct Value.
+        let w = m
+            .witnesses
+            .admissible(&digest, frame.params.clone(), frame.assignment.clone());
+        Ok(w.to_value())
+    }
+}
+
+/// Check a `Constrain` / `Prove` predicate, explaining it on failure.
+fn check_explained(
+    proc_: &DirProc,
+    index: usize,
+    predicate: &str,
+    env: &IndexMap<String, Value>,
+) -> Result<(), DvmFault> {
+    admissibility::check_predicate(predicate, env).map_err(|e| {
+        DvmFault::from(e).with_explanation(admissibility::explain(proc_, index, predicate, env))
+    })
+}
+
+/// Explain a Φ refutation when the search space is a single candidate (no
+/// unknowns): the failure is then concrete rather than one of many.
+fn explain_sole_candidate(
+    proc_: &DirProc,
+    env: &IndexMap<String, Value>,
+    refutation: &PhiRefutation,
+) -> Option<Explanation> {
+    if !refutation.unknowns.is_empty() {
+        return None;
+    }
+    let index = *refutation.failures.first()?;
+    let scope = phi_replay(proc_, env, &IndexMap::new(), index).ok()?;
+    match proc_.body.get(index)? {
+        DirStmt::Constrain { predicate } => {
+            Some(admissibility::explain(proc_, index, predicate, &scope))
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn is_non_existence(fault: &DvmFault) -> bool {
+    matches!(fault.witness.as_deref(), Some(w) if w.kind == PhiWitnessKind::NonExistent)
+}
+
+fn parse_call_1(expr: &str, name: &str) -> Option<String> {
+    let s = expr.trim();
+    let prefix = format!("{name}(");
+    if !s.starts_with(&prefix) || !s.ends_with(')') {
+        return None;
+    }
+    let inner = &s[prefix.len()..s.len() - 1];
+    Some(inner.trim().to_string())
+}
+
+pub(crate) fn parse_q_alloc(expr: &str) -> Option<String> {
+    parse_call_1(expr, "q_alloc").filter(|s| !s.is_empty())
+}
+
+pub(crate) fn parse_q_move(expr: &str) -> Option<String> {
+    parse_call_1(expr, "q_move").filter(|s| !s.is_empty())
+}
+
+pub(crate) fn parse_q_use(expr: &str) -> Option<String> {
+    parse_call_1(expr, "q_use").filter(|s| !s.is_empty())
+}
+
+pub(crate) fn parse_q_consume(expr: &str) -> Option<String> {
+    parse_call_1(expr, "q_consume").filter(|s| !s.is_empty())
+}
+
+fn parse_phi_witness(expr: &str) -> Option<String> {
+    // Accept `phi_witness()` or a single-argument call: phi_witness(<label_expr>)
+    // Return the raw argument expression (not evaluated here; empty if absent).
+    parse_call_1(expr, "phi_witness")
+}
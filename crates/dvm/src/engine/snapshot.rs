@@ -0,0 +1,173 @@
+//! Execution snapshots: the complete state of a paused run.
+//!
+//! A snapshot is taken between two statements and holds the program, the call
+//! stack (per-frame environments, Φ assignments and positions), `QState`, the
+//! effect log, logical time, the witness counter and any step records. Resuming
+//! it with `Dvm::resume` yields exactly the trace of an uninterrupted run.
+//! Resuming validates the snapshot's program first, as starting a run does.
+//! Attached observers are not part of a snapshot.
+
+use super::execute::MachineState;
+use super::observer::StmtLocation;
+use crate::{DirProgram, DvmError, DvmOutcome, EffectLog, EffectMode, TimeState};
+use serde::{Deserialize, Serialize};
+
+/// Version of the snapshot format produced by this build.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// A paused run (serializable; see the module docs).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DvmSnapshot {
+    snapshot_version: u32,
+    program: DirProgram,
+    state: MachineState,
+}
+
+/// Where a bounded run stopped.
+#[derive(Debug, Clone)]
+pub enum Checkpoint {
+    Finished(DvmOutcome),
+    Paused(Box<DvmSnapshot>),
+}
+
+impl DvmSnapshot {
+    pub(crate) fn new(program: DirProgram, state: MachineState) -> Self {
+        Self {
+            snapshot_version: SNAPSHOT_VERSION,
+            program,
+            state,
+        }
+    }
+
+    /// The state to resume from, after checking the snapshot version.
+    pub(crate) fn state(&self) -> Result<MachineState, DvmError> {
+        if self.snapshot_version != SNAPSHOT_VERSION {
+            return Err(DvmError::Runtime(format!(
+                "unsupported snapshot_version {} (expected {SNAPSHOT_VERSION})",
+                self.snapshot_version
+            )));
+        }
+        Ok(self.state.clone())
+    }
+
+    pub fn program(&self) -> &DirProgram {
+        &self.program
+    }
+
+    pub fn effect_mode(&self) -> EffectMode {
+        self.state.effect_mode
+    }
+
+    pub fn effects(&self) -> &EffectLog {
+        &self.state.effects
+    }
+
+    pub fn time(&self) -> &TimeState {
+        &self.state.time
+    }
+
+    /// Number of active frames (1 when paused in the entrypoint).
+    pub fn depth(&self) -> usize {
+        self.state.frames.len()
+    }
+
+    /// The next statement to execute.
+    pub fn location(&self) -> Option<StmtLocation<'_>> {
+        let frame = self.state.frames.last()?;
+        let forge = self.program.forges.get(frame.forge)?;
+        Some(StmtLocation {
+            forge: &forge.name,
+            proc: &forge.procs.get(frame.proc)?.name,
+            index: frame.pc,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Dvm, DvmConfig, DvmTrace};
+
+    const PROGRAM: &str = include_str!("../../../../tests/fixtures/dir/regime_tour.dir.json");
+
+    fn dvm() -> Dvm {
+        Dvm::new(DvmConfig {
+            steps: true,
+            ..DvmConfig::default()
+        })
+    }
+
+    #[test]
+    fn resuming_at_every_statement_reproduces_the_trace() {
+        let dvm = dvm();
+        let program = dvm.load_dir_json(PROGRAM.as_bytes()).unwrap();
+        let expected = dvm.run_entrypoint_trace(&program, "main");
+        assert!(matches!(expected, DvmTrace::Success(_)), "{expected:?}");
+
+        for k in 0.. {
+            let snapshot = match dvm.run_for(&program, "main", k).unwrap() {
+                Checkpoint::Paused(s) => s,
+                Checkpoint::Finished(_) => break,
+            };
+            // Round-trip through JSON, as a checkpoint file would.
+            let json = serde_json::to_string(&snapshot).unwrap();
+            let snapshot: DvmSnapshot = serde_json::from_str(&json).unwrap();
+            assert_eq!(dvm.resume_trace(&snapshot), expected, "paused after {k}");
+        }
+    }
+
+    #[test]
+    fn checkpoints_chain_and_expose_position() {
+        let dvm = dvm();
+        let program = dvm.load_dir_json(PROGRAM.as_bytes()).unwrap();
+        let expected = dvm.run_entrypoint_with_fault(&program, "main").unwrap();
+
+        let Checkpoint::Paused(first) = dvm.run_for(&program, "main", 3).unwrap() else {
+            panic!("finished early");
+        };
+        assert_eq!(first.depth(), 2);
+        let at = first.location().unwrap();
+        assert_eq!((at.proc, at.index), ("measure", 1));
+
+        let mut snapshot = first;
+        let outcome = loop {
+            match dvm.resume_for(&snapshot, 3).unwrap() {
+                Checkpoint::Paused(s) => snapshot = s,
+                Checkpoint::Finished(o) => break o,
+            }
+        };
+        assert_eq!(
+            DvmTrace::Success(outcome.into()),
+            DvmTrace::Success(expected.into())
+        );
+    }
+
+    #[test]
+    fn invalid_snapshots_are_rejected() {
+        let dvm = dvm();
+        let program = dvm.load_dir_json(PROGRAM.as_bytes()).unwrap();
+        let Checkpoint::Paused(snapshot) = dvm.run_for(&program, "main", 1).unwrap() else {
+            panic!("finished early");
+        };
+
+        let mut json = serde_json::to_value(&*snapshot).unwrap();
+        json["state"]["frames"][0]["pc"] = 99.into();
+        let broken: DvmSnapshot = serde_json::from_value(json).unwrap();
+        let fault = dvm.resume(&broken).unwrap_err();
+        assert_eq!(
+            fault.error.to_string(),
+            "runtime error: invalid snapshot: frame 0 is past the end of proc 'main'"
+        );
+
+        let mut json = serde_json::to_value(&*snapshot).unwrap();
+        json["program"]["forges"][0]["procs"][0]["regime"] = "X".into();
+        let tampered: DvmSnapshot = serde_json::from_value(json).unwrap();
+        let fault = dvm.resume(&tampered).unwrap_err();
+        assert!(
+            matches!(fault.error, DvmError::DirValidate(_)),
+            "{}",
+            fault.error
+        );
+    }
+}
@@ -0,0 +1,278 @@
+//! Statement-at-a-time execution for tools that drive a run themselves.
+//!
+//! A `Stepper` is obtained from `Dvm::stepper` and executes one statement of the
+//! innermost frame per `step`. Between steps it exposes the next statement's
+//! location, the call stack with each frame's environment, `QState`, the effect
+//! log and logical time. Driving it to the end yields exactly the trace of
+//! `Dvm::run_entrypoint_trace`.
+
+use super::execute::{Machine, Step};
+use super::observer::StmtLocation;
+use super::snapshot::DvmSnapshot;
+use super::{DvmFault, DvmOutcome};
+use crate::{
+    dir::DirStmt, effects::EffectLog, regime::QSnapshot, time::TimeState, DvmTrace, Value,
+};
+use indexmap::IndexMap;
+
+/// One active frame, as seen between two statements.
+#[derive(Debug, Clone, Copy)]
+pub struct StackFrame<'a> {
+    /// The frame's next statement.
+    pub location: StmtLocation<'a>,
+
+    /// Regime of the frame's proc (`K`, `Q` or `Φ`).
+    pub regime: &'a str,
+
+    /// Bindings of the frame (K values; Q bindings live in `QState`).
+    pub env: &'a IndexMap<String, Value>,
+
+    /// Resolved Φ assignment (empty outside the Φ regime).
+    pub assignment: &'a IndexMap<String, Value>,
+}
+
+/// Whether a run can make progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepStatus {
+    /// Another statement is ready to execute.
+    Running,
+
+    /// The entrypoint has returned.
+    Finished,
+}
+
+/// A run executed one statement at a time (see the module docs).
+pub struct Stepper<'p> {
+    machine: Machine<'p>,
+
+    /// Set when a statement faults; the run cannot continue past it.
+    fault: Option<DvmFault>,
+}
+
+impl std::fmt::Debug for Stepper<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Stepper")
+            .field("location", &self.location())
+            .field("depth", &self.depth())
+            .field("fault", &self.fault)
+            .finish()
+    }
+}
+
+impl<'p> Stepper<'p> {
+    pub(crate) fn new(machine: Machine<'p>) -> Self {
+        Self {
+            machine,
+            fault: None,
+        }
+    }
+
+    /// Execute the next statement.
+    ///
+    /// A `Call` steps into its callee; the call itself completes (and binds its
+    /// result) with the step that executes the callee's `Return`. After a fault,
+    /// every further step returns the same fault.
+    pub fn step(&mut self) -> Result<StepStatus, DvmFault> {
+        if let Some(fault) = &self.fault {
+            return Err(fault.clone());
+        }
+        match self.machine.step() {
+            Ok(Step::Running) => Ok(StepStatus::Running),
+            Ok(Step::Finished) => Ok(StepStatus::Finished),
+            Err(fault) => {
+                self.fault = Some(fault.clone());
+                Err(fault)
+            }
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.fault.is_none() && self.machine.frames().is_empty()
+    }
+
+    /// The entrypoint's returned value, once the run has finished.
+    pub fn returned(&self) -> Option<&Value> {
+        self.machine.returned()
+    }
+
+    /// The fault that stopped the run, if any.
+    pub fn fault(&self) -> Option<&DvmFault> {
+        self.fault.as_ref()
+    }
+
+    /// The next statement to execute (the faulting one after a fault); `None`
+    /// once the run has finished.
+    pub fn location(&self) -> Option<StmtLocation<'p>> {
+        let frame = self.machine.frames().last()?;
+        Some(self.machine.location(frame))
+    }
+
+    /// The statement at `location`.
+    pub fn statement(&self) -> Option<&'p DirStmt> {
+        let frame = self.machine.frames().last()?;
+        self.machine.proc_of(frame).body.get(frame.pc)
+    }
+
+    /// Number of active frames (1 in the entrypoint).
+    pub fn depth(&self) -> usize {
+        self.machine.frames().len()
+    }
+
+    /// The call stack, entrypoint first.
+    pub fn frames(&self) -> Vec<StackFrame<'_>> {
+        self.machine
+            .frames()
+            .iter()
+            .map(|frame| StackFrame {
+                location: self.machine.location(frame),
+                regime: &self.machine.proc_of(frame).regime,
+                env: &frame.env,
+                assignment: &frame.assignment,
+            })
+            .collect()
+    }
+
+    /// Bindings of the innermost frame.
+    pub fn env(&self) -> Option<&IndexMap<String, Value>> {
+        self.machine.frames().last().map(|frame| &frame.env)
+    }
+
+    pub fn q(&self) -> QSnapshot {
+        self.machine.q.snapshot()
+    }
+
+    pub fn effects(&self) -> &EffectLog {
+        &self.machine.effects
+    }
+
+    pub fn time(&self) -> &TimeState {
+        &self.machine.time
+    }
+
+    /// A resumable snapshot of the run; `None` once it has finished or faulted.
+    pub fn snapshot(&self) -> Option<DvmSnapshot> {
+        if self.fault.is_some() || self.machine.frames().is_empty() {
+            return None;
+        }
+        Some(DvmSnapshot::new(
+            self.machine.program.clone(),
+            self.machine.capture(),
+        ))
+    }
+
+    /// Run the remaining statements.
+    pub fn run(mut self) -> Result<DvmOutcome, DvmFault> {
+        if let Some(fault) = self.fault {
+            return Err(fault);
+        }
+        self.machine.run()?;
+        Ok(self.machine.into_outcome())
+    }
+
+    /// Trace API counterpart of `run`.
+    pub fn into_trace(self) -> DvmTrace {
+        match self.run() {
+            Ok(ok) => DvmTrace::Success(ok.into()),
+            Err(fault) => DvmTrace::Failure(fault.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{regime::QResState, Dvm, DvmConfig};
+
+    const PROGRAM: &str = include_str!("../../../../tests/fixtures/dir/regime_tour.dir.json");
+
+    fn dvm() -> Dvm {
+        Dvm::new(DvmConfig {
+            steps: true,
+            ..DvmConfig::default()
+        })
+    }
+
+    #[test]
+    fn stepping_exposes_state_between_statements() {
+        let dvm = dvm();
+        let program = dvm.load_dir_json(PROGRAM.as_bytes()).unwrap();
+        let mut stepper = dvm.stepper(&program, "main").unwrap();
+
+        let at = stepper.location().unwrap();
+        assert_eq!((at.proc, at.index), ("main", 0));
+        assert!(matches!(stepper.statement(), Some(DirStmt::Let { .. })));
+
+        stepper.step().unwrap();
+        assert_eq!(stepper.env().unwrap().get("n"), Some(&Value::Int(2)));
+
+        // Into the callee, then past its allocation.
+        stepper.step().unwrap();
+        stepper.step().unwrap();
+        let frames = stepper.frames();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].location.index, 1);
+        assert_eq!(
+            (frames[1].location.proc, frames[1].location.index),
+            ("measure", 1)
+        );
+        let q = stepper.q();
+        let res = &q.env.get("a").unwrap().res;
+        assert_eq!(q.resources.get(res).unwrap().state, QResState::Live);
+        assert_eq!(stepper.time().tick.0, 2);
+
+        // The Return completes the call in the caller.
+        stepper.step().unwrap();
+        assert_eq!(stepper.step().unwrap(), StepStatus::Running);
+        assert_eq!(stepper.depth(), 1);
+        assert_eq!(stepper.env().unwrap().get("m"), Some(&Value::Int(7)));
+
+        // Through the Φ call, then the effects.
+        while stepper.location().unwrap().index < 5 {
+            stepper.step().unwrap();
+        }
+        assert_eq!(stepper.effects().events.len(), 2);
+        stepper.step().unwrap();
+        assert_eq!(stepper.step().unwrap(), StepStatus::Finished);
+        assert!(stepper.is_finished());
+        assert_eq!(stepper.returned(), Some(&Value::Int(7)));
+        assert!(stepper.location().is_none());
+    }
+
+    #[test]
+    fn completing_a_stepper_yields_the_run_trace() {
+        let dvm = dvm();
+        let program = dvm.load_dir_json(PROGRAM.as_bytes()).unwrap();
+        let expected = dvm.run_entrypoint_trace(&program, "main");
+
+        for k in 0..16 {
+            let mut stepper = dvm.stepper(&program, "main").unwrap();
+            for _ in 0..k {
+                if stepper.step().unwrap() == StepStatus::Finished {
+                    break;
+                }
+            }
+            assert_eq!(stepper.into_trace(), expected, "stepped {k}");
+        }
+    }
+
+    #[test]
+    fn a_fault_stops_the_stepper_at_the_faulting_statement() {
+        let dvm = dvm();
+        let program = dvm
+            .load_dir_json(PROGRAM.replace("q_consume(a)", "a Add 1").as_bytes())
+            .unwrap();
+        let expected = dvm.run_entrypoint_trace(&program, "main");
+
+        let mut stepper = dvm.stepper(&program, "main").unwrap();
+        let fault = loop {
+            if let Err(fault) = stepper.step() {
+                break fault;
+            }
+        };
+        let at = stepper.location().unwrap();
+        assert_eq!((at.proc, at.index), ("measure", 1));
+        assert_eq!(stepper.step().unwrap_err().error, fault.error);
+        assert!(stepper.snapshot().is_none());
+        assert_eq!(stepper.into_trace(), expected);
+    }
+}
@@ -0,0 +1,81 @@
+//! Per-statement step records for detailed traces (`DvmConfig::steps`).
+//!
+//! Steps are recorded by an internal observer, in the order statements start.
+//! A `Call` statement's record precedes its callee's statements and also receives
+//! the binding of the call result when the callee returns.
+//!
+//! Records are part of the trace schema: their shape is versioned by the
+//! trace's `schema_version` (see `trace::migrate`).
+
+use super::observer::{ExecutionObserver, StmtLocation};
+use crate::{dir::DirStmt, effects::EffectEvent, time::LogicalTick, Value};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+/// One executed (or faulting) statement.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StepRecord {
+    pub forge: String,
+    pub proc: String,
+
+    /// Body index of the statement.
+    pub index: usize,
+
+    /// Logical time when the statement started.
+    pub tick: LogicalTick,
+
+    /// Bindings written by the statement, in write order.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub bindings: IndexMap<String, Value>,
+
+    /// Effects emitted by the statement.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub effects: Vec<EffectEvent>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct StepRecorder {
+    pub(crate) steps: Vec<StepRecord>,
+
+    /// Indices into `steps` of statements still executing, innermost last.
+    open: Vec<usize>,
+}
+
+impl StepRecorder {
+    fn current(&mut self) -> Option<&mut StepRecord> {
+        let at = *self.open.last()?;
+        self.steps.get_mut(at)
+    }
+}
+
+impl ExecutionObserver for StepRecorder {
+    fn on_stmt_start(&mut self, loc: StmtLocation<'_>, _stmt: &DirStmt, tick: LogicalTick) {
+        self.open.push(self.steps.len());
+        self.steps.push(StepRecord {
+            forge: loc.forge.to_string(),
+            proc: loc.proc.to_string(),
+            index: loc.index,
+            tick,
+            bindings: IndexMap::new(),
+            effects: Vec::new(),
+        });
+    }
+
+    fn on_stmt_end(&mut self, _loc: StmtLocation<'_>, _tick: LogicalTick) {
+        self.open.pop();
+    }
+
+    fn on_binding(&mut self, _loc: StmtLocation<'_>, name: &str, value: &Value) {
+        if let Some(step) = self.current() {
+            step.bindings.insert(name.to_string(), value.clone());
+        }
+    }
+
+    fn on_effect(&mut self, _loc: StmtLocation<'_>, event: &EffectEvent) {
+        if let Some(step) = self.current() {
+            step.effects.push(event.clone());
+        }
+    }
+}
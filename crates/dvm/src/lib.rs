@@ -3,14 +3,12 @@
 //! This crate implements the normative execution semantics for DIR artifacts:
 //! - K-regime: deterministic classical execution (reference semantics)
 //! - Q-regime: linear resource semantics enforcement (host-mode semantics)
-//! - Φ-regime: validation + deterministic refusal (v0.1), with witness stub wiring
+//! - Φ-regime: bounded admissibility resolution, then execution under the resolved
+//!   assignment, with witnesses
 //!
 //! This crate contains NO compiler logic and NO CLI logic.
 //! It consumes DIR and produces execution traces or refusal/failure traces.
 
-// use indexmap::IndexMap;
-use serde::{Deserialize, Serialize};
-
 pub mod error {
     use thiserror::Error;
 
@@ -47,92 +45,7 @@ pub mod error {
 
 pub use error::DvmError;
 
-pub mod dir {
-    //! Minimal DIR model as currently produced by the Dust compiler.
-    //! This mirrors the `dust_dir` crate structures found in the compiler repository.
-    //!
-    //! NOTE: In this repository, DIR types are defined locally to keep dustrun standalone.
-
-    use serde::{Deserialize, Serialize};
-
-    #[derive(Debug, Clone, Serialize, Deserialize)]
-    pub struct DirProgram {
-        pub forges: Vec<DirForge>,
-    }
-
-    #[derive(Debug, Clone, Serialize, Deserialize)]
-    pub struct DirForge {
-        pub name: String,
-        pub shapes: Vec<DirShape>,
-        pub procs: Vec<DirProc>,
-        pub binds: Vec<DirBind>,
-    }
-
-    #[derive(Debug, Clone, Serialize, Deserialize)]
-    pub struct DirShape {
-        pub name: String,
-        pub fields: Vec<DirField>,
-    }
-
-    #[derive(Debug, Clone, Serialize, Deserialize)]
-    pub struct DirField {
-        pub name: String,
-        pub ty: String,
-    }
-
-    #[derive(Debug, Clone, Serialize, Deserialize)]
-    pub struct DirProc {
-        pub regime: String, // "K" | "Q" | "Φ"
-        pub name: String,
-        pub params: Vec<DirParam>,
-        pub uses: Vec<DirUses>,
-        pub ret: Option<String>,
-        pub qualifiers: Vec<String>,
-        pub body: Vec<DirStmt>, // v0.1 lowered statements
-    }
-
-    #[derive(Debug, Clone, Serialize, Deserialize)]
-    pub struct DirParam {
-        pub name: String,
-        pub ty: String,
-    }
-
-    #[derive(Debug, Clone, Serialize, Deserialize)]
-    pub struct DirUses {
-        pub resource: String,
-        pub args: Vec<(String, DirLit)>,
-    }
-
-    #[derive(Debug, Clone, Serialize, Deserialize)]
-    pub enum DirLit {
-        Int(i64),
-        Bool(bool),
-        String(String),
-    }
-
-    #[derive(Debug, Clone, Serialize, Deserialize)]
-    pub enum DirStmt {
-        Let { name: String, expr: String },
-        Constrain { predicate: String },
-        Prove { name: String, from: String },
-        Effect { kind: String, payload: String },
-        Return { expr: String },
-    }
-
-    #[derive(Debug, Clone, Serialize, Deserialize)]
-    pub struct DirBind {
-        pub source: String,
-        pub target: String,
-        pub contract: Vec<DirClause>,
-    }
-
-    #[derive(Debug, Clone, Serialize, Deserialize)]
-    pub struct DirClause {
-        pub key: String,
-        pub op: String,
-        pub value: String,
-    }
-}
+pub mod dir;
 
 pub use dir::*;
 
@@ -144,19 +57,21 @@ pub mod effects {
 
     use serde::{Deserialize, Serialize};
 
-    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
     pub enum EffectMode {
         Simulate,
         Realize,
     }
 
     #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+    #[serde(deny_unknown_fields)]
     pub struct EffectEvent {
         pub kind: String,    // "observe" | "emit" | "seal" (v0.1)
         pub payload: String, // rendered payload expression
     }
 
     #[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+    #[serde(deny_unknown_fields)]
     pub struct EffectLog {
         pub events: Vec<EffectEvent>,
     }
@@ -187,6 +102,7 @@ pub mod time {
     pub struct LogicalTick(pub u64);
 
     #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+    #[serde(deny_unknown_fields)]
     pub struct TimeState {
         pub tick: LogicalTick,
     }
@@ -212,7 +128,7 @@ pub mod value {
     use indexmap::IndexMap;
     use serde::{Deserialize, Serialize};
 
-    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
     pub enum Value {
         Int(i64),
         Bool(bool),
@@ -238,6 +154,28 @@ pub mod value {
             }
         }
     }
+
+    /// Compact, deterministic rendering: `42`, `true`, `"s"`, `Ty{a:1,b:2}`, `unit`.
+    impl std::fmt::Display for Value {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Value::Int(n) => write!(f, "{n}"),
+                Value::Bool(b) => write!(f, "{b}"),
+                Value::String(s) => write!(f, "{s:?}"),
+                Value::Struct { ty, fields } => {
+                    write!(f, "{ty}{{")?;
+                    for (i, (k, v)) in fields.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ",")?;
+                        }
+                        write!(f, "{k}:{v}")?;
+                    }
+                    write!(f, "}}")
+                }
+                Value::Unit => write!(f, "unit"),
+            }
+        }
+    }
 }
 
 pub use value::Value;
@@ -419,781 +357,378 @@ pub mod expr {
         }
     }
 
-    // Precedence: Mul/Div > Add/Sub > comparisons > And > Or
-    pub fn eval(expr: &str, env: &IndexMap<String, Value>) -> Result<Value, DvmError> {
-        let toks = lex(expr)?;
-        let mut p = Parser::new(toks);
-        let v = parse_or(&mut p, env)?;
-        Ok(v)
-    }
-
-    fn parse_or(p: &mut Parser, env: &IndexMap<String, Value>) -> Result<Value, DvmError> {
-        let mut left = parse_and(p, env)?;
-        loop {
-            if matches!(p.peek(), Tok::Ident(op) if op == "Or") {
-                p.next();
-                let right = parse_and(p, env)?;
-                let lb = left
-                    .as_bool()
-                    .ok_or_else(|| DvmError::Runtime("Or requires bool operands".into()))?;
-                let rb = right
-                    .as_bool()
-                    .ok_or_else(|| DvmError::Runtime("Or requires bool operands".into()))?;
-                left = Value::Bool(lb || rb);
-            } else {
-                break;
-            }
-        }
-        Ok(left)
-    }
-
-    fn parse_and(p: &mut Parser, env: &IndexMap<String, Value>) -> Result<Value, DvmError> {
-        let mut left = parse_cmp(p, env)?;
-        loop {
-            if matches!(p.peek(), Tok::Ident(op) if op == "And") {
-                p.next();
-                let right = parse_cmp(p, env)?;
-                let lb = left
-                    .as_bool()
-                    .ok_or_else(|| DvmError::Runtime("And requires bool operands".into()))?;
-                let rb = right
-                    .as_bool()
-                    .ok_or_else(|| DvmError::Runtime("And requires bool operands".into()))?;
-                left = Value::Bool(lb && rb);
-            } else {
-                break;
-            }
-        }
-        Ok(left)
-    }
-
-    fn parse_cmp(p: &mut Parser, env: &IndexMap<String, Value>) -> Result<Value, DvmError> {
-        let mut left = parse_add(p, env)?;
-        loop {
-            let op = match p.peek() {
-                Tok::Ident(s) if ["Eq", "Lt", "Le", "Gt", "Ge"].contains(&s.as_str()) => s.clone(),
-                _ => break,
-            };
-            p.next();
-            let right = parse_add(p, env)?;
-            left = match op.as_str() {
-                "Eq" => Value::Bool(left == right),
-                "Lt" => Value::Bool(cmp_int(&left, &right, |a, b| a < b)?),
-                "Le" => Value::Bool(cmp_int(&left, &right, |a, b| a <= b)?),
-                "Gt" => Value::Bool(cmp_int(&left, &right, |a, b| a > b)?),
-                "Ge" => Value::Bool(cmp_int(&left, &right, |a, b| a >= b)?),
-                _ => return Err(DvmError::Runtime(format!("unknown comparison op: {op}"))),
-            };
-        }
-        Ok(left)
-    }
-
-    fn cmp_int<F: FnOnce(i64, i64) -> bool>(l: &Value, r: &Value, f: F) -> Result<bool, DvmError> {
-        let a = l
-            .as_int()
-            .ok_or_else(|| DvmError::Runtime("comparison requires int operands".into()))?;
-        let b = r
-            .as_int()
-            .ok_or_else(|| DvmError::Runtime("comparison requires int operands".into()))?;
-        Ok(f(a, b))
-    }
-
-    fn parse_add(p: &mut Parser, env: &IndexMap<String, Value>) -> Result<Value, DvmError> {
-        let mut left = parse_mul(p, env)?;
-        loop {
-            let op = match p.peek() {
-                Tok::Ident(s) if s == "Add" || s == "Sub" => s.clone(),
-                _ => break,
-            };
-            p.next();
-            let right = parse_mul(p, env)?;
-            let a = left
-                .as_int()
-                .ok_or_else(|| DvmError::Runtime("Add/Sub requires int operands".into()))?;
-            let b = right
-                .as_int()
-                .ok_or_else(|| DvmError::Runtime("Add/Sub requires int operands".into()))?;
-            left = if op == "Add" {
-                Value::Int(a + b)
-            } else {
-                Value::Int(a - b)
-            };
-        }
-        Ok(left)
-    }
-
-    fn parse_mul(p: &mut Parser, env: &IndexMap<String, Value>) -> Result<Value, DvmError> {
-        let mut left = parse_primary(p, env)?;
-        loop {
-            let op = match p.peek() {
-                Tok::Ident(s) if s == "Mul" || s == "Div" => s.clone(),
-                _ => break,
-            };
-            p.next();
-            let right = parse_primary(p, env)?;
-            let a = left
-                .as_int()
-                .ok_or_else(|| DvmError::Runtime("Mul/Div requires int operands".into()))?;
-            let b = right
-                .as_int()
-                .ok_or_else(|| DvmError::Runtime("Mul/Div requires int operands".into()))?;
-            if op == "Div" && b == 0 {
-                return Err(DvmError::Runtime("division by zero".into()));
-            }
-            left = if op == "Mul" {
-                Value::Int(a * b)
-            } else {
-                Value::Int(a / b)
-            };
-        }
-        Ok(left)
-    }
-
-    fn parse_primary(p: &mut Parser, env: &IndexMap<String, Value>) -> Result<Value, DvmError> {
-        match p.next() {
-            Tok::Int(n) => Ok(Value::Int(n)),
-            Tok::Bool(b) => Ok(Value::Bool(b)),
-            Tok::Str(s) => Ok(Value::String(s)),
-            Tok::Ident(id) => {
-                if let Some(v) = env.get(&id) {
-                    Ok(v.clone())
-                } else {
-                    Err(DvmError::Runtime(format!("unknown identifier: {id}")))
-                }
-            }
-            Tok::LParen => {
-                let v = parse_or(p, env)?;
-                p.eat(Tok::RParen)?;
-                Ok(v)
+    /// Binary operators, as emitted by the compiler (identifier spelling).
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum BinOp {
+        Add,
+        Sub,
+        Mul,
+        Div,
+        Eq,
+        Lt,
+        Le,
+        Gt,
+        Ge,
+        And,
+        Or,
+    }
+
+    impl BinOp {
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                BinOp::Add => "Add",
+                BinOp::Sub => "Sub",
+                BinOp::Mul => "Mul",
+                BinOp::Div => "Div",
+                BinOp::Eq => "Eq",
+                BinOp::Lt => "Lt",
+                BinOp::Le => "Le",
+                BinOp::Gt => "Gt",
+                BinOp::Ge => "Ge",
+                BinOp::And => "And",
+                BinOp::Or => "Or",
             }
-            other => Err(DvmError::Runtime(format!(
-                "unexpected token in expression: {:?}",
-                other
-            ))),
         }
-    }
-}
-
-pub mod admissibility {
-    //! v0.1 admissibility model:
-    //! - Constrain predicates must evaluate to true in evaluation context over classical env.
-    //! - Φ-regime host-mode semantics will evolve to match the spec.
-
-    use super::{expr, DvmError, Value};
-    use indexmap::IndexMap;
 
-    pub fn check_predicate(predicate: &str, env: &IndexMap<String, Value>) -> Result<(), DvmError> {
-        let v = expr::eval(predicate, env)?;
-        let ok = v.as_bool().ok_or_else(|| {
-            DvmError::ConstraintFailure("constraint predicate did not evaluate to bool".into())
-        })?;
-        if ok {
-            Ok(())
-        } else {
-            Err(DvmError::Inadmissible(format!(
-                "constraint failed: {predicate}"
-            )))
+        fn from_ident(s: &str) -> Option<Self> {
+            Some(match s {
+                "Add" => BinOp::Add,
+                "Sub" => BinOp::Sub,
+                "Mul" => BinOp::Mul,
+                "Div" => BinOp::Div,
+                "Eq" => BinOp::Eq,
+                "Lt" => BinOp::Lt,
+                "Le" => BinOp::Le,
+                "Gt" => BinOp::Gt,
+                "Ge" => BinOp::Ge,
+                "And" => BinOp::And,
+                "Or" => BinOp::Or,
+                _ => return None,
+            })
         }
     }
-}
-
-pub mod regime;
-pub use regime::*;
 
-pub mod engine {
-    use super::{
-        admissibility,
-        dir::DirStmt,
-        effects::EffectLog,
-        effects::EffectMode,
-        expr,
-        regime::{
-            phi_refuse_execution, phi_validate_proc, PhiValidation, PhiWitnessBuilder, QState,
+    /// Parsed expression.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Expr {
+        Int(i64),
+        Bool(bool),
+        Str(String),
+        Ident(String),
+        Binary {
+            op: BinOp,
+            lhs: Box<Expr>,
+            rhs: Box<Expr>,
         },
-        time::TimeState,
-        DirProc, DirProgram, DvmError, Value,
-    };
-    use indexmap::IndexMap;
-
-    #[derive(Debug, Clone)]
-    pub struct DvmConfig {
-        pub effect_mode: EffectMode,
-        pub trace: bool,
-    }
-
-    impl Default for DvmConfig {
-        fn default() -> Self {
-            Self {
-                effect_mode: EffectMode::Simulate,
-                trace: false,
-            }
-        }
     }
 
-    /// Successful outcome (no refusal/failure).
-    #[derive(Debug, Clone)]
-    pub struct DvmOutcome {
-        pub returned: Option<Value>,
-        pub effects: EffectLog,
-        pub time: TimeState,
-    }
-
-    /// Fault/refusal with deterministic partial context.
-    #[derive(Debug, Clone)]
-    pub struct DvmFault {
-        pub error: DvmError,
-        pub effects: EffectLog,
-        pub time: TimeState,
-    }
-
-    impl DvmFault {
-        pub fn new(error: DvmError, effects: EffectLog, time: TimeState) -> Self {
-            Self {
-                error,
-                effects,
-                time,
-            }
-        }
-    }
-
-    impl From<DvmError> for DvmFault {
-        fn from(error: DvmError) -> Self {
-            DvmFault::new(error, EffectLog::default(), TimeState::default())
-        }
-    }
-
-    #[derive(Debug)]
-    pub struct Dvm {
-        cfg: DvmConfig,
-    }
-
-    impl Dvm {
-        pub fn new(cfg: DvmConfig) -> Self {
-            Self { cfg }
-        }
-
-        /// Load a DIR program from JSON bytes.
-        pub fn load_dir_json(&self, bytes: &[u8]) -> Result<DirProgram, DvmError> {
-            serde_json::from_slice::<DirProgram>(bytes)
-                .map_err(|e| DvmError::DirLoad(format!("{e}")))
-        }
-
-        /// Validate basic DIR structure (v0.1).
-        pub fn validate_dir(&self, program: &DirProgram) -> Result<(), DvmError> {
-            if program.forges.is_empty() {
-                return Err(DvmError::DirValidate("program has no forges".into()));
-            }
-            for forge in &program.forges {
-                if forge.name.trim().is_empty() {
-                    return Err(DvmError::DirValidate("forge name is empty".into()));
-                }
-                for proc_ in &forge.procs {
-                    if proc_.name.trim().is_empty() {
-                        return Err(DvmError::DirValidate("proc name is empty".into()));
+    impl Expr {
+        /// Canonical form: a prefix rendering that is invariant under whitespace,
+        /// redundant parentheses, operand order of commutative operators
+        /// (Add, Mul, Eq, And, Or, flattening associative chains) and the
+        /// mirrored comparisons (`a Gt b` == `b Lt a`, `a Ge b` == `b Le a`).
+        pub fn canonical(&self) -> String {
+            match self {
+                Expr::Int(n) => n.to_string(),
+                Expr::Bool(b) => b.to_string(),
+                Expr::Str(s) => format!("{s:?}"),
+                Expr::Ident(id) => id.clone(),
+                Expr::Binary { op, lhs, rhs } => match op {
+                    BinOp::Gt => format!("(Lt {} {})", rhs.canonical(), lhs.canonical()),
+                    BinOp::Ge => format!("(Le {} {})", rhs.canonical(), lhs.canonical()),
+                    BinOp::Eq => {
+                        let mut xs = [lhs.canonical(), rhs.canonical()];
+                        xs.sort();
+                        format!("(Eq {} {})", xs[0], xs[1])
                     }
-                    if proc_.regime != "K" && proc_.regime != "Q" && proc_.regime != "Φ" {
-                        return Err(DvmError::DirValidate(format!(
-                            "unknown regime: {}",
-                            proc_.regime
-                        )));
+                    BinOp::Add | BinOp::Mul | BinOp::And | BinOp::Or => {
+                        let mut xs = Vec::new();
+                        self.flatten_into(*op, &mut xs);
+                        xs.sort();
+                        format!("({} {})", op.as_str(), xs.join(" "))
                     }
-                }
-            }
-            Ok(())
-        }
-
-        /// Compatibility API: prior callers expect `Result<Outcome, DvmError>`.
-        ///
-        /// This now drops partial context on failure. Prefer `run_entrypoint_trace` in new code.
-        pub fn run_entrypoint(
-            &self,
-            program: &DirProgram,
-            entry: &str,
-        ) -> Result<DvmOutcome, DvmError> {
-            match self.run_entrypoint_with_fault(program, entry) {
-                Ok(ok) => Ok(ok),
-                Err(fault) => Err(fault.error),
+                    BinOp::Sub | BinOp::Div | BinOp::Lt | BinOp::Le => {
+                        format!("({} {} {})", op.as_str(), lhs.canonical(), rhs.canonical())
+                    }
+                },
             }
         }
 
-        /// New API: returns a structured fault carrying deterministic partial context.
-        pub fn run_entrypoint_with_fault(
-            &self,
-            program: &DirProgram,
-            entry: &str,
-        ) -> Result<DvmOutcome, DvmFault> {
-            // validation failures have no prior context
-            self.validate_dir(program)
-                .map_err(|e| DvmFault::new(e, EffectLog::default(), TimeState::default()))?;
-
-            let proc_ = find_proc(program, entry).ok_or_else(|| {
-                DvmFault::new(
-                    DvmError::EntrypointNotFound(entry.to_string()),
-                    EffectLog::default(),
-                    TimeState::default(),
-                )
-            })?;
-
-            let mut env = IndexMap::<String, Value>::new();
-            if !proc_.params.is_empty() {
-                return Err(DvmFault::new(
-                    DvmError::Runtime(format!(
-                        "entrypoint has params in v0.1 host-runner: {}:{}",
-                        proc_.name, proc_.regime
-                    )),
-                    EffectLog::default(),
-                    TimeState::default(),
-                ));
-            }
-
-            match proc_.regime.as_str() {
-                "K" => self.exec_k(proc_, &mut env),
-                "Q" => self.exec_q(proc_, &mut env),
-                "Φ" => self.exec_phi(proc_, &mut env),
-                other => Err(DvmFault::new(
-                    DvmError::UnsupportedRegime(format!("unknown regime: {other}")),
-                    EffectLog::default(),
-                    TimeState::default(),
-                )),
-            }
+        /// Identifiers referenced by the expression, in order of first appearance.
+        pub fn idents(&self) -> Vec<&str> {
+            let mut out = Vec::new();
+            self.collect_idents(&mut out);
+            out
         }
 
-        // Trace API: produce a single trace value for conformance and tooling.
-        pub fn run_entrypoint_trace(&self, program: &DirProgram, entry: &str) -> crate::DvmTrace {
-            match self.run_entrypoint_with_fault(program, entry) {
-                Ok(ok) => crate::DvmTrace::Success(ok.into()),
-                Err(fault) => {
-                    let effects = if fault.effects.events.is_empty() {
-                        None
-                    } else {
-                        Some(fault.effects)
-                    };
-
-                    let time = if fault.time.tick.0 == 0 {
-                        None
-                    } else {
-                        Some(fault.time)
-                    };
-
-                    crate::DvmTrace::Failure(crate::DvmFailureTrace {
-                        error: crate::TraceError::from(&fault.error),
-                        effects,
-                        time,
-                    })
+        fn collect_idents<'a>(&'a self, out: &mut Vec<&'a str>) {
+            match self {
+                Expr::Ident(id) if !out.contains(&id.as_str()) => out.push(id),
+                Expr::Binary { lhs, rhs, .. } => {
+                    lhs.collect_idents(out);
+                    rhs.collect_idents(out);
                 }
+                _ => {}
             }
         }
 
-        fn exec_k(
-            &self,
-            proc_: &DirProc,
-            env: &mut IndexMap<String, Value>,
-        ) -> Result<DvmOutcome, DvmFault> {
-            let mut effects = EffectLog::default();
-            let mut time = TimeState::default();
-
-            for stmt in &proc_.body {
-                if self.cfg.trace {
-                    log::info!("tick={} stmt={:?}", time.tick.0, stmt);
-                }
+        pub fn is_literal(&self) -> bool {
+            matches!(self, Expr::Int(_) | Expr::Bool(_) | Expr::Str(_))
+        }
 
-                let step_res: Result<Option<Value>, DvmError> = match stmt {
-                    DirStmt::Let { name, expr: e } => {
-                        let v = expr::eval(e, env)?;
-                        env.insert(name.clone(), v);
-                        Ok(None)
-                    }
-                    DirStmt::Constrain { predicate } => {
-                        admissibility::check_predicate(predicate, env)?;
-                        Ok(None)
-                    }
-                    DirStmt::Prove { name, from } => {
-                        admissibility::check_predicate(from, env)?;
-                        env.insert(name.clone(), Value::Unit);
-                        Ok(None)
-                    }
-                    DirStmt::Effect { kind, payload } => {
-                        let rendered = render_payload(payload, env)?;
-                        effects.push(kind.clone(), rendered);
-                        match self.cfg.effect_mode {
-                            EffectMode::Simulate => {}
-                            EffectMode::Realize => {}
-                        }
-                        Ok(None)
-                    }
-                    DirStmt::Return { expr: e } => {
-                        let v = expr::eval(e, env)?;
-                        Ok(Some(v))
-                    }
-                };
-
-                match step_res {
-                    Ok(Some(v)) => {
-                        return Ok(DvmOutcome {
-                            returned: Some(v),
-                            effects,
-                            time,
-                        });
-                    }
-                    Ok(None) => {
-                        time.step();
-                    }
-                    Err(e) => {
-                        return Err(DvmFault::new(e, effects, time));
-                    }
+        fn flatten_into(&self, chain: BinOp, out: &mut Vec<String>) {
+            match self {
+                Expr::Binary { op, lhs, rhs } if *op == chain => {
+                    lhs.flatten_into(chain, out);
+                    rhs.flatten_into(chain, out);
                 }
+                other => out.push(other.canonical()),
             }
-
-            Ok(DvmOutcome {
-                returned: None,
-                effects,
-                time,
-            })
         }
+    }
 
-        fn exec_q(
-            &self,
-            proc_: &DirProc,
-            env: &mut IndexMap<String, Value>,
-        ) -> Result<DvmOutcome, DvmFault> {
-            let mut effects = EffectLog::default();
-            let mut time = TimeState::default();
-            let mut q = QState::new();
-
-            for stmt in &proc_.body {
-                if self.cfg.trace {
-                    log::info!("tick={} stmt={:?}", time.tick.0, stmt);
-                }
-
-                let step_res: Result<Option<Value>, DvmError> = match stmt {
-                    DirStmt::Let { name, expr: e } => {
-                        if let Some(ty) = parse_q_alloc(e) {
-                            q.alloc(name, &ty)?;
-                            env.insert(name.clone(), Value::Unit);
-                            Ok(None)
-                        } else if let Some(src) = parse_q_move(e) {
-                            q.mov(&src, name)?;
-                            env.insert(name.clone(), Value::Unit);
-                            Ok(None)
-                        } else if let Some(src) = parse_q_use(e) {
-                            let _ = q.require_usable(&src, "q_use")?;
-                            env.insert(name.clone(), Value::Unit);
-                            Ok(None)
-                        } else if let Some(src) = parse_q_consume(e) {
-                            q.consume(&src, "q_consume")?;
-                            env.insert(name.clone(), Value::Unit);
-                            Ok(None)
-                        } else {
-                            let v = expr::eval(e, env)?;
-                            env.insert(name.clone(), v);
-                            Ok(None)
+    /// Source rendering in DIR operator spelling; nested operations are parenthesized.
+    impl std::fmt::Display for Expr {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Expr::Int(n) => write!(f, "{n}"),
+                Expr::Bool(b) => write!(f, "{b}"),
+                Expr::Str(s) => write!(f, "{s:?}"),
+                Expr::Ident(id) => write!(f, "{id}"),
+                Expr::Binary { op, lhs, rhs } => {
+                    for (i, side) in [lhs, rhs].into_iter().enumerate() {
+                        if i > 0 {
+                            write!(f, " {} ", op.as_str())?;
                         }
-                    }
-                    DirStmt::Constrain { predicate } => {
-                        admissibility::check_predicate(predicate, env)?;
-                        Ok(None)
-                    }
-                    DirStmt::Prove { name, from } => {
-                        admissibility::check_predicate(from, env)?;
-                        env.insert(name.clone(), Value::Unit);
-                        Ok(None)
-                    }
-                    DirStmt::Effect { kind, payload } => {
-                        let rendered = render_payload(payload, env)?;
-                        effects.push(kind.clone(), rendered);
-                        match self.cfg.effect_mode {
-                            EffectMode::Simulate => {}
-                            EffectMode::Realize => {}
+                        match side.as_ref() {
+                            Expr::Binary { .. } => write!(f, "({side})")?,
+                            _ => write!(f, "{side}")?,
                         }
-                        Ok(None)
-                    }
-                    DirStmt::Return { expr: e } => {
-                        let v = expr::eval(e, env)?;
-                        Ok(Some(v))
-                    }
-                };
-
-                match step_res {
-                    Ok(Some(v)) => {
-                        return Ok(DvmOutcome {
-                            returned: Some(v),
-                            effects,
-                            time,
-                        });
-                    }
-                    Ok(None) => {
-                        time.step();
-                    }
-                    Err(e) => {
-                        return Err(DvmFault::new(e, effects, time));
                     }
+                    Ok(())
                 }
             }
-
-            Ok(DvmOutcome {
-                returned: None,
-                effects,
-                time,
-            })
         }
+    }
 
-        fn exec_phi(
-            &self,
-            proc_: &DirProc,
-            env: &mut IndexMap<String, Value>,
-        ) -> Result<DvmOutcome, DvmFault> {
-            // v0.1: validate constraints (local host-mode) then refuse execution deterministically,
-            // but allow construction of Φ witness stubs as a host intrinsic.
-            match phi_validate_proc(proc_, env) {
-                Ok(PhiValidation::LocallyAdmissible) => {}
-                Ok(PhiValidation::LocallyInadmissible { message }) => {
-                    return Err(DvmFault::new(
-                        DvmError::Inadmissible(message),
-                        EffectLog::default(),
-                        TimeState::default(),
-                    ));
-                }
-                Err(e) => {
-                    return Err(DvmFault::new(e, EffectLog::default(), TimeState::default()));
-                }
-            }
-
-            let mut effects = EffectLog::default();
-            let mut time = TimeState::default();
-            let mut builder = PhiWitnessBuilder::new();
-
-            for stmt in &proc_.body {
-                if self.cfg.trace {
-                    log::info!("tick={} stmt={:?}", time.tick.0, stmt);
-                }
-
-                let step_res: Result<(), DvmError> = match stmt {
-                    DirStmt::Let { name, expr: e } => {
-                        if let Some(arg_expr) = parse_phi_witness(e) {
-                            // Evaluate the argument expression and require it to be a String.
-                            let v = expr::eval(&arg_expr, env)?;
-                            let digest = match v {
-                                Value::String(s) => s,
-                                other => {
-                                    return Err(DvmFault::new(
-                                        DvmError::Runtime(format!(
-                                            "phi_witness expects a String digest, got {:?}",
-                                            other
-                                        )),
-                                        effects,
-                                        time,
-                                    ));
-                                }
-                            };
-
-                            let w = builder.admissible(&digest);
-
-                            // Integrate witness as a first-class Value (struct) rather than a JSON string.
-                            env.insert(name.clone(), phi_witness_to_value(&w));
-                        } else {
-                            // v0.1: allow ordinary Let evaluation in host-mode so Φ intrinsics
-                            // can consume previously-bound values (e.g., digest strings).
-                            let v = expr::eval(e, env)?;
-                            env.insert(name.clone(), v);
-                        }
-                        Ok(())
-                    }
-
-                    // --- re
-                    DirStmt::Effect { kind, payload } => {
-                        let rendered = render_payload(payload, env)?;
-                        effects.push(kind.clone(), rendered);
-                        Ok(())
-                    }
-                    DirStmt::Constrain { .. } => Ok(()), // already validated
-                    DirStmt::Prove { name, from } => {
-                        // Require predicate to hold in host-mode.
-                        admissibility::check_predicate(from, env)?;
-
-                        // Deterministic v0.1 digest of the proved predicate.
-                        let digest = format!("pred:{from}");
-
-                        // Produce a witness stub and inject as a first-class Struct Value.
-                        let w = builder.admissible(&digest);
-                        env.insert(name.clone(), phi_witness_to_value(&w));
-
-                        Ok(())
-                    }
-                    DirStmt::Return { .. } => Ok(()), // ignored in v0.1
-                };
-
-                if let Err(e) = step_res {
-                    return Err(DvmFault::new(e, effects, time));
-                }
+    // Precedence: Mul/Div > Add/Sub > comparisons > And > Or
+    pub fn eval(expr: &str, env: &IndexMap<String, Value>) -> Result<Value, DvmError> {
+        eval_expr(&parse(expr)?, env)
+    }
 
-                time.step();
+    /// Parse an expression string into an `Expr`.
+    pub fn parse(expr: &str) -> Result<Expr, DvmError> {
+        let toks = lex(expr)?;
+        let mut p = Parser::new(toks);
+        parse_or(&mut p)
+    }
+
+    /// Evaluate a parsed expression. Both operands of every operator are evaluated
+    /// (left first); there is no short-circuiting.
+    pub fn eval_expr(e: &Expr, env: &IndexMap<String, Value>) -> Result<Value, DvmError> {
+        match e {
+            Expr::Int(n) => Ok(Value::Int(*n)),
+            Expr::Bool(b) => Ok(Value::Bool(*b)),
+            Expr::Str(s) => Ok(Value::String(s.clone())),
+            Expr::Ident(id) => env
+                .get(id)
+                .cloned()
+                .ok_or_else(|| DvmError::Runtime(format!("unknown identifier: {id}"))),
+            Expr::Binary { op, lhs, rhs } => {
+                let left = eval_expr(lhs, env)?;
+                let right = eval_expr(rhs, env)?;
+                apply(*op, &left, &right)
             }
-
-            // Refuse execution but carry partial context.
-            Err(DvmFault::new(phi_refuse_execution(), effects, time))
         }
     }
 
-    fn find_proc<'a>(program: &'a DirProgram, name: &str) -> Option<&'a DirProc> {
-        for forge in &program.forges {
-            for p in &forge.procs {
-                if p.name == name {
-                    return Some(p);
+    /// Apply a binary operator to evaluated operands.
+    pub fn apply(op: BinOp, left: &Value, right: &Value) -> Result<Value, DvmError> {
+        match op {
+            BinOp::Or | BinOp::And => {
+                let err = || DvmError::Runtime(format!("{} requires bool operands", op.as_str()));
+                let lb = left.as_bool().ok_or_else(err)?;
+                let rb = right.as_bool().ok_or_else(err)?;
+                Ok(Value::Bool(if op == BinOp::Or {
+                    lb || rb
+                } else {
+                    lb && rb
+                }))
+            }
+            BinOp::Eq => Ok(Value::Bool(left == right)),
+            BinOp::Lt => Ok(Value::Bool(cmp_int(left, right, |a, b| a < b)?)),
+            BinOp::Le => Ok(Value::Bool(cmp_int(left, right, |a, b| a <= b)?)),
+            BinOp::Gt => Ok(Value::Bool(cmp_int(left, right, |a, b| a > b)?)),
+            BinOp::Ge => Ok(Value::Bool(cmp_int(left, right, |a, b| a >= b)?)),
+            BinOp::Add | BinOp::Sub => {
+                let a = left
+                    .as_int()
+                    .ok_or_else(|| DvmError::Runtime("Add/Sub requires int operands".into()))?;
+                let b = right
+                    .as_int()
+                    .ok_or_else(|| DvmError::Runtime("Add/Sub requires int operands".into()))?;
+                Ok(Value::Int(if op == BinOp::Add { a + b } else { a - b }))
+            }
+            BinOp::Mul | BinOp::Div => {
+                let a = left
+                    .as_int()
+                    .ok_or_else(|| DvmError::Runtime("Mul/Div requires int operands".into()))?;
+                let b = right
+                    .as_int()
+                    .ok_or_else(|| DvmError::Runtime("Mul/Div requires int operands".into()))?;
+                if op == BinOp::Div && b == 0 {
+                    return Err(DvmError::Runtime("division by zero".into()));
                 }
+                Ok(Value::Int(if op == BinOp::Mul { a * b } else { a / b }))
             }
         }
-        None
     }
 
-    fn render_payload(
-        payload_expr: &str,
-        env: &IndexMap<String, Value>,
-    ) -> Result<String, DvmError> {
-        let v = expr::eval(payload_expr, env)?;
-        Ok(match v {
-            Value::String(s) => s,
-            Value::Int(n) => n.to_string(),
-            Value::Bool(b) => b.to_string(),
-            Value::Struct { .. } => serde_json::to_string(&v).map_err(|e| {
-                DvmError::Runtime(format!("failed to render struct payload as json: {e}"))
-            })?,
-            Value::Unit => "unit".into(),
-        })
+    fn cmp_int<F: FnOnce(i64, i64) -> bool>(l: &Value, r: &Value, f: F) -> Result<bool, DvmError> {
+        let a = l
+            .as_int()
+            .ok_or_else(|| DvmError::Runtime("comparison requires int operands".into()))?;
+        let b = r
+            .as_int()
+            .ok_or_else(|| DvmError::Runtime("comparison requires int operands".into()))?;
+        Ok(f(a, b))
     }
 
-    #[allow(dead_code)]
-    fn value_to_string(v: &Value) -> String {
-        match v {
-            Value::Int(n) => n.to_string(),
-            Value::Bool(b) => b.to_string(),
-            Value::String(s) => format!("{:?}", s),
-            Value::Struct { ty, fields } => {
-                let mut parts = Vec::new();
-                for (k, vv) in fields.iter() {
-                    parts.push(format!("{k}:{}", value_to_string(vv)));
-                }
-                format!("{ty}{{{}}}", parts.join(","))
-            }
-            Value::Unit => "unit".into(),
+    fn peek_op(p: &Parser, ops: &[BinOp]) -> Option<BinOp> {
+        match p.peek() {
+            Tok::Ident(s) => BinOp::from_ident(s).filter(|op| ops.contains(op)),
+            _ => None,
         }
     }
 
-    fn parse_call_1(expr: &str, name: &str) -> Option<String> {
-        let s = expr.trim();
-        let prefix = format!("{name}(");
-        if !s.starts_with(&prefix) || !s.ends_with(')') {
-            return None;
+    fn parse_level(
+        p: &mut Parser,
+        ops: &[BinOp],
+        next: fn(&mut Parser) -> Result<Expr, DvmError>,
+    ) -> Result<Expr, DvmError> {
+        let mut left = next(p)?;
+        while let Some(op) = peek_op(p, ops) {
+            p.next();
+            let right = next(p)?;
+            left = Expr::Binary {
+                op,
+                lhs: Box::new(left),
+                rhs: Box::new(right),
+            };
         }
-        let inner = &s[prefix.len()..s.len() - 1];
-        Some(inner.trim().to_string())
+        Ok(left)
     }
 
-    fn parse_q_alloc(expr: &str) -> Option<String> {
-        parse_call_1(expr, "q_alloc").filter(|s| !s.is_empty())
+    fn parse_or(p: &mut Parser) -> Result<Expr, DvmError> {
+        parse_level(p, &[BinOp::Or], parse_and)
     }
 
-    fn parse_q_move(expr: &str) -> Option<String> {
-        parse_call_1(expr, "q_move").filter(|s| !s.is_empty())
+    fn parse_and(p: &mut Parser) -> Result<Expr, DvmError> {
+        parse_level(p, &[BinOp::And], parse_cmp)
     }
 
-    fn parse_q_use(expr: &str) -> Option<String> {
-        parse_call_1(expr, "q_use").filter(|s| !s.is_empty())
+    fn parse_cmp(p: &mut Parser) -> Result<Expr, DvmError> {
+        parse_level(
+            p,
+            &[BinOp::Eq, BinOp::Lt, BinOp::Le, BinOp::Gt, BinOp::Ge],
+            parse_add,
+        )
     }
 
-    fn parse_q_consume(expr: &str) -> Option<String> {
-        parse_call_1(expr, "q_consume").filter(|s| !s.is_empty())
+    fn parse_add(p: &mut Parser) -> Result<Expr, DvmError> {
+        parse_level(p, &[BinOp::Add, BinOp::Sub], parse_mul)
     }
 
-    fn parse_phi_witness(expr: &str) -> Option<String> {
-        // Accept a single-argument call: phi_witness(<arg_expr>)
-        // Return the raw argument expression (not evaluated here).
-        parse_call_1(expr, "phi_witness").filter(|s| !s.is_empty())
+    fn parse_mul(p: &mut Parser) -> Result<Expr, DvmError> {
+        parse_level(p, &[BinOp::Mul, BinOp::Div], parse_primary)
     }
 
-    fn phi_witness_to_value(w: &crate::regime::PhiWitness) -> Value {
-        use crate::regime::PhiWitnessKind;
+    fn parse_primary(p: &mut Parser) -> Result<Expr, DvmError> {
+        match p.next() {
+            Tok::Int(n) => Ok(Expr::Int(n)),
+            Tok::Bool(b) => Ok(Expr::Bool(b)),
+            Tok::Str(s) => Ok(Expr::Str(s)),
+            Tok::Ident(id) => Ok(Expr::Ident(id)),
+            Tok::LParen => {
+                let e = parse_or(p)?;
+                p.eat(Tok::RParen)?;
+                Ok(e)
+            }
+            other => Err(DvmError::Runtime(format!(
+                "unexpected token in expression: {:?}",
+                other
+            ))),
+        }
+    }
 
-        let mut fields = IndexMap::new();
+    #[cfg(test)]
+    mod tests {
+        use super::*;
 
-        let kind_str = match w.kind {
-            PhiWitnessKind::Admissible => "Admissible",
-            PhiWitnessKind::NonExistent => "NonExistent",
-        };
+        #[test]
+        fn canonical_form_ignores_layout_and_operand_order() {
+            let a = parse("(x Add 1) Gt (2 Mul y)").unwrap();
+            let b = parse("y Mul 2 Lt 1 Add x").unwrap();
+            assert_eq!(a.canonical(), b.canonical());
+            assert_eq!(a.canonical(), "(Lt (Mul 2 y) (Add 1 x))");
 
-        fields.insert("kind".to_string(), Value::String(kind_str.to_string()));
-        fields.insert("id".to_string(), Value::String(w.id.clone()));
-        fields.insert(
-            "constraint_digest".to_string(),
-            Value::String(w.constraint_digest.clone()),
-        );
-        fields.insert("note".to_string(), Value::String(w.note.clone()));
+            let c = parse("a And (b And c)").unwrap();
+            let d = parse("(c And a) And b").unwrap();
+            assert_eq!(c.canonical(), d.canonical());
 
-        Value::Struct {
-            ty: "PhiWitness".to_string(),
-            fields,
+            // Non-commutative operators keep operand order.
+            let e = parse("x Sub 1").unwrap();
+            let f = parse("1 Sub x").unwrap();
+            assert_ne!(e.canonical(), f.canonical());
         }
-    }
-}
 
-pub use engine::{Dvm, DvmConfig, DvmFault, DvmOutcome};
+        #[test]
+        fn idents_are_listed_once_in_order() {
+            let e = parse("(b Add a) Lt b Mul 2 Or \"c\" Eq c").unwrap();
+            assert_eq!(e.idents(), ["b", "a", "c"]);
+        }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-pub struct TraceError {
-    pub kind: String,
-    pub message: String,
-}
+        #[test]
+        fn eval_matches_operator_semantics() {
+            let mut env = IndexMap::new();
+            env.insert("x".to_string(), Value::Int(5));
+            assert_eq!(eval("x Mul 2 Add 1", &env).unwrap(), Value::Int(11));
+            assert_eq!(eval("x Lt 3 Or true", &env).unwrap(), Value::Bool(true));
+            assert!(eval("x Div 0", &env).is_err());
+            assert!(eval("y", &env).is_err());
+        }
 
-impl From<&DvmError> for TraceError {
-    fn from(e: &DvmError) -> Self {
-        let (kind, message) = match e {
-            DvmError::DirLoad(s) => ("DirLoad", s.clone()),
-            DvmError::DirValidate(s) => ("DirValidate", s.clone()),
-            DvmError::EntrypointNotFound(s) => ("EntrypointNotFound", s.clone()),
-            DvmError::UnsupportedRegime(s) => ("UnsupportedRegime", s.clone()),
-            DvmError::Inadmissible(s) => ("Inadmissible", s.clone()),
-            DvmError::ConstraintFailure(s) => ("ConstraintFailure", s.clone()),
-            DvmError::EffectViolation(s) => ("EffectViolation", s.clone()),
-            DvmError::TimeViolation(s) => ("TimeViolation", s.clone()),
-            DvmError::Runtime(s) => ("Runtime", s.clone()),
-        };
-        Self {
-            kind: kind.to_string(),
-            message,
+        #[test]
+        fn display_round_trips_through_parse() {
+            for src in [
+                "x Lt 3",
+                "(x Mul x) Eq 49",
+                "(a And b) Or (c Sub 1 Lt \"s\")",
+            ] {
+                let e = parse(src).unwrap();
+                assert_eq!(parse(&e.to_string()).unwrap(), e);
+            }
+            assert_eq!(
+                parse("x Mul x Eq 49").unwrap().to_string(),
+                "(x Mul x) Eq 49"
+            );
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub struct DvmSuccessTrace {
-    pub returned: Option<Value>,
-    pub effects: EffectLog,
-    pub time: TimeState,
-}
+pub mod admissibility;
 
-impl From<DvmOutcome> for DvmSuccessTrace {
-    fn from(o: DvmOutcome) -> Self {
-        Self {
-            returned: o.returned,
-            effects: o.effects,
-            time: o.time,
-        }
-    }
-}
+pub mod regime;
+pub use regime::*;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub struct DvmFailureTrace {
-    pub error: TraceError,
+pub mod engine;
 
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub effects: Option<EffectLog>,
+pub use engine::{
+    Checkpoint, Dvm, DvmConfig, DvmFault, DvmOutcome, DvmSnapshot, ExecutionObserver, QTransition,
+    StackFrame, StepRecord, StepStatus, Stepper, StmtLocation,
+};
 
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub time: Option<TimeState>,
-}
+pub use admissibility::Explanation;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(untagged)]
-pub enum DvmTrace {
-    Failure(DvmFailureTrace),
-    Success(DvmSuccessTrace),
-}
+pub mod trace;
+pub use trace::{DvmFailureTrace, DvmSuccessTrace, DvmTrace, TraceError};
@@ -0,0 +1,71 @@
+//! Cross-regime call rules (v0.1).
+//!
+//! A `Call` statement transfers control from the caller's regime to the callee's.
+//! Legal transitions:
+//!
+//! | caller | may call   |
+//! |--------|------------|
+//! | K      | K, Q, Φ    |
+//! | Q      | Q, K       |
+//! | Φ      | Φ, K       |
+//!
+//! Q and Φ never call each other: linear resources cannot enter an admissibility
+//! search space, and Φ resolution cannot allocate or consume linear resources.
+//!
+//! Boundary rules enforced by the engine:
+//! - Q bindings passed to a Q callee are moved into it; a Q binding returned by a
+//!   Q callee is moved into the caller's result binding.
+//! - Q bindings never cross into K (as arguments or return values).
+//! - A Φ callee invoked from K yields either its returned value or, if it has no
+//!   admissible assignment, its `NonExistent` witness as a value.
+
+use crate::{DirProc, DvmError};
+
+/// Maximum nesting of calls within one run.
+pub const MAX_CALL_DEPTH: usize = 256;
+
+/// Whether a `caller` regime may call a `callee` regime.
+pub fn transition_allowed(caller: &str, callee: &str) -> bool {
+    matches!(
+        (caller, callee),
+        ("K", "K" | "Q" | "Φ") | ("Q", "Q" | "K") | ("Φ", "Φ" | "K")
+    )
+}
+
+/// Reject an illegal regime transition (static validation).
+pub fn check_transition(caller: &DirProc, callee: &DirProc) -> Result<(), DvmError> {
+    if transition_allowed(&caller.regime, &callee.regime) {
+        Ok(())
+    } else {
+        Err(DvmError::DirValidate(format!(
+            "illegal regime transition: {} proc '{}' calls {} proc '{}'",
+            caller.regime, caller.name, callee.regime, callee.name
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transition_table() {
+        for (caller, callee, ok) in [
+            ("K", "K", true),
+            ("K", "Q", true),
+            ("K", "Φ", true),
+            ("Q", "Q", true),
+            ("Q", "K", true),
+            ("Q", "Φ", false),
+            ("Φ", "Φ", true),
+            ("Φ", "K", true),
+            ("Φ", "Q", false),
+        ] {
+            assert_eq!(
+                transition_allowed(caller, callee),
+                ok,
+                "{caller} -> {callee}"
+            );
+        }
+    }
+}
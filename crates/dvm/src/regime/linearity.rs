@@ -0,0 +1,325 @@
+//! Static Q-regime linearity checking, without executing the program.
+//!
+//! Each proc body is replayed in statement order over a fresh `QState`:
+//! `q_alloc` / `q_move` / `q_use` / `q_consume` and the ownership transfers of
+//! calls are applied abstractly, so violations carry the exact messages the
+//! interpreter faults with (use after move, double consume, moving into a bound
+//! name, a linear binding passed out of the Q regime or returned into another
+//! regime). Bindings still owning a live resource when a Q proc returns are
+//! reported as leak warnings: the interpreter releases them at return without
+//! faulting, so a program with leaks still runs.
+//!
+//! As in the interpreter, a call result is linear when the callee's `Return`
+//! names one of its Q bindings; the callee is replayed with the call's linear
+//! arguments to find out. A checked proc's own params are linear when their
+//! declared type is not classical (`Int`, `Bool`, `String`, `Unit`,
+//! `PhiWitness` or a declared shape). Bodies are straight-line, so a replay
+//! stops at the first `Return`.
+
+use super::q::QState;
+use crate::{
+    dir::validate::{detail, DirIssue, Severity},
+    engine::semantics::{parse_q_alloc, parse_q_consume, parse_q_move, parse_q_use},
+    DirForge, DirProgram, DirStmt, DvmError,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Types whose values are copied rather than owned.
+pub const CLASSICAL_TYPES: &[&str] = &["Int", "Bool", "String", "Unit", "PhiWitness"];
+
+/// Whether a value of type `ty` (as seen from `forge`) is a linear resource.
+pub fn is_linear_type(program: &DirProgram, forge: &DirForge, ty: &str) -> bool {
+    let shape = |forge: &DirForge, name: &str| forge.shapes.iter().any(|s| s.name == name);
+    let qualified_shape = ty.split_once("::").is_some_and(|(f, name)| {
+        program
+            .forges
+            .iter()
+            .any(|other| other.name == f && shape(other, name))
+    });
+    !(CLASSICAL_TYPES.contains(&ty) || shape(forge, ty) || qualified_shape)
+}
+
+/// Every linearity issue in `program`, in program order.
+pub fn check(program: &DirProgram) -> Vec<DirIssue> {
+    let mut checker = Checker {
+        program,
+        returns: HashMap::new(),
+        active: Vec::new(),
+    };
+    let mut issues = Vec::new();
+    for (f, forge) in program.forges.iter().enumerate() {
+        for (p, proc_) in forge.procs.iter().enumerate() {
+            let linear: Vec<bool> = proc_
+                .params
+                .iter()
+                .map(|param| proc_.regime == "Q" && is_linear_type(program, forge, &param.ty))
+                .collect();
+            let mut report = |index: Option<usize>, severity: Severity, message: String| {
+                issues.push(DirIssue {
+                    forge: Some(forge.name.clone()),
+                    proc: Some(proc_.name.clone()),
+                    index,
+                    message,
+                    severity,
+                })
+            };
+            checker.replay((f, p), &linear, &mut report);
+        }
+    }
+    issues
+}
+
+/// A proc located as `(forge index, proc index)`.
+type At = (usize, usize);
+
+struct Checker<'p> {
+    program: &'p DirProgram,
+
+    /// Q binding returned by a proc called with the given linear params.
+    returns: HashMap<(At, Vec<bool>), Option<String>>,
+
+    /// Procs being replayed for a call result (recursive calls return nothing linear).
+    active: Vec<At>,
+}
+
+impl Checker<'_> {
+    /// The Q binding `at` hands to its caller when called with `linear` params.
+    fn returned(&mut self, at: At, linear: Vec<bool>) -> Option<String> {
+        if self.program.forges[at.0].procs[at.1].regime != "Q" || self.active.contains(&at) {
+            return None;
+        }
+        if let Some(src) = self.returns.get(&(at, linear.clone())) {
+            return src.clone();
+        }
+        self.active.push(at);
+        let src = self.replay(at, &linear, &mut |_, _, _| {});
+        self.active.pop();
+        self.returns.insert((at, linear), src.clone());
+        src
+    }
+
+    /// Replay the body of `at` with the params flagged in `linear` owning
+    /// resources, reporting violations; returns the Q binding its `Return`
+    /// hands to the caller, if any.
+    fn replay(
+        &mut self,
+        at: At,
+        linear: &[bool],
+        report: &mut dyn FnMut(Option<usize>, Severity, String),
+    ) -> Option<String> {
+        let program = self.program;
+        let proc_ = &program.forges[at.0].procs[at.1];
+        let q_regime = proc_.regime == "Q";
+        let mut q = QState::new();
+        for (param, _) in proc_.params.iter().zip(linear).filter(|(_, l)| **l) {
+            // Params are distinct names, so this cannot fail.
+            let _ = q.alloc(&param.name, &param.ty);
+        }
+
+        // Bindings already reported (failed crossings, and destinations of failed
+        // moves, owned from then on) are not reported again as leaks.
+        let mut reported = HashSet::new();
+        let mut returned = None;
+        let mut returned_at = None;
+        for (index, stmt) in proc_.body.iter().enumerate() {
+            let mut fail = |e: DvmError| report(Some(index), Severity::Error, detail(e));
+            match stmt {
+                DirStmt::Let { name, expr } if q_regime => {
+                    let result = if let Some(ty) = parse_q_alloc(expr) {
+                        q.alloc(name, &ty)
+                    } else if let Some(src) = parse_q_move(expr) {
+                        let moved = q.mov(&src, name);
+                        if moved.is_err() && q.alloc(name, "?").is_ok() {
+                            reported.insert(name.as_str());
+                        }
+                        moved
+                    } else if let Some(src) = parse_q_use(expr) {
+                        q.require_usable(&src, "q_use").map(drop)
+                    } else if let Some(src) = parse_q_consume(expr) {
+                        q.consume(&src, "q_consume")
+                    } else {
+                        Ok(())
+                    };
+                    if let Err(e) = result {
+                        fail(e);
+                    }
+                }
+                DirStmt::Call { name, callee, args } => {
+                    // Unresolved calls are reported by `dir::validate`.
+                    let Ok(target_at) = program.resolve_proc(Some(at.0), callee) else {
+                        continue;
+                    };
+                    let target = &program.forges[target_at.0].procs[target_at.1];
+                    let mut arg_linear = Vec::new();
+                    for arg in args.iter().map(|arg| arg.trim()) {
+                        let bound = q.is_bound(arg);
+                        arg_linear.push(bound);
+                        if !bound {
+                            continue;
+                        }
+                        if target.regime != "Q" {
+                            fail(DvmError::Inadmissible(format!(
+                                "Q binding cannot cross into {} regime: {arg} (call {})",
+                                target.regime, target.name
+                            )));
+                            reported.insert(arg);
+                        } else if let Err(e) = q.take(arg, "q_call") {
+                            fail(e);
+                        }
+                    }
+                    if arg_linear.len() != target.params.len() {
+                        // Arity mismatches are reported by `dir::validate`.
+                        continue;
+                    }
+                    let Some(src) = self.returned(target_at, arg_linear) else {
+                        continue;
+                    };
+                    match name {
+                        _ if !q_regime => fail(DvmError::Inadmissible(format!(
+                            "Q resource cannot be returned into {} regime: {src} (call {})",
+                            proc_.regime, target.name
+                        ))),
+                        None => fail(DvmError::Inadmissible(format!(
+                            "Q call result discarded: {} returns a linear resource",
+                            target.name
+                        ))),
+                        Some(dst) => {
+                            let ty = target.ret.as_deref().unwrap_or("?");
+                            if q.alloc(dst, ty).is_err() {
+                                fail(DvmError::Inadmissible(format!(
+                                    "Q move failed: destination already bound: {dst}"
+                                )));
+                            }
+                        }
+                    }
+                }
+                DirStmt::Return { expr } => {
+                    // Returning a Q binding hands its resource to the caller.
+                    let e = expr.trim();
+                    if q.is_bound(e) {
+                        if let Err(e) = q.take(e, "q_return") {
+                            fail(e);
+                        }
+                        returned = Some(e.to_string());
+                    }
+                    returned_at = Some(index);
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        for name in q.live_bindings() {
+            if !reported.contains(name) {
+                report(returned_at, Severity::Warning, QState::err_leak(name));
+            }
+        }
+        returned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dir::asm, Dvm, DvmConfig};
+
+    fn issues(source: &str) -> Vec<String> {
+        check(&asm::parse(source).unwrap())
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn violations_match_the_interpreter() {
+        let source = "forge qcore {\n  proc Q main() -> Int {\n    let a = q_alloc(QBit)\n    \
+                      let b = q_move(a)\n    let x = q_use(a)\n    return 0\n  }\n}\n";
+        let program = asm::parse(source).unwrap();
+        let fault = Dvm::new(DvmConfig::default())
+            .run_entrypoint(&program, "main")
+            .unwrap_err();
+        assert_eq!(
+            issues(source),
+            [
+                format!("qcore::main#2: {}", detail(fault)),
+                "qcore::main#3: Q leak: binding still live at return: b".to_string(),
+            ]
+        );
+        assert_eq!(
+            issues(source)[0],
+            "qcore::main#2: Q use failed: binding already moved: a (op=q_use)"
+        );
+        let severities: Vec<Severity> = check(&program).iter().map(|i| i.severity).collect();
+        assert_eq!(severities, [Severity::Error, Severity::Warning]);
+
+        let source = "forge qcore {\n  proc Q main() -> Int {\n    let x = q_alloc(QBit)\n    \
+                      let r = q_consume(x)\n    let s = q_consume(x)\n    \
+                      let y = q_alloc(QBit)\n    let y = q_move(x)\n    return 0\n  }\n}\n";
+        assert_eq!(
+            issues(source),
+            [
+                "qcore::main#2: Q consume failed: binding already moved: x",
+                "qcore::main#4: Q move failed: destination already bound: y",
+                "qcore::main#5: Q leak: binding still live at return: y",
+            ]
+        );
+    }
+
+    #[test]
+    fn calls_transfer_ownership() {
+        let source = "forge qcore {\n  proc Q main() -> Int {\n    let a = q_alloc(QBit)\n    \
+                      call b = flip(a)\n    let x = q_use(a)\n    call n = count(b)\n    \
+                      return n\n  }\n\n  \
+                      proc Q flip(q: QBit) -> QBit {\n    return q\n  }\n\n  \
+                      proc Q count(q: QBit) -> Int {\n    let r = q_consume(q)\n    return 1\n  }\n\n  \
+                      proc K outside() -> Int {\n    call q = fresh()\n    return 0\n  }\n\n  \
+                      proc Q fresh() -> QBit {\n    let q = q_alloc(QBit)\n    call k = plain(q)\n    \
+                      return q\n  }\n\n  proc K plain(n: Int) -> Int {\n    return n\n  }\n}\n";
+        assert_eq!(
+            issues(source),
+            [
+                "qcore::main#2: Q use failed: binding already moved: a (op=q_use)",
+                "qcore::outside#0: Q resource cannot be returned into K regime: q (call fresh)",
+                "qcore::fresh#1: Q binding cannot cross into K regime: q (call plain)",
+            ]
+        );
+    }
+
+    #[test]
+    fn call_results_are_linear_as_the_interpreter_decides() {
+        let dvm = Dvm::new(DvmConfig::default());
+
+        // The declared `Int` does not matter: `q` itself is returned into K.
+        let source = "forge core {\n  proc K main() -> Int {\n    call r = f()\n    \
+                      return 0\n  }\n\n  proc Q f() -> Int {\n    let q = q_alloc(QBit)\n    \
+                      return q\n  }\n}\n";
+        let fault = dvm
+            .run_entrypoint(&asm::parse(source).unwrap(), "main")
+            .unwrap_err();
+        assert_eq!(issues(source), [format!("core::main#0: {}", detail(fault))]);
+        assert_eq!(
+            issues(source),
+            ["core::main#0: Q resource cannot be returned into K regime: q (call f)"]
+        );
+
+        // Nor does a declared `QBit` when a classical value is returned; a
+        // param is only linear when the caller passes a Q binding.
+        let source = "forge core {\n  proc K main() -> Int {\n    call r = f()\n    \
+                      call s = id(3)\n    return s\n  }\n\n  \
+                      proc Q f() -> QBit {\n    let q = q_alloc(QBit)\n    \
+                      let c = q_consume(q)\n    return 0\n  }\n\n  \
+                      proc Q id(q: QBit) -> QBit {\n    return q\n  }\n}\n";
+        let outcome = dvm.run_entrypoint(&asm::parse(source).unwrap(), "main");
+        assert_eq!(outcome.unwrap().returned, Some(crate::Value::Int(3)));
+        assert!(issues(source).is_empty(), "{:?}", issues(source));
+    }
+
+    #[test]
+    fn well_formed_programs_have_no_issues() {
+        let source = "forge core {\n  proc K main() -> Int {\n    call m = measure(2)\n    \
+                      return m\n  }\n\n  proc Q measure(k: Int) -> Int {\n    \
+                      let a = q_alloc(QBit)\n    let b = q_move(a)\n    let u = q_use(b)\n    \
+                      let r = q_consume(b)\n    return k Add 5\n  }\n}\n";
+        assert!(issues(source).is_empty());
+    }
+}
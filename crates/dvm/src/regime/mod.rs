@@ -4,17 +4,57 @@
 // components. Regime semantics are enforced by the DVM regardless of whether
 // execution is native, VM, or delegated to specialized backends.
 
+pub mod call;
+pub mod linearity;
 pub mod phi;
 pub mod phi_witness;
 pub mod q;
 
+pub use call::{check_transition, transition_allowed, MAX_CALL_DEPTH};
+
 pub use q::{QBinding, QResId, QResMeta, QResState, QSnapshot, QState};
 
 pub use phi::{
-    refuse_execution as phi_refuse_execution, validate_proc as phi_validate_proc, PhiValidation,
+    resolve_proc as phi_resolve_proc, validate_proc as phi_validate_proc, PhiDomain, PhiResolution,
+    PhiStop, PhiUnknown, PhiValidation,
 };
 
 pub use phi_witness::{
-    refuse_global_witness as phi_refuse_global_witness, PhiWitness, PhiWitnessBuilder,
+    constraint_digest as phi_constraint_digest, prove_digest as phi_prove_digest,
+    verify as phi_verify_witness, PhiRefutation, PhiVerification, PhiWitness, PhiWitnessBuilder,
     PhiWitnessKind,
 };
+
+/// DIR builders shared by the regime tests.
+#[cfg(test)]
+pub(crate) mod testing {
+    use crate::{dir::DirStmt, DirProc};
+
+    /// A Φ proc named `main` with no params.
+    pub(crate) fn phi_proc(body: Vec<DirStmt>) -> DirProc {
+        DirProc {
+            regime: "Φ".into(),
+            name: "main".into(),
+            params: vec![],
+            uses: vec![],
+            ret: None,
+            qualifiers: vec![],
+            body,
+            span: None,
+            body_spans: Vec::new(),
+        }
+    }
+
+    pub(crate) fn let_(name: &str, expr: &str) -> DirStmt {
+        DirStmt::Let {
+            name: name.into(),
+            expr: expr.into(),
+        }
+    }
+
+    pub(crate) fn constrain(predicate: &str) -> DirStmt {
+        DirStmt::Constrain {
+            predicate: predicate.into(),
+        }
+    }
+}
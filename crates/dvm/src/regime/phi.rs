@@ -1,20 +1,32 @@
-//! Φ-regime host-mode semantics (v0.1):
+//! Φ-regime host-mode semantics:
 //!
-//! This module provides **validation-only** semantics for Φ-regime programs.
-//! It does NOT execute Φ-regime computation yet.
+//! This module provides **admissibility resolution** for Φ-regime programs.
+//! The engine executes a Φ proc's body only after resolution succeeds, under
+//! the resolved assignment.
 //!
-//! Responsibilities in v0.1:
+//! Responsibilities:
 //! - Recognize Φ-regime as a distinct regime.
-//! - Provide deterministic, semantically meaningful refusal for execution.
-//! - Provide hooks for admissibility witness generation.
+//! - Resolve declared unknowns (`phi_unknown(...)`) by deterministic, bounded
+//!   exhaustive search over their domains.
+//! - Provide deterministic, semantically meaningful refusal for non-existence.
+//! - Provide the assignment / refutation material that witnesses attest.
 //!
-//! In v0.1, witnesses are **stub artifacts** with deterministic structure.
-//! They are produced for `Prove` and `phi_witness(...)` but do not constitute
-//! global proofs.
+//! Resolution is existence-oriented: a proc is admissible iff some assignment of
+//! its unknowns satisfies every `Constrain` in body order. Candidates are
+//! enumerated in a fixed order (unknowns in declaration order, each domain
+//! ascending), so the first satisfying assignment is deterministic.
 
+use super::phi_witness::PhiRefutation;
 use crate::DvmError;
 use crate::{DirProc, Value};
 use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+/// Default search domain for `phi_unknown(Int)` (inclusive bounds).
+pub const PHI_DEFAULT_INT_DOMAIN: (i64, i64) = (-8, 8);
+
+/// Upper bound on the number of candidate assignments a single resolution may search.
+pub const PHI_MAX_CANDIDATES: u64 = 1 << 16;
 
 /// Φ-regime validation result.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -26,47 +38,500 @@ pub enum PhiValidation {
     LocallyInadmissible { message: String },
 }
 
+/// Finite search domain of a Φ unknown.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PhiDomain {
+    /// `false`, then `true`.
+    Bool,
+
+    /// Every integer in `lo..=hi`, ascending.
+    Int { lo: i64, hi: i64 },
+}
+
+impl PhiDomain {
+    /// Number of values in the domain; an error if it does not fit in a `u64`.
+    pub fn size(&self) -> Result<u64, DvmError> {
+        match self {
+            PhiDomain::Bool => Ok(2),
+            PhiDomain::Int { lo, hi } => {
+                u64::try_from((i128::from(*hi) - i128::from(*lo) + 1).max(0)).map_err(|_| {
+                    DvmError::Inadmissible(format!("Φ domain {lo}..={hi} is too large to search"))
+                })
+            }
+        }
+    }
+
+    /// The `i`-th value of the domain in enumeration order, if there is one.
+    pub fn nth(&self, i: u64) -> Option<Value> {
+        match self {
+            PhiDomain::Bool => (i < 2).then_some(Value::Bool(i != 0)),
+            PhiDomain::Int { lo, hi } => lo
+                .checked_add_unsigned(i)
+                .filter(|n| n <= hi)
+                .map(Value::Int),
+        }
+    }
+
+    /// Whether `v` is a member of the domain.
+    pub fn contains(&self, v: &Value) -> bool {
+        match (self, v) {
+            (PhiDomain::Bool, Value::Bool(_)) => true,
+            (PhiDomain::Int { lo, hi }, Value::Int(n)) => lo <= n && n <= hi,
+            _ => false,
+        }
+    }
+}
+
+/// A declared Φ unknown: `Let { name, expr: "phi_unknown(<domain>)" }`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PhiUnknown {
+    pub name: String,
+    pub domain: PhiDomain,
+}
+
+/// Outcome of Φ admissibility resolution.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PhiResolution {
+    /// The first satisfying assignment in enumeration order.
+    Admissible { assignment: IndexMap<String, Value> },
+
+    /// No candidate satisfies the constraint set.
+    NonExistent {
+        refutation: PhiRefutation,
+        message: String,
+    },
+}
+
 /// Validate a Φ-regime procedure body under host-mode checks.
 ///
-/// Current v0.1 rules:
-/// - Evaluate each `Constrain { predicate }` against the provided environment.
-///
-/// This is intentionally conservative: it only validates that constraints are
-/// well-formed and true under the current environment assumptions.
+/// A proc is locally admissible iff resolution finds a satisfying assignment
+/// (for procs without unknowns: iff every constraint holds under `env`).
 pub fn validate_proc(
     proc_: &DirProc,
     env: &IndexMap<String, Value>,
 ) -> Result<PhiValidation, DvmError> {
-    if proc_.regime != "Φ" {
-        return Err(DvmError::Runtime(format!(
-            "phi::validate_proc called on non-Φ proc '{}'(regime={})",
-            proc_.name, proc_.regime
-        )));
+    Ok(match resolve_proc(proc_, env)? {
+        PhiResolution::Admissible { .. } => PhiValidation::LocallyAdmissible,
+        PhiResolution::NonExistent { message, .. } => {
+            PhiValidation::LocallyInadmissible { message }
+        }
+    })
+}
+
+/// Resolve a Φ-regime procedure's unknowns by bounded exhaustive search.
+///
+/// Candidates are refuted at the first `Constrain` that does not hold under
+/// them (false, or not a bool). A statement that cannot be evaluated is an
+/// error rather than a refutation.
+pub fn resolve_proc(
+    proc_: &DirProc,
+    env: &IndexMap<String, Value>,
+) -> Result<PhiResolution, DvmError> {
+    require_phi(proc_, "resolve_proc")?;
+    require_resolvable(proc_)?;
+
+    let unknowns = declared_unknowns(proc_)?;
+    let total = candidate_count(&unknowns)?;
+
+    let mut failures = Vec::new();
+    let mut first_reason = None;
+
+    for k in 0..total {
+        let assignment = nth_candidate(&unknowns, k)?;
+        match first_failure(proc_, env, &assignment) {
+            None => return Ok(PhiResolution::Admissible { assignment }),
+            Some(PhiStop::Refuted { index, reason }) => {
+                failures.push(index);
+                first_reason.get_or_insert(reason);
+            }
+            Some(stop @ PhiStop::Failed { .. }) => return Err(stop.into_error()),
+        }
     }
 
+    let message = if unknowns.is_empty() {
+        first_reason.unwrap_or_else(|| "empty candidate space".into())
+    } else {
+        format!(
+            "no admissible assignment: {total} candidate(s) over {} unknown(s) refuted",
+            unknowns.len()
+        )
+    };
+
+    Ok(PhiResolution::NonExistent {
+        refutation: PhiRefutation { unknowns, failures },
+        message,
+    })
+}
+
+/// Collect the unknowns declared in a Φ proc body, in declaration order.
+pub fn declared_unknowns(proc_: &DirProc) -> Result<Vec<PhiUnknown>, DvmError> {
+    let mut out = Vec::new();
     for stmt in &proc_.body {
-        if let crate::dir::DirStmt::Constrain { predicate } = stmt {
-            match crate::admissibility::check_predicate(predicate, env) {
-                Ok(()) => {}
-                Err(e) => {
-                    let msg = match e {
-                        crate::DvmError::Inadmissible(s) => s,
-                        crate::DvmError::ConstraintFailure(s) => s,
-                        other => other.to_string(),
-                    };
-                    return Ok(PhiValidation::LocallyInadmissible { message: msg });
+        if let crate::dir::DirStmt::Let { name, expr } = stmt {
+            if let Some(domain) = parse_unknown(expr) {
+                out.push(PhiUnknown {
+                    name: name.clone(),
+                    domain: domain?,
+                });
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Total number of candidate assignments over `unknowns`, bounded by `PHI_MAX_CANDIDATES`.
+pub fn candidate_count(unknowns: &[PhiUnknown]) -> Result<u64, DvmError> {
+    let mut total: u64 = 1;
+    for u in unknowns {
+        total = total
+            .checked_mul(u.domain.size()?)
+            .filter(|t| *t <= PHI_MAX_CANDIDATES)
+            .ok_or_else(|| {
+                DvmError::Inadmissible(format!(
+                    "Φ search space exceeds {PHI_MAX_CANDIDATES} candidates"
+                ))
+            })?;
+    }
+    Ok(total)
+}
+
+/// The `k`-th candidate assignment (the last unknown varies fastest); an error
+/// if `k` is outside the search space.
+pub fn nth_candidate(unknowns: &[PhiUnknown], k: u64) -> Result<IndexMap<String, Value>, DvmError> {
+    let outside = || DvmError::Inadmissible(format!("Φ candidate {k} is outside the search space"));
+    let mut rest = k;
+    let mut values = vec![Value::Unit; unknowns.len()];
+    for (i, u) in unknowns.iter().enumerate().rev() {
+        let size = u.domain.size()?;
+        if size == 0 {
+            return Err(outside());
+        }
+        values[i] = u.domain.nth(rest % size).ok_or_else(outside)?;
+        rest /= size;
+    }
+    if rest != 0 {
+        return Err(outside());
+    }
+    Ok(unknowns
+        .iter()
+        .map(|u| u.name.clone())
+        .zip(values)
+        .collect())
+}
+
+/// Why a replay stopped before `until`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PhiStop {
+    /// A `Constrain` does not hold under the candidate.
+    Refuted { index: usize, reason: String },
+
+    /// A statement cannot be evaluated (e.g. an unbound name): not a refutation.
+    Failed { index: usize, error: DvmError },
+}
+
+impl PhiStop {
+    /// The error a failed replay reports.
+    pub fn into_error(self) -> DvmError {
+        match self {
+            PhiStop::Refuted { reason, .. } => DvmError::Inadmissible(reason),
+            PhiStop::Failed { index, error } => DvmError::Runtime(format!(
+                "Φ statement #{index} cannot be evaluated: {}",
+                failure_message(error)
+            )),
+        }
+    }
+}
+
+/// Evaluate the body of a Φ proc under one candidate assignment.
+///
+/// Returns where the candidate is refuted or cannot be evaluated, or `None` if
+/// every constraint holds. Host intrinsics other than `phi_unknown` (e.g.
+/// `phi_witness`) do not participate in resolution.
+pub fn first_failure(
+    proc_: &DirProc,
+    env: &IndexMap<String, Value>,
+    assignment: &IndexMap<String, Value>,
+) -> Option<PhiStop> {
+    replay(proc_, env, assignment, proc_.body.len()).err()
+}
+
+/// Evaluate the first `until` body statements under one candidate assignment.
+///
+/// On success returns the bindings in scope after statement `until - 1`;
+/// otherwise where and why the replay stopped.
+/// `Call` statements are not evaluated during resolution: their results are
+/// only bound once the body executes, and `resolve_proc` refuses constraints
+/// that depend on them.
+pub fn replay(
+    proc_: &DirProc,
+    env: &IndexMap<String, Value>,
+    assignment: &IndexMap<String, Value>,
+    until: usize,
+) -> Result<IndexMap<String, Value>, PhiStop> {
+    use crate::dir::DirStmt;
+
+    let mut local = env.clone();
+    for (i, stmt) in proc_.body.iter().enumerate().take(until) {
+        match stmt {
+            DirStmt::Let { name, expr } => {
+                if parse_unknown(expr).is_some() {
+                    if let Some(v) = assignment.get(name) {
+                        local.insert(name.clone(), v.clone());
+                    }
+                } else if !is_host_intrinsic(expr) {
+                    match crate::expr::eval(expr, &local) {
+                        Ok(v) => {
+                            local.insert(name.clone(), v);
+                        }
+                        Err(error) => return Err(PhiStop::Failed { index: i, error }),
+                    }
                 }
             }
+            DirStmt::Constrain { predicate } => {
+                match crate::admissibility::check_predicate(predicate, &local) {
+                    Ok(()) => {}
+                    Err(e @ (DvmError::Inadmissible(_) | DvmError::ConstraintFailure(_))) => {
+                        return Err(PhiStop::Refuted {
+                            index: i,
+                            reason: failure_message(e),
+                        });
+                    }
+                    Err(error) => return Err(PhiStop::Failed { index: i, error }),
+                }
+            }
+            _ => {}
         }
     }
+    Ok(local)
+}
+
+/// Refuse procs whose constraints depend on bindings that only exist once the
+/// body executes (call results, `Prove` names, `phi_witness` lets, and lets
+/// derived from them): resolution cannot evaluate them, so a search would
+/// misreport the proc as unsatisfiable.
+fn require_resolvable(proc_: &DirProc) -> Result<(), DvmError> {
+    use crate::dir::DirStmt;
 
-    Ok(PhiValidation::LocallyAdmissible)
+    let mut deferred: IndexMap<String, &'static str> = IndexMap::new();
+    let depends = |deferred: &IndexMap<String, &'static str>, expr: &str| {
+        let parsed = crate::expr::parse(expr).ok()?;
+        parsed
+            .idents()
+            .into_iter()
+            .find_map(|id| deferred.get_key_value(id))
+            .map(|(name, origin)| (name.clone(), *origin))
+    };
+
+    for (i, stmt) in proc_.body.iter().enumerate() {
+        match stmt {
+            DirStmt::Let { name, expr } => {
+                let origin = if is_host_intrinsic(expr) {
+                    Some("phi_witness")
+                } else if parse_unknown(expr).is_some() {
+                    None
+                } else {
+                    depends(&deferred, expr).map(|(_, origin)| origin)
+                };
+                match origin {
+                    Some(origin) => deferred.insert(name.clone(), origin),
+                    None => deferred.shift_remove(name),
+                };
+            }
+            DirStmt::Call { name: Some(n), .. } => {
+                deferred.insert(n.clone(), "call result");
+            }
+            DirStmt::Prove { name, .. } => {
+                deferred.insert(name.clone(), "proof");
+            }
+            DirStmt::Constrain { predicate } => {
+                if let Some((name, origin)) = depends(&deferred, predicate) {
+                    return Err(DvmError::Runtime(format!(
+                        "Φ constraint at #{i} depends on '{name}' ({origin}), which is only \
+                         bound during execution and cannot be resolved: {predicate}"
+                    )));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Render a predicate failure as a refutation reason.
+pub(crate) fn failure_message(e: DvmError) -> String {
+    match e {
+        DvmError::Inadmissible(s) => s,
+        DvmError::ConstraintFailure(s) => s,
+        other => other.to_string(),
+    }
 }
 
-/// Canonical refusal for Φ execution in v0.1.
+/// Parse a `phi_unknown(...)` declaration.
 ///
-/// This message is part of the conformance surface.
-/// Change only with versioned trace updates.
-pub fn refuse_execution() -> DvmError {
-    DvmError::UnsupportedRegime("Φ-regime execution wiring into engine is a later step".into())
+/// Accepted forms: `phi_unknown(Bool)`, `phi_unknown(Int)` (default domain),
+/// and `phi_unknown(Int, <lo>, <hi>)`. Returns `None` if `expr` is not a
+/// `phi_unknown` call at all.
+pub fn parse_unknown(expr: &str) -> Option<Result<PhiDomain, DvmError>> {
+    let s = expr.trim();
+    let inner = s.strip_prefix("phi_unknown(")?.strip_suffix(')')?;
+    let parts: Vec<&str> = inner.split(',').map(str::trim).collect();
+
+    let parsed = match parts.as_slice() {
+        ["Bool"] => Ok(PhiDomain::Bool),
+        ["Int"] => Ok(PhiDomain::Int {
+            lo: PHI_DEFAULT_INT_DOMAIN.0,
+            hi: PHI_DEFAULT_INT_DOMAIN.1,
+        }),
+        ["Int", lo, hi] => match (lo.parse::<i64>(), hi.parse::<i64>()) {
+            (Ok(lo), Ok(hi)) if lo <= hi => Ok(PhiDomain::Int { lo, hi }),
+            _ => Err(DvmError::Runtime(format!(
+                "phi_unknown: invalid Int domain: {inner}"
+            ))),
+        },
+        _ => Err(DvmError::Runtime(format!(
+            "phi_unknown: unsupported domain: {inner}"
+        ))),
+    };
+    Some(parsed)
+}
+
+/// Whether `expr` is a Φ host intrinsic evaluated only during execution.
+fn is_host_intrinsic(expr: &str) -> bool {
+    expr.trim().starts_with("phi_witness(")
+}
+
+fn require_phi(proc_: &DirProc, what: &str) -> Result<(), DvmError> {
+    if proc_.regime != "Φ" {
+        return Err(DvmError::Runtime(format!(
+            "phi::{what} called on non-Φ proc '{}'(regime={})",
+            proc_.name, proc_.regime
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dir::DirStmt;
+    use crate::regime::testing::{constrain, let_, phi_proc};
+
+    #[test]
+    fn resolves_first_satisfying_assignment() {
+        let p = phi_proc(vec![
+            let_("x", "phi_unknown(Int, 0, 9)"),
+            let_("b", "phi_unknown(Bool)"),
+            constrain("x Mul x Eq 49"),
+            constrain("b"),
+        ]);
+        match resolve_proc(&p, &IndexMap::new()).unwrap() {
+            PhiResolution::Admissible { assignment } => {
+                assert_eq!(assignment.get("x"), Some(&Value::Int(7)));
+                assert_eq!(assignment.get("b"), Some(&Value::Bool(true)));
+            }
+            other => panic!("unexpected resolution: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn refutation_records_first_failure_per_candidate() {
+        let p = phi_proc(vec![
+            let_("b", "phi_unknown(Bool)"),
+            constrain("b"),
+            constrain("b Eq false"),
+        ]);
+        match resolve_proc(&p, &IndexMap::new()).unwrap() {
+            PhiResolution::NonExistent {
+                refutation,
+                message,
+            } => {
+                assert_eq!(refutation.failures, vec![1, 2]);
+                assert_eq!(refutation.unknowns.len(), 1);
+                assert_eq!(
+                    message,
+                    "no admissible assignment: 2 candidate(s) over 1 unknown(s) refuted"
+                );
+            }
+            other => panic!("unexpected resolution: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn without_unknowns_the_failing_constraint_is_the_reason() {
+        let p = phi_proc(vec![constrain("false")]);
+        assert_eq!(
+            validate_proc(&p, &IndexMap::new()).unwrap(),
+            PhiValidation::LocallyInadmissible {
+                message: "constraint failed: false".into()
+            }
+        );
+    }
+
+    #[test]
+    fn constraints_on_execution_bindings_are_refused() {
+        let call = DirStmt::Call {
+            name: Some("h".into()),
+            callee: "three".into(),
+            args: vec![],
+        };
+        let p = phi_proc(vec![
+            let_("x", "phi_unknown(Int, 0, 5)"),
+            call,
+            let_("g", "h Add 1"),
+            constrain("x Gt g"),
+        ]);
+        assert_eq!(
+            resolve_proc(&p, &IndexMap::new()).unwrap_err().to_string(),
+            "runtime error: Φ constraint at #3 depends on 'g' (call result), which is only \
+             bound during execution and cannot be resolved: x Gt g"
+        );
+
+        let p = phi_proc(vec![
+            let_("w", "phi_witness()"),
+            let_("w", "1"),
+            let_("x", "phi_unknown(Int, 0, 5)"),
+            constrain("x Gt w"),
+        ]);
+        assert!(matches!(
+            resolve_proc(&p, &IndexMap::new()).unwrap(),
+            PhiResolution::Admissible { .. }
+        ));
+    }
+
+    #[test]
+    fn domains_wider_than_u64_are_refused() {
+        let p = phi_proc(vec![
+            let_(
+                "x",
+                &format!("phi_unknown(Int, {}, {})", i64::MIN, i64::MAX),
+            ),
+            constrain("x Eq 0"),
+        ]);
+        assert_eq!(
+            resolve_proc(&p, &IndexMap::new()).unwrap_err().to_string(),
+            format!(
+                "inadmissible program: Φ domain {}..={} is too large to search",
+                i64::MIN,
+                i64::MAX
+            )
+        );
+
+        let top = PhiDomain::Int {
+            lo: i64::MAX - 1,
+            hi: i64::MAX,
+        };
+        assert_eq!(top.size().unwrap(), 2);
+        assert_eq!(top.nth(1), Some(Value::Int(i64::MAX)));
+        assert_eq!(top.nth(2), None);
+    }
+
+    #[test]
+    fn oversized_search_space_is_refused() {
+        let p = phi_proc(vec![
+            let_("a", "phi_unknown(Int, 0, 999)"),
+            let_("b", "phi_unknown(Int, 0, 999)"),
+        ]);
+        assert!(resolve_proc(&p, &IndexMap::new()).is_err());
+    }
 }
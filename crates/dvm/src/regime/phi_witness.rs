@@ -1,21 +1,35 @@
-//! Φ-regime witness stubs (v0.1).
+//! Φ-regime witnesses.
 //!
 //! In the Φ regime, execution is governed by admissibility and global consistency.
 //! A **witness** is the program-visible artifact that attests:
 //! - a constraint set is admissible under the regime rules, or
 //! - a constraint set is non-existent (inadmissible) under the regime rules.
 //!
-//! In v0.1, we do not build global witnesses yet.
-//! We provide deterministic **stub witness objects** so developers can:
-//! - write programs that request witnesses,
-//! - test compilation + runtime wiring,
-//! - build tooling around witness transport,
-
-//! - This module provides witness stubs
-//!   without Φ execution being implemented.
+//! Witnesses carry checkable evidence produced by Φ resolution:
+//! - `Admissible` witnesses carry the satisfying assignment of the proc's unknowns.
+//! - `NonExistent` witnesses carry an exhaustive-search refutation: the searched
+//!   domains and, for every candidate in enumeration order, the body index of the
+//!   statement that refuted it.
+//!
+//! Both record the param bindings the proc was resolved under (a Φ proc called
+//! with arguments), since the evidence only holds for those.
+//!
+//! `verify` re-checks a witness against the proc it describes without trusting
+//! the producer: assignments are re-evaluated, refutations are replayed, both
+//! under the recorded params.
+//!
+//! Constraint digests are computed by the DVM, never supplied by programs:
+//! SHA-256 over a canonical rendering of the constraint set (see `constraint_digest`),
+//! prefixed with the digest scheme version.
 
-use crate::DvmError;
+use super::phi::{self, PhiDomain, PhiStop, PhiUnknown};
+use crate::{dir::DirStmt, expr, DirProc, DvmError, Value};
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Version of the constraint digest scheme. Bump when the canonical rendering changes.
+pub const PHI_DIGEST_VERSION: &str = "v2";
 
 /// Witness kind for Φ-regime.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -27,13 +41,28 @@ pub enum PhiWitnessKind {
     NonExistent,
 }
 
+/// Exhaustive-search refutation certificate.
+///
+/// Candidates are enumerated as in `phi::nth_candidate`; `failures[k]` is the
+/// body index of the first statement that refutes candidate `k`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PhiRefutation {
+    /// Unknowns in declaration order, with the domains that were searched.
+    pub unknowns: Vec<PhiUnknown>,
+
+    /// First refuting body index for each candidate, in enumeration order.
+    pub failures: Vec<usize>,
+}
+
 /// A deterministic witness envelope.
 ///
-/// v0.1 guarantees:
+/// Guarantees:
 /// - fully deterministic fields
 /// - stable JSON serialization
 /// - no host-specific data
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct PhiWitness {
     /// Witness kind.
     pub kind: PhiWitnessKind,
@@ -41,20 +70,35 @@ pub struct PhiWitness {
     /// Deterministic identifier for this witness within a run.
     pub id: String,
 
-    /// Canonical digest of the constraint set (v0.1: string digest, not cryptographic).
-    ///
-    /// Future versions can switch to a cryptographic digest with versioning.
+    /// Canonical digest of the constraint set: `Φdigest:<version>:sha256:<hex>`.
     pub constraint_digest: String,
 
     /// Human-readable explanation string (stable, not verbose).
     pub note: String,
+
+    /// Param bindings the proc was resolved under, in declaration order.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub params: IndexMap<String, Value>,
+
+    /// Satisfying assignment (`Admissible` only): unknown name -> value, in declaration order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignment: Option<IndexMap<String, Value>>,
+
+    /// Refutation certificate (`NonExistent` only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refutation: Option<PhiRefutation>,
+
+    /// Caller-supplied label (`phi_witness(<label>)`); not part of the digest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
 }
 
-/// v0.1 witness builder:
-/// - Accepts a constraint digest string (caller-provided, deterministic).
+/// Witness builder:
+/// - Accepts a constraint digest string (see `constraint_digest` / `prove_digest`).
 /// - Returns a deterministic witness id.
-/// - Does not perform global proof construction.
-#[derive(Debug, Default)]
+/// - Packages resolution evidence; it does not search itself (see `phi::resolve_proc`).
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct PhiWitnessBuilder {
     counter: u64,
 }
@@ -69,60 +113,824 @@ impl PhiWitnessBuilder {
         format!("Φwitness:{}", self.counter)
     }
 
-    /// Construct a stub admissible witness.
-    pub fn admissible(&mut self, constraint_digest: &str) -> PhiWitness {
+    /// Construct an admissible witness carrying the satisfying assignment.
+    pub fn admissible(
+        &mut self,
+        constraint_digest: &str,
+        params: IndexMap<String, Value>,
+        assignment: IndexMap<String, Value>,
+    ) -> PhiWitness {
         PhiWitness {
             kind: PhiWitnessKind::Admissible,
             id: self.next_id(),
             constraint_digest: constraint_digest.to_string(),
-            note: "Φ witness stub (v0.1): admissible".into(),
+            note: "Φ witness: admissible".into(),
+            params,
+            assignment: Some(assignment),
+            refutation: None,
+            label: None,
         }
     }
 
-    /// Construct a stub non-existence witness.
-    pub fn non_existent(&mut self, constraint_digest: &str, reason: &str) -> PhiWitness {
+    /// Construct a non-existence witness carrying the refutation certificate.
+    pub fn non_existent(
+        &mut self,
+        constraint_digest: &str,
+        reason: &str,
+        params: IndexMap<String, Value>,
+        refutation: PhiRefutation,
+    ) -> PhiWitness {
         PhiWitness {
             kind: PhiWitnessKind::NonExistent,
             id: self.next_id(),
             constraint_digest: constraint_digest.to_string(),
-            note: format!("Φ witness stub (v0.1): non-existent: {reason}"),
+            note: format!("Φ witness: non-existent: {reason}"),
+            params,
+            assignment: None,
+            refutation: Some(refutation),
+            label: None,
+        }
+    }
+}
+
+impl PhiWitness {
+    /// Program-visible encoding: a `PhiWitness` Struct value.
+    ///
+    /// The encoding is lossless (see `from_value`), so a witness a program
+    /// observed as a value can still be verified.
+    pub fn to_value(&self) -> Value {
+        let mut fields = IndexMap::new();
+
+        let kind = match self.kind {
+            PhiWitnessKind::Admissible => "Admissible",
+            PhiWitnessKind::NonExistent => "NonExistent",
+        };
+        fields.insert("kind".to_string(), Value::String(kind.to_string()));
+        fields.insert("id".to_string(), Value::String(self.id.clone()));
+        fields.insert(
+            "constraint_digest".to_string(),
+            Value::String(self.constraint_digest.clone()),
+        );
+        fields.insert("note".to_string(), Value::String(self.note.clone()));
+
+        if !self.params.is_empty() {
+            fields.insert(
+                "params".to_string(),
+                structure("PhiParams", self.params.clone()),
+            );
+        }
+        if let Some(assignment) = &self.assignment {
+            fields.insert(
+                "assignment".to_string(),
+                structure("PhiAssignment", assignment.clone()),
+            );
+        }
+        if let Some(refutation) = &self.refutation {
+            fields.insert(
+                "refuted".to_string(),
+                Value::Int(refutation.failures.len() as i64),
+            );
+            fields.insert("refutation".to_string(), refutation_to_value(refutation));
+        }
+        if let Some(label) = &self.label {
+            fields.insert("label".to_string(), Value::String(label.clone()));
         }
+
+        structure("PhiWitness", fields)
+    }
+
+    /// Decode a witness from its value encoding (`to_value`).
+    pub fn from_value(value: &Value) -> Result<Self, DvmError> {
+        let fields = struct_fields(value, "PhiWitness")?;
+
+        let kind = match string_field(fields, "kind")?.as_str() {
+            "Admissible" => PhiWitnessKind::Admissible,
+            "NonExistent" => PhiWitnessKind::NonExistent,
+            other => return Err(malformed(format!("unknown witness kind '{other}'"))),
+        };
+        let optional = |name: &str, ty: &str| {
+            fields
+                .get(name)
+                .map(|v| struct_fields(v, ty).cloned())
+                .transpose()
+        };
+
+        Ok(PhiWitness {
+            kind,
+            id: string_field(fields, "id")?,
+            constraint_digest: string_field(fields, "constraint_digest")?,
+            note: string_field(fields, "note")?,
+            params: optional("params", "PhiParams")?.unwrap_or_default(),
+            assignment: optional("assignment", "PhiAssignment")?,
+            refutation: fields
+                .get("refutation")
+                .map(refutation_from_value)
+                .transpose()?,
+            label: fields
+                .get("label")
+                .map(|_| string_field(fields, "label"))
+                .transpose()?,
+        })
+    }
+}
+
+fn structure(ty: &str, fields: IndexMap<String, Value>) -> Value {
+    Value::Struct {
+        ty: ty.to_string(),
+        fields,
+    }
+}
+
+/// `PhiRefutation { unknowns: PhiUnknowns { <name>: <domain> }, failures: PhiFailures { "<k>": <index> } }`,
+/// where a domain is `"Bool"` or `Int { lo, hi }`.
+fn refutation_to_value(refutation: &PhiRefutation) -> Value {
+    let unknowns = refutation
+        .unknowns
+        .iter()
+        .map(|u| {
+            let domain = match u.domain {
+                PhiDomain::Bool => Value::String("Bool".into()),
+                PhiDomain::Int { lo, hi } => structure(
+                    "Int",
+                    IndexMap::from([
+                        ("lo".to_string(), Value::Int(lo)),
+                        ("hi".to_string(), Value::Int(hi)),
+                    ]),
+                ),
+            };
+            (u.name.clone(), domain)
+        })
+        .collect();
+    let failures = refutation
+        .failures
+        .iter()
+        .enumerate()
+        .map(|(k, &index)| (k.to_string(), Value::Int(index as i64)))
+        .collect();
+
+    structure(
+        "PhiRefutation",
+        IndexMap::from([
+            ("unknowns".to_string(), structure("PhiUnknowns", unknowns)),
+            ("failures".to_string(), structure("PhiFailures", failures)),
+        ]),
+    )
+}
+
+fn refutation_from_value(value: &Value) -> Result<PhiRefutation, DvmError> {
+    let fields = struct_fields(value, "PhiRefutation")?;
+    let field = |name: &str| {
+        fields
+            .get(name)
+            .ok_or_else(|| malformed(format!("PhiRefutation has no '{name}'")))
+    };
+
+    let mut unknowns = Vec::new();
+    for (name, domain) in struct_fields(field("unknowns")?, "PhiUnknowns")? {
+        let domain = match domain {
+            Value::String(s) if s == "Bool" => PhiDomain::Bool,
+            Value::Struct { ty, fields } if ty == "Int" => {
+                match (fields.get("lo"), fields.get("hi"), fields.len()) {
+                    (Some(Value::Int(lo)), Some(Value::Int(hi)), 2) => {
+                        PhiDomain::Int { lo: *lo, hi: *hi }
+                    }
+                    _ => return Err(malformed(format!("bad Int domain for '{name}'"))),
+                }
+            }
+            _ => return Err(malformed(format!("bad domain for '{name}'"))),
+        };
+        unknowns.push(PhiUnknown {
+            name: name.clone(),
+            domain,
+        });
+    }
+
+    let mut failures = Vec::new();
+    for (k, (key, index)) in struct_fields(field("failures")?, "PhiFailures")?
+        .iter()
+        .enumerate()
+    {
+        match index {
+            Value::Int(i) if *key == k.to_string() && *i >= 0 => failures.push(*i as usize),
+            _ => return Err(malformed(format!("bad failure entry '{key}'"))),
+        }
+    }
+
+    Ok(PhiRefutation { unknowns, failures })
+}
+
+fn struct_fields<'a>(
+    value: &'a Value,
+    expected: &str,
+) -> Result<&'a IndexMap<String, Value>, DvmError> {
+    match value {
+        Value::Struct { ty, fields } if ty == expected => Ok(fields),
+        other => Err(malformed(format!(
+            "expected a {expected} struct, got {other:?}"
+        ))),
+    }
+}
+
+fn string_field(fields: &IndexMap<String, Value>, name: &str) -> Result<String, DvmError> {
+    match fields.get(name) {
+        Some(Value::String(s)) => Ok(s.clone()),
+        Some(other) => Err(malformed(format!(
+            "'{name}' must be a String, got {other:?}"
+        ))),
+        None => Err(malformed(format!("missing '{name}'"))),
+    }
+}
+
+fn malformed(reason: String) -> DvmError {
+    DvmError::Runtime(format!("malformed PhiWitness value: {reason}"))
+}
+
+/// Digest of a Φ proc's constraint set under the given param bindings: the
+/// params (in declaration order), its declared unknowns (in declaration order),
+/// the `Let` definitions its constraints depend on (canonicalized, in body order)
+/// and its `Constrain` predicates (canonicalized, sorted, deduplicated).
+pub fn constraint_digest(
+    proc_: &DirProc,
+    params: &IndexMap<String, Value>,
+) -> Result<String, DvmError> {
+    digest_of(proc_, params, None)
+}
+
+/// Digest of a Φ proc's constraint set extended with a proved predicate (`Prove { from }`).
+pub fn prove_digest(
+    proc_: &DirProc,
+    params: &IndexMap<String, Value>,
+    from: &str,
+) -> Result<String, DvmError> {
+    digest_of(proc_, params, Some(from))
+}
+
+fn digest_of(
+    proc_: &DirProc,
+    params: &IndexMap<String, Value>,
+    proved: Option<&str>,
+) -> Result<String, DvmError> {
+    let mut text = format!("Φdigest:{PHI_DIGEST_VERSION}\n");
+
+    for (name, value) in params {
+        let value = serde_json::to_string(value)
+            .map_err(|e| DvmError::Runtime(format!("cannot digest param '{name}': {e}")))?;
+        text.push_str(&format!("param {name} {value}\n"));
+    }
+
+    let unknowns = phi::declared_unknowns(proc_)?;
+    for u in &unknowns {
+        let domain = match u.domain {
+            PhiDomain::Bool => "Bool".to_string(),
+            PhiDomain::Int { lo, hi } => format!("Int[{lo},{hi}]"),
+        };
+        text.push_str(&format!("unknown {} {domain}\n", u.name));
+    }
+
+    let constraints: Vec<&str> = proc_
+        .body
+        .iter()
+        .filter_map(|stmt| match stmt {
+            DirStmt::Constrain { predicate } => Some(predicate.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    let read: Vec<&str> = constraints.iter().copied().chain(proved).collect();
+    for (name, definition) in let_dependencies(proc_, &unknowns, &read) {
+        text.push_str(&format!("let {name} = {definition}\n"));
+    }
+
+    let mut constraints: Vec<String> = constraints.into_iter().map(canonical_predicate).collect();
+    constraints.sort();
+    constraints.dedup();
+    for c in constraints {
+        text.push_str(&format!("constraint {c}\n"));
+    }
+
+    if let Some(from) = proved {
+        text.push_str(&format!("prove {}\n", canonical_predicate(from)));
+    }
+
+    let hash = Sha256::digest(text.as_bytes());
+    let hex: String = hash.iter().map(|b| format!("{b:02x}")).collect();
+    Ok(format!("Φdigest:{PHI_DIGEST_VERSION}:sha256:{hex}"))
+}
+
+/// Non-unknown `Let` definitions the predicates depend on, directly or through
+/// other lets, canonicalized and in body order.
+fn let_dependencies<'a>(
+    proc_: &'a DirProc,
+    unknowns: &[PhiUnknown],
+    predicates: &[&str],
+) -> Vec<(&'a str, String)> {
+    let mut needed: Vec<String> = Vec::new();
+    let mut need = |text: &str| {
+        if let Ok(e) = expr::parse(text) {
+            needed.extend(e.idents().into_iter().map(str::to_string));
+        }
+    };
+    for p in predicates {
+        need(p);
+    }
+
+    // Walk backwards so a definition pulls in the lets it reads from.
+    let mut out = Vec::new();
+    for stmt in proc_.body.iter().rev() {
+        let DirStmt::Let { name, expr } = stmt else {
+            continue;
+        };
+        if unknowns.iter().any(|u| &u.name == name) || !needed.contains(name) {
+            continue;
+        }
+        if let Ok(e) = expr::parse(expr) {
+            needed.extend(e.idents().into_iter().map(str::to_string));
+        }
+        out.push((name.as_str(), canonical_predicate(expr)));
+    }
+    out.reverse();
+    out
+}
+
+/// Canonical rendering of an expression; unparsable ones are kept verbatim (trimmed).
+fn canonical_predicate(predicate: &str) -> String {
+    match expr::parse(predicate) {
+        Ok(e) => e.canonical(),
+        Err(_) => format!("raw {}", predicate.trim()),
     }
 }
 
-/// Canonical refusal for full witness construction in v0.1.
+/// Result of independently verifying a witness.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PhiVerification {
+    /// The witness attests what it claims about the proc.
+    Valid,
+
+    /// The witness does not hold for the proc.
+    Invalid { reason: String },
+}
+
+/// Verify a witness against the Φ proc it claims to describe.
 ///
-/// This is separate from execution refusal: witness *stubs* exist, but global proof does not.
-pub fn refuse_global_witness() -> DvmError {
-    DvmError::UnsupportedRegime("Φ global witness construction is not implemented in v0.1".into())
+/// The witness digest must identify the proc's constraint set, or (for
+/// admissible witnesses) the constraint set extended with one of its `Prove`
+/// predicates.
+///
+/// - `Admissible`: the assignment must bind exactly the proc's unknowns, in
+///   declaration order, to values within their declared domains, and every
+///   constraint (and the proved predicate, if any) must hold under it.
+/// - `NonExistent`: the certificate must cover the proc's declared unknowns and
+///   domains, and replaying each candidate must be refuted at the recorded index.
+///
+/// The witness params must bind exactly the proc's params; constraints are
+/// evaluated under them. A statement that cannot be evaluated makes the
+/// witness invalid: it never counts as a refutation.
+pub fn verify(witness: &PhiWitness, proc_: &DirProc) -> Result<PhiVerification, DvmError> {
+    if proc_.regime != "Φ" {
+        return Err(DvmError::Runtime(format!(
+            "phi_witness::verify called on non-Φ proc '{}'(regime={})",
+            proc_.name, proc_.regime
+        )));
+    }
+
+    let unknowns = phi::declared_unknowns(proc_)?;
+    let env = &witness.params;
+
+    let invalid = |reason: String| Ok(PhiVerification::Invalid { reason });
+
+    let bound: Vec<&str> = env.keys().map(String::as_str).collect();
+    let declared: Vec<&str> = proc_.params.iter().map(|p| p.name.as_str()).collect();
+    if bound != declared {
+        return invalid(format!(
+            "witness binds params [{}], proc declares [{}]",
+            bound.join(", "),
+            declared.join(", ")
+        ));
+    }
+
+    // Which claim does the digest identify? `None` = the constraint set itself,
+    // `Some(i)` = the constraint set plus the `Prove` at body index `i`.
+    let claim = if witness.constraint_digest == constraint_digest(proc_, env)? {
+        None
+    } else {
+        let mut found = None;
+        for (i, stmt) in proc_.body.iter().enumerate() {
+            if let DirStmt::Prove { from, .. } = stmt {
+                if witness.constraint_digest == prove_digest(proc_, env, from)? {
+                    found = Some(i);
+                    break;
+                }
+            }
+        }
+        match found {
+            Some(i) if witness.kind == PhiWitnessKind::Admissible => Some(i),
+            _ => return invalid("constraint digest does not match the proc".into()),
+        }
+    };
+
+    match witness.kind {
+        PhiWitnessKind::Admissible => {
+            let Some(assignment) = &witness.assignment else {
+                return invalid("admissible witness has no assignment".into());
+            };
+
+            let names: Vec<&str> = assignment.keys().map(String::as_str).collect();
+            let expected: Vec<&str> = unknowns.iter().map(|u| u.name.as_str()).collect();
+            if names != expected {
+                return invalid(format!(
+                    "assignment binds [{}], proc declares [{}]",
+                    names.join(", "),
+                    expected.join(", ")
+                ));
+            }
+
+            for u in &unknowns {
+                let v = &assignment[&u.name];
+                if !u.domain.contains(v) {
+                    return invalid(format!(
+                        "assignment value for {} is outside its domain: {v:?}",
+                        u.name
+                    ));
+                }
+            }
+
+            match phi::first_failure(proc_, env, assignment) {
+                None => {}
+                Some(PhiStop::Refuted { index, reason }) => {
+                    return invalid(format!("assignment refuted at statement {index}: {reason}"));
+                }
+                Some(stop @ PhiStop::Failed { .. }) => {
+                    return invalid(stop.into_error().to_string());
+                }
+            }
+
+            let Some(index) = claim else {
+                return Ok(PhiVerification::Valid);
+            };
+            let DirStmt::Prove { from, .. } = &proc_.body[index] else {
+                unreachable!("claim index always refers to a Prove");
+            };
+            let scope = match phi::replay(proc_, env, assignment, index) {
+                Ok(scope) => scope,
+                Err(stop) => return invalid(stop.into_error().to_string()),
+            };
+            match crate::admissibility::check_predicate(from, &scope) {
+                Ok(()) => Ok(PhiVerification::Valid),
+                Err(e) => invalid(format!(
+                    "proved predicate does not hold: {}",
+                    phi::failure_message(e)
+                )),
+            }
+        }
+
+        PhiWitnessKind::NonExistent => {
+            let Some(refutation) = &witness.refutation else {
+                return invalid("non-existence witness has no refutation".into());
+            };
+
+            if refutation.unknowns != unknowns {
+                return invalid("refutation unknowns do not match the proc's declarations".into());
+            }
+
+            let total = phi::candidate_count(&unknowns)?;
+            if refutation.failures.len() as u64 != total {
+                return invalid(format!(
+                    "refutation covers {} candidate(s), search space has {total}",
+                    refutation.failures.len()
+                ));
+            }
+
+            for (k, claimed) in refutation.failures.iter().enumerate() {
+                let candidate = phi::nth_candidate(&unknowns, k as u64)?;
+                match phi::first_failure(proc_, env, &candidate) {
+                    Some(PhiStop::Refuted { index, .. }) if index == *claimed => {}
+                    Some(PhiStop::Refuted { index, .. }) => {
+                        return invalid(format!(
+                            "candidate {k} is refuted at statement {index}, certificate claims {claimed}"
+                        ));
+                    }
+                    Some(stop @ PhiStop::Failed { .. }) => {
+                        return invalid(format!("candidate {k}: {}", stop.into_error()));
+                    }
+                    None => {
+                        return invalid(format!("candidate {k} satisfies every constraint"));
+                    }
+                }
+            }
+
+            Ok(PhiVerification::Valid)
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dir::DirStmt;
+    use crate::regime::testing::{constrain, let_, phi_proc};
+
+    fn unknown_x_with(predicate: &str) -> DirProc {
+        phi_proc(vec![
+            let_("x", "phi_unknown(Int, 0, 3)"),
+            constrain(predicate),
+        ])
+    }
 
     #[test]
     fn witness_ids_are_deterministic() {
         let mut b = PhiWitnessBuilder::new();
-        let w1 = b.admissible("c0");
-        let w2 = b.non_existent("c1", "failed");
+        let w1 = b.admissible("c0", IndexMap::new(), IndexMap::new());
+        let w2 = b.non_existent(
+            "c1",
+            "failed",
+            IndexMap::new(),
+            PhiRefutation {
+                unknowns: vec![],
+                failures: vec![0],
+            },
+        );
         assert_eq!(w1.id, "Φwitness:1");
         assert_eq!(w2.id, "Φwitness:2");
         assert_eq!(w1.constraint_digest, "c0");
         assert_eq!(w2.constraint_digest, "c1");
         assert_eq!(w1.kind, PhiWitnessKind::Admissible);
         assert_eq!(w2.kind, PhiWitnessKind::NonExistent);
+        assert!(w1.refutation.is_none());
+        assert!(w2.assignment.is_none());
     }
 
     #[test]
     fn witness_serialization_is_stable() {
         let mut b = PhiWitnessBuilder::new();
-        let w = b.admissible("digest:example");
+        let mut assignment = IndexMap::new();
+        assignment.insert("x".to_string(), Value::Int(7));
+        let w = b.admissible("digest:example", IndexMap::new(), assignment);
         let s = serde_json::to_string(&w).unwrap();
         // Sanity: key fields exist.
         assert!(s.contains("\"kind\""));
         assert!(s.contains("\"id\""));
         assert!(s.contains("\"constraint_digest\""));
         assert!(s.contains("digest:example"));
+        assert!(s.contains("\"assignment\":{\"x\":{\"Int\":7}}"));
+        assert!(!s.contains("\"refutation\""));
+
+        let back: PhiWitness = serde_json::from_str(&s).unwrap();
+        assert_eq!(back, w);
+    }
+
+    #[test]
+    fn value_encoding_round_trips() {
+        let mut b = PhiWitnessBuilder::new();
+        let params = IndexMap::from([("sq".to_string(), Value::Int(2))]);
+        let refutation = PhiRefutation {
+            unknowns: vec![
+                PhiUnknown {
+                    name: "b".into(),
+                    domain: PhiDomain::Bool,
+                },
+                PhiUnknown {
+                    name: "x".into(),
+                    domain: PhiDomain::Int { lo: -1, hi: 0 },
+                },
+            ],
+            failures: vec![1, 2, 1, 3],
+        };
+        let mut admissible = b.admissible(
+            "c0",
+            params.clone(),
+            IndexMap::from([("x".into(), Value::Int(3))]),
+        );
+        admissible.label = Some("root".into());
+        let non_existent = b.non_existent("c1", "no", params, refutation);
+
+        for w in [admissible, non_existent] {
+            assert_eq!(PhiWitness::from_value(&w.to_value()).unwrap(), w);
+        }
+
+        assert_eq!(
+            PhiWitness::from_value(&Value::Int(1))
+                .unwrap_err()
+                .to_string(),
+            "runtime error: malformed PhiWitness value: expected a PhiWitness struct, got Int(1)"
+        );
+    }
+
+    #[test]
+    fn digest_is_invariant_under_equivalent_rewrites() {
+        let a = unknown_x_with("(x Add 1) Gt 2");
+        let b = unknown_x_with("2 Lt 1 Add x");
+        let c = unknown_x_with("x Add 1 Ge 2");
+
+        let da = constraint_digest(&a, &IndexMap::new()).unwrap();
+        assert_eq!(da, constraint_digest(&b, &IndexMap::new()).unwrap());
+        assert_ne!(da, constraint_digest(&c, &IndexMap::new()).unwrap());
+        assert!(da.starts_with("Φdigest:v2:sha256:"));
+        assert_eq!(da.len(), "Φdigest:v2:sha256:".len() + 64);
+
+        assert_ne!(da, prove_digest(&a, &IndexMap::new(), "x Gt 0").unwrap());
+        assert_eq!(
+            prove_digest(&a, &IndexMap::new(), "x Gt 0").unwrap(),
+            prove_digest(&b, &IndexMap::new(), "0 Lt x").unwrap()
+        );
+    }
+
+    #[test]
+    fn digest_covers_let_definitions_and_params() {
+        let via = |definition: &str| {
+            let p = phi_proc(vec![
+                let_("x", "phi_unknown(Int, 0, 3)"),
+                let_("unused", "x Sub 1"),
+                let_("y", definition),
+                constrain("y Gt 2"),
+            ]);
+            constraint_digest(&p, &IndexMap::new()).unwrap()
+        };
+        assert_ne!(via("x Add 1"), via("x Mul 0"));
+        assert_eq!(via("x Add 1"), via("1 Add x"));
+
+        let mut p = phi_proc(vec![
+            let_("x", "phi_unknown(Int, 0, 3)"),
+            let_("unused", "x Sub 2"),
+            let_("y", "x Add 1"),
+            constrain("y Gt 2"),
+        ]);
+        assert_eq!(
+            constraint_digest(&p, &IndexMap::new()).unwrap(),
+            via("x Add 1")
+        );
+
+        p.params.push(crate::dir::DirParam {
+            name: "sq".into(),
+            ty: "Int".into(),
+        });
+        let with = |sq: i64| IndexMap::from([("sq".to_string(), Value::Int(sq))]);
+        assert_ne!(
+            constraint_digest(&p, &with(2)).unwrap(),
+            constraint_digest(&p, &with(9)).unwrap()
+        );
+    }
+
+    #[test]
+    fn verify_accepts_resolved_assignment_and_rejects_tampering() {
+        let p = unknown_x_with("x Eq 2");
+        let assignment = match phi::resolve_proc(&p, &IndexMap::new()).unwrap() {
+            phi::PhiResolution::Admissible { assignment } => assignment,
+            other => panic!("unexpected resolution: {other:?}"),
+        };
+
+        let mut b = PhiWitnessBuilder::new();
+        let digest = constraint_digest(&p, &IndexMap::new()).unwrap();
+        let mut w = b.admissible(&digest, IndexMap::new(), assignment);
+        assert_eq!(verify(&w, &p).unwrap(), PhiVerification::Valid);
+
+        w.assignment
+            .as_mut()
+            .unwrap()
+            .insert("x".into(), Value::Int(3));
+        assert!(matches!(
+            verify(&w, &p).unwrap(),
+            PhiVerification::Invalid { .. }
+        ));
+    }
+
+    #[test]
+    fn verify_replays_refutation() {
+        let p = unknown_x_with("x Gt 5");
+        let refutation = match phi::resolve_proc(&p, &IndexMap::new()).unwrap() {
+            phi::PhiResolution::NonExistent { refutation, .. } => refutation,
+            other => panic!("unexpected resolution: {other:?}"),
+        };
+
+        let mut b = PhiWitnessBuilder::new();
+        let digest = constraint_digest(&p, &IndexMap::new()).unwrap();
+        let w = b.non_existent(&digest, "no", IndexMap::new(), refutation.clone());
+        assert_eq!(verify(&w, &p).unwrap(), PhiVerification::Valid);
+
+        // The same certificate does not refute a satisfiable proc.
+        let satisfiable = unknown_x_with("x Gt 2");
+        let mut w = w;
+        w.constraint_digest = constraint_digest(&satisfiable, &IndexMap::new()).unwrap();
+        assert_eq!(
+            verify(&w, &satisfiable).unwrap(),
+            PhiVerification::Invalid {
+                reason: "candidate 3 satisfies every constraint".into()
+            }
+        );
+
+        // A truncated certificate is rejected.
+        let mut short = refutation;
+        short.failures.pop();
+        let w = b.non_existent(&digest, "no", IndexMap::new(), short);
+        assert!(matches!(
+            verify(&w, &p).unwrap(),
+            PhiVerification::Invalid { .. }
+        ));
+    }
+
+    #[test]
+    fn verify_replays_under_the_recorded_params() {
+        let mut root = phi_proc(vec![
+            let_("x", "phi_unknown(Int, 0, 8)"),
+            constrain("x Mul x Eq sq"),
+        ]);
+        root.params.push(crate::dir::DirParam {
+            name: "sq".into(),
+            ty: "Int".into(),
+        });
+        let params = |sq: i64| IndexMap::from([("sq".to_string(), Value::Int(sq))]);
+        let digest = |sq: i64| constraint_digest(&root, &params(sq)).unwrap();
+        let mut b = PhiWitnessBuilder::new();
+
+        // Genuine: root(9) is satisfied by x = 3.
+        let assignment = match phi::resolve_proc(&root, &params(9)).unwrap() {
+            phi::PhiResolution::Admissible { assignment } => assignment,
+            other => panic!("unexpected resolution: {other:?}"),
+        };
+        let genuine = b.admissible(&digest(9), params(9), assignment.clone());
+        assert_eq!(verify(&genuine, &root).unwrap(), PhiVerification::Valid);
+        let unbound = b.admissible(&digest(9), IndexMap::new(), assignment);
+        assert_eq!(
+            verify(&unbound, &root).unwrap(),
+            PhiVerification::Invalid {
+                reason: "witness binds params [], proc declares [sq]".into()
+            }
+        );
+
+        // Forged: root(2)'s refutation does not refute root(9).
+        let refutation = match phi::resolve_proc(&root, &params(2)).unwrap() {
+            phi::PhiResolution::NonExistent { refutation, .. } => refutation,
+            other => panic!("unexpected resolution: {other:?}"),
+        };
+        let w = b.non_existent(&digest(2), "no", params(2), refutation.clone());
+        assert_eq!(verify(&w, &root).unwrap(), PhiVerification::Valid);
+        let forged = b.non_existent(&digest(9), "no", params(9), refutation);
+        assert_eq!(
+            verify(&forged, &root).unwrap(),
+            PhiVerification::Invalid {
+                reason: "candidate 3 satisfies every constraint".into()
+            }
+        );
+    }
+
+    #[test]
+    fn evaluation_errors_never_refute() {
+        let p = unknown_x_with("x Gt missing");
+        assert_eq!(
+            phi::resolve_proc(&p, &IndexMap::new())
+                .unwrap_err()
+                .to_string(),
+            "runtime error: Φ statement #1 cannot be evaluated: \
+             runtime error: unknown identifier: missing"
+        );
+
+        let refutation = PhiRefutation {
+            unknowns: phi::declared_unknowns(&p).unwrap(),
+            failures: vec![1; 4],
+        };
+        let digest = constraint_digest(&p, &IndexMap::new()).unwrap();
+        let w = PhiWitnessBuilder::new().non_existent(&digest, "no", IndexMap::new(), refutation);
+        assert!(matches!(
+            verify(&w, &p).unwrap(),
+            PhiVerification::Invalid { reason } if reason.starts_with("candidate 0: ")
+        ));
+    }
+
+    #[test]
+    fn verify_checks_proved_predicate_and_digest() {
+        let mut p = unknown_x_with("x Gt 0");
+        p.body.push(DirStmt::Prove {
+            name: "p".into(),
+            from: "x Eq 1".into(),
+        });
+
+        let mut assignment = IndexMap::new();
+        assignment.insert("x".to_string(), Value::Int(1));
+
+        let mut b = PhiWitnessBuilder::new();
+        let proved = b.admissible(
+            &prove_digest(&p, &IndexMap::new(), "x Eq 1").unwrap(),
+            IndexMap::new(),
+            assignment.clone(),
+        );
+        assert_eq!(verify(&proved, &p).unwrap(), PhiVerification::Valid);
+
+        assignment.insert("x".to_string(), Value::Int(2));
+        let wrong = b.admissible(
+            &prove_digest(&p, &IndexMap::new(), "x Eq 1").unwrap(),
+            IndexMap::new(),
+            assignment.clone(),
+        );
+        assert!(matches!(
+            verify(&wrong, &p).unwrap(),
+            PhiVerification::Invalid { .. }
+        ));
+
+        let forged = b.admissible("digest:example", IndexMap::new(), assignment);
+        assert_eq!(
+            verify(&forged, &p).unwrap(),
+            PhiVerification::Invalid {
+                reason: "constraint digest does not match the proc".into()
+            }
+        );
     }
 }
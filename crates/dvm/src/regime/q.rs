@@ -62,6 +62,10 @@ pub struct QState {
 
     /// Deterministic allocation counter
     alloc_counter: u64,
+
+    /// Suspended caller name environments (innermost caller last).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    frames: Vec<IndexMap<String, QBinding>>,
 }
 
 impl QState {
@@ -110,33 +114,57 @@ impl QState {
             )));
         }
 
-        let src_binding = self.env.get(src).cloned().ok_or_else(|| {
-            DvmError::Inadmissible(format!("Q move failed: unknown binding: {src}"))
-        })?;
+        let res = self.take(src, "q_move")?;
 
-        if src_binding.moved {
-            return Err(DvmError::Inadmissible(format!(
-                "Q move failed: binding already moved: {src}"
-            )));
-        }
+        // Create dst binding
+        self.env
+            .insert(dst.to_string(), QBinding { res, moved: false });
 
-        // Ensure resource is live
-        self.ensure_live(&src_binding.res, "q_move", src)?;
+        Ok(())
+    }
 
-        // Mark src as moved
-        if let Some(b) = self.env.get_mut(src) {
-            b.moved = true;
-        }
+    /// Whether `name` is a Q binding in the current frame (moved or not).
+    pub fn is_bound(&self, name: &str) -> bool {
+        self.env.contains_key(name)
+    }
 
-        // Create dst binding
-        self.env.insert(
-            dst.to_string(),
-            QBinding {
-                res: src_binding.res,
-                moved: false,
-            },
-        );
+    /// Enter a Q callee frame, moving each `(caller binding, callee param)` pair.
+    ///
+    /// The caller's bindings are marked moved before the callee runs; the callee
+    /// starts with only the transferred bindings in scope.
+    pub fn enter_call(&mut self, transfers: &[(String, String)]) -> Result<(), DvmError> {
+        let mut callee = IndexMap::new();
+        for (src, dst) in transfers {
+            let res = self.take(src, "q_call")?;
+            callee.insert(dst.clone(), QBinding { res, moved: false });
+        }
+        let caller = std::mem::replace(&mut self.env, callee);
+        self.frames.push(caller);
+        Ok(())
+    }
 
+    /// Leave the current callee frame, optionally moving the returned binding
+    /// (`(callee binding, caller destination)`) back into the caller.
+    pub fn exit_call(&mut self, ret: Option<(&str, &str)>) -> Result<(), DvmError> {
+        let res = match ret {
+            Some((src, _)) => Some(self.take(src, "q_return")?),
+            None => None,
+        };
+
+        self.env = self
+            .frames
+            .pop()
+            .ok_or_else(|| DvmError::Runtime("Q internal: call frame underflow".into()))?;
+
+        if let (Some(res), Some((_, dst))) = (res, ret) {
+            if self.env.contains_key(dst) {
+                return Err(DvmError::Inadmissible(format!(
+                    "Q move failed: destination already bound: {dst}"
+                )));
+            }
+            self.env
+                .insert(dst.to_string(), QBinding { res, moved: false });
+        }
         Ok(())
     }
 
@@ -199,6 +227,19 @@ impl QState {
         Ok(meta.ty.clone())
     }
 
+    /// Bindings of the current frame that still own a live resource (neither
+    /// moved nor consumed), in binding order.
+    pub fn live_bindings(&self) -> Vec<&str> {
+        self.env
+            .iter()
+            .filter(|(_, b)| {
+                !b.moved
+                    && matches!(self.resources.get(&b.res), Some(m) if m.state == QResState::Live)
+            })
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
     /// Deterministic snapshot for diagnostics and debugging.
     pub fn snapshot(&self) -> QSnapshot {
         QSnapshot {
@@ -212,6 +253,29 @@ impl QState {
     // internal helpers
     // -------------------------
 
+    /// Mark a live binding moved and return its resource.
+    pub(crate) fn take(&mut self, src: &str, op: &str) -> Result<QResId, DvmError> {
+        let src_binding = self.env.get(src).cloned().ok_or_else(|| {
+            DvmError::Inadmissible(format!("Q move failed: unknown binding: {src}"))
+        })?;
+
+        if src_binding.moved {
+            return Err(DvmError::Inadmissible(format!(
+                "Q move failed: binding already moved: {src}"
+            )));
+        }
+
+        // Ensure resource is live
+        self.ensure_live(&src_binding.res, op, src)?;
+
+        // Mark src as moved
+        if let Some(b) = self.env.get_mut(src) {
+            b.moved = true;
+        }
+
+        Ok(src_binding.res)
+    }
+
     fn fresh_id(&mut self, hint: &str) -> QResId {
         self.alloc_counter = self.alloc_counter.saturating_add(1);
         QResId(format!("qres:{}:{}", hint, self.alloc_counter))
@@ -223,6 +287,12 @@ impl QState {
         format!("Q use failed: binding already moved: {name} (op={op})")
     }
 
+    /// Diagnostic for a binding still owning a live resource when its proc returns.
+    pub(crate) fn err_leak(name: &str) -> String {
+        // CANONICAL ERROR STRING (stable conformance surface)
+        format!("Q leak: binding still live at return: {name}")
+    }
+
     fn ensure_live(&self, id: &QResId, op: &str, binding_name: &str) -> Result<(), DvmError> {
         let meta = self.resources.get(id).ok_or_else(|| {
             DvmError::Runtime(format!(
@@ -282,6 +352,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn call_frames_transfer_ownership_both_ways() {
+        let mut q = QState::new();
+        q.alloc("a", "QBit").unwrap();
+
+        q.enter_call(&[("a".into(), "p".into())]).unwrap();
+        assert!(q.require_usable("p", "H").is_ok());
+        assert!(!q.is_bound("a"));
+        q.exit_call(Some(("p", "r"))).unwrap();
+
+        assert!(q.require_usable("r", "H").is_ok());
+        let err = q.require_usable("a", "q_use").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "inadmissible program: Q use failed: binding already moved: a (op=q_use)"
+        );
+    }
+
+    #[test]
+    fn moved_binding_cannot_be_passed_to_a_callee() {
+        let mut q = QState::new();
+        q.alloc("a", "QBit").unwrap();
+        q.mov("a", "b").unwrap();
+
+        let err = q.enter_call(&[("a".into(), "p".into())]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "inadmissible program: Q move failed: binding already moved: a"
+        );
+    }
+
     #[test]
     fn consume_invalidates_all_aliases() {
         let mut q = QState::new();
@@ -0,0 +1,387 @@
+//! Semantic differences between two traces.
+//!
+//! Rather than a textual diff, `diff` reports what changed in terms of the trace
+//! surface: the outcome kind, the error, the returned value (with a path into
+//! nested structs), the first diverging effect, the final tick, and the first
+//! diverging step record. The diff is empty exactly when the traces are equal.
+
+use super::DvmTrace;
+use crate::{EffectEvent, StepRecord, Value};
+use std::fmt;
+
+/// The differences between a left and a right trace, in trace-field order.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TraceDiff {
+    pub differences: Vec<TraceDifference>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceDifference {
+    /// One trace is a success and the other a failure.
+    Outcome {
+        left: &'static str,
+        right: &'static str,
+    },
+
+    /// `error.kind`, `error.message` or `error.span` differs.
+    Error {
+        field: &'static str,
+        left: String,
+        right: String,
+    },
+
+    /// The returned value differs at `path` (`returned`, `returned.a.b`, ...);
+    /// `None` where the value or field is absent.
+    Returned {
+        path: String,
+        left: Option<Value>,
+        right: Option<Value>,
+    },
+
+    /// The first diverging effect has a different kind.
+    EffectKind {
+        index: usize,
+        left: String,
+        right: String,
+    },
+
+    /// The first diverging effect has the same kind but a different payload.
+    EffectPayload {
+        index: usize,
+        kind: String,
+        left: String,
+        right: String,
+    },
+
+    /// The effect logs agree on a common prefix but differ in length.
+    EffectCount { left: usize, right: usize },
+
+    /// The final logical tick differs.
+    Tick { left: u64, right: u64 },
+
+    /// The first diverging step record (`index` into `steps`).
+    Step { index: usize },
+
+    /// Another field (`schema_version`, `witness`, `explanation`) differs.
+    Field { field: &'static str },
+}
+
+impl TraceDiff {
+    pub fn is_empty(&self) -> bool {
+        self.differences.is_empty()
+    }
+}
+
+/// Compare two traces.
+pub fn diff(left: &DvmTrace, right: &DvmTrace) -> TraceDiff {
+    let mut out = Vec::new();
+    let l = View::of(left);
+    let r = View::of(right);
+
+    if l.schema_version != r.schema_version {
+        out.push(TraceDifference::Field {
+            field: "schema_version",
+        });
+    }
+
+    match (left, right) {
+        (DvmTrace::Failure(a), DvmTrace::Failure(b)) => {
+            if a.error.kind != b.error.kind {
+                out.push(TraceDifference::Error {
+                    field: "kind",
+                    left: a.error.kind.clone(),
+                    right: b.error.kind.clone(),
+                });
+            }
+            if a.error.message != b.error.message {
+                out.push(TraceDifference::Error {
+                    field: "message",
+                    left: a.error.message.clone(),
+                    right: b.error.message.clone(),
+                });
+            }
+            if a.error.span != b.error.span {
+                let show = |span: &Option<Box<crate::DirSpan>>| match span {
+                    Some(span) => span.to_string(),
+                    None => "<none>".into(),
+                };
+                out.push(TraceDifference::Error {
+                    field: "span",
+                    left: show(&a.error.span),
+                    right: show(&b.error.span),
+                });
+            }
+        }
+        (DvmTrace::Success(a), DvmTrace::Success(b)) => {
+            diff_value(
+                "returned".into(),
+                a.returned.as_ref(),
+                b.returned.as_ref(),
+                &mut out,
+            );
+        }
+        _ => out.push(TraceDifference::Outcome {
+            left: l.outcome,
+            right: r.outcome,
+        }),
+    }
+
+    diff_effects(l.effects, r.effects, &mut out);
+
+    if l.tick != r.tick {
+        out.push(TraceDifference::Tick {
+            left: l.tick,
+            right: r.tick,
+        });
+    }
+
+    if let (DvmTrace::Failure(a), DvmTrace::Failure(b)) = (left, right) {
+        if a.witness != b.witness {
+            out.push(TraceDifference::Field { field: "witness" });
+        }
+        if a.explanation != b.explanation {
+            out.push(TraceDifference::Field {
+                field: "explanation",
+            });
+        }
+    }
+
+    if l.steps != r.steps {
+        let index = l
+            .steps
+            .iter()
+            .zip(r.steps)
+            .position(|(a, b)| a != b)
+            .unwrap_or_else(|| l.steps.len().min(r.steps.len()));
+        out.push(TraceDifference::Step { index });
+    }
+
+    TraceDiff { differences: out }
+}
+
+/// The fields shared by both trace variants.
+struct View<'a> {
+    outcome: &'static str,
+    schema_version: u32,
+    effects: &'a [EffectEvent],
+    tick: u64,
+    steps: &'a [StepRecord],
+}
+
+impl<'a> View<'a> {
+    fn of(trace: &'a DvmTrace) -> Self {
+        match trace {
+            DvmTrace::Success(t) => View {
+                outcome: "success",
+                schema_version: t.schema_version,
+                effects: &t.effects.events,
+                tick: t.time.tick.0,
+                steps: &t.steps,
+            },
+            DvmTrace::Failure(t) => View {
+                outcome: "failure",
+                schema_version: t.schema_version,
+                effects: t.effects.as_ref().map_or(&[], |e| &e.events),
+                tick: t.time.as_ref().map_or(0, |t| t.tick.0),
+                steps: &t.steps,
+            },
+        }
+    }
+}
+
+fn diff_value(path: String, l: Option<&Value>, r: Option<&Value>, out: &mut Vec<TraceDifference>) {
+    match (l, r) {
+        (
+            Some(Value::Struct { ty: lt, fields: lf }),
+            Some(Value::Struct { ty: rt, fields: rf }),
+        ) if lt == rt => {
+            for (name, lv) in lf {
+                diff_value(format!("{path}.{name}"), Some(lv), rf.get(name), out);
+            }
+            for (name, rv) in rf {
+                if !lf.contains_key(name) {
+                    diff_value(format!("{path}.{name}"), None, Some(rv), out);
+                }
+            }
+        }
+        (l, r) if l != r => out.push(TraceDifference::Returned {
+            path,
+            left: l.cloned(),
+            right: r.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+fn diff_effects(l: &[EffectEvent], r: &[EffectEvent], out: &mut Vec<TraceDifference>) {
+    let first = l.iter().zip(r).enumerate().find(|(_, (a, b))| a != b);
+    match first {
+        Some((index, (a, b))) if a.kind != b.kind => out.push(TraceDifference::EffectKind {
+            index,
+            left: a.kind.clone(),
+            right: b.kind.clone(),
+        }),
+        Some((index, (a, b))) => out.push(TraceDifference::EffectPayload {
+            index,
+            kind: a.kind.clone(),
+            left: a.payload.clone(),
+            right: b.payload.clone(),
+        }),
+        None if l.len() != r.len() => out.push(TraceDifference::EffectCount {
+            left: l.len(),
+            right: r.len(),
+        }),
+        None => {}
+    }
+}
+
+impl fmt::Display for TraceDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn opt(v: &Option<Value>) -> String {
+            v.as_ref().map_or("<absent>".into(), Value::to_string)
+        }
+
+        match self {
+            TraceDifference::Outcome { left, right } => write!(f, "outcome: {left} vs {right}"),
+            TraceDifference::Error { field, left, right } => {
+                write!(f, "error.{field}: {left:?} vs {right:?}")
+            }
+            TraceDifference::Returned { path, left, right } => {
+                write!(f, "{path}: {} vs {}", opt(left), opt(right))
+            }
+            TraceDifference::EffectKind { index, left, right } => {
+                write!(f, "effects[{index}].kind: {left} vs {right}")
+            }
+            TraceDifference::EffectPayload {
+                index,
+                kind,
+                left,
+                right,
+            } => write!(
+                f,
+                "effects[{index}].payload ({kind}): {left:?} vs {right:?}"
+            ),
+            TraceDifference::EffectCount { left, right } => write!(
+                f,
+                "effects: {left} vs {right} event(s); first extra at index {}",
+                left.min(right)
+            ),
+            TraceDifference::Tick { left, right } => write!(
+                f,
+                "time.tick: {left} vs {right} (delta {:+})",
+                *right as i128 - *left as i128
+            ),
+            TraceDifference::Step { index } => write!(f, "steps[{index}]: differs"),
+            TraceDifference::Field { field } => write!(f, "{field}: differs"),
+        }
+    }
+}
+
+impl fmt::Display for TraceDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for d in &self.differences {
+            writeln!(f, "{d}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DvmError, DvmFailureTrace, DvmSuccessTrace, EffectLog, TimeState};
+    use indexmap::IndexMap;
+
+    fn success(returned: Value, effects: &[(&str, &str)], tick: u64) -> DvmTrace {
+        let mut log = EffectLog::default();
+        for (k, p) in effects {
+            log.push(*k, *p);
+        }
+        let mut time = TimeState::default();
+        time.tick.0 = tick;
+        DvmTrace::Success(DvmSuccessTrace {
+            schema_version: super::super::TRACE_SCHEMA_VERSION,
+            returned: Some(returned),
+            effects: log,
+            time,
+            steps: Vec::new(),
+        })
+    }
+
+    fn point(x: i64, y: i64) -> Value {
+        let mut inner = IndexMap::new();
+        inner.insert("y".to_string(), Value::Int(y));
+        let mut fields = IndexMap::new();
+        fields.insert("x".to_string(), Value::Int(x));
+        fields.insert(
+            "inner".to_string(),
+            Value::Struct {
+                ty: "Inner".into(),
+                fields: inner,
+            },
+        );
+        Value::Struct {
+            ty: "Point".into(),
+            fields,
+        }
+    }
+
+    #[test]
+    fn equal_traces_have_no_differences() {
+        let a = success(point(1, 2), &[("emit", "a")], 3);
+        assert!(diff(&a, &a.clone()).is_empty());
+    }
+
+    #[test]
+    fn reports_value_path_first_effect_and_tick_delta() {
+        let a = success(
+            point(1, 2),
+            &[("emit", "a"), ("emit", "b"), ("emit", "c")],
+            3,
+        );
+        let b = success(
+            point(1, 5),
+            &[("emit", "a"), ("emit", "x"), ("seal", "c")],
+            5,
+        );
+
+        let d = diff(&a, &b);
+        let lines: Vec<String> = d.differences.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "returned.inner.y: 2 vs 5",
+                "effects[1].payload (emit): \"b\" vs \"x\"",
+                "time.tick: 3 vs 5 (delta +2)",
+            ]
+        );
+
+        let c = success(point(1, 2), &[("emit", "a")], 3);
+        let d = diff(&a, &c);
+        assert_eq!(
+            d.differences,
+            vec![TraceDifference::EffectCount { left: 3, right: 1 }]
+        );
+    }
+
+    #[test]
+    fn reports_outcome_and_error_differences() {
+        let a = success(Value::Int(0), &[], 0);
+        let f = DvmTrace::Failure(DvmFailureTrace::from_error(&DvmError::Runtime("x".into())));
+        let g = DvmTrace::Failure(DvmFailureTrace::from_error(&DvmError::Inadmissible(
+            "x".into(),
+        )));
+
+        assert_eq!(
+            diff(&a, &f).differences,
+            vec![TraceDifference::Outcome {
+                left: "success",
+                right: "failure"
+            }]
+        );
+        assert_eq!(
+            diff(&f, &g).to_string(),
+            "error.kind: \"Runtime\" vs \"Inadmissible\"\n"
+        );
+    }
+}
@@ -0,0 +1,340 @@
+//! Streaming JSON Lines traces for long-running programs.
+//!
+//! `JsonlSink` is an `ExecutionObserver` that writes one record per line as the run
+//! progresses: a header, each effect as it is emitted, optionally each step record
+//! as its statement completes, and a terminal outcome record written by `finish`.
+//! Only open statements are held in memory. `read` reassembles the lines into a
+//! `DvmTrace`; a stream cut short still yields the effects and steps written so far.
+//!
+//! Record shapes are documented in `docs/trace-schema.md` (section 1.4).
+
+use super::{schema_version, DvmTrace, TraceSchemaError, TRACE_SCHEMA_VERSION};
+use crate::{
+    dir::DirStmt, DvmFault, EffectEvent, EffectLog, ExecutionObserver, LogicalTick, StepRecord,
+    StmtLocation, Value,
+};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, Write};
+
+/// One line of a JSONL trace.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case", deny_unknown_fields)]
+pub enum JsonlRecord {
+    /// First line of every stream.
+    Header { schema_version: u32 },
+
+    /// An effect, `index` being its position in the effect log.
+    Effect { index: usize, event: EffectEvent },
+
+    /// A step record; `seq` is its position in `steps` (statement start order).
+    /// Records are written when their statement completes.
+    Step { seq: usize, step: StepRecord },
+
+    /// Last line: the trace without its `effects` and `steps`.
+    Outcome { trace: DvmTrace },
+}
+
+/// Streams a run as JSON Lines to `W`. Attach it (shared, as `Rc<RefCell<_>>`) with
+/// `Dvm::attach_observer` and call `finish` with the run's trace.
+///
+/// Each record is written with a single `write_all`; wrap files in a `LineWriter`
+/// so every completed record reaches the file. After the first I/O error, writing
+/// stops and `finish` reports the error.
+#[derive(Debug)]
+pub struct JsonlSink<W: Write> {
+    out: W,
+    steps: bool,
+    effects: usize,
+    seq: usize,
+
+    /// Started statements not yet written: `(seq, record)`, innermost last.
+    open: Vec<(usize, StepRecord)>,
+
+    error: Option<io::Error>,
+}
+
+impl<W: Write> JsonlSink<W> {
+    /// Start a stream; `steps` also streams step records.
+    pub fn new(out: W, steps: bool) -> Self {
+        let mut sink = Self {
+            out,
+            steps,
+            effects: 0,
+            seq: 0,
+            open: Vec::new(),
+            error: None,
+        };
+        sink.write(&JsonlRecord::Header {
+            schema_version: TRACE_SCHEMA_VERSION,
+        });
+        sink
+    }
+
+    /// Write the outcome record and flush.
+    pub fn finish(&mut self, trace: &DvmTrace) -> io::Result<()> {
+        let mut outcome = trace.clone();
+        match &mut outcome {
+            DvmTrace::Success(t) => {
+                t.effects = EffectLog::default();
+                t.steps.clear();
+            }
+            DvmTrace::Failure(t) => {
+                t.effects = None;
+                t.steps.clear();
+            }
+        }
+        self.write(&JsonlRecord::Outcome { trace: outcome });
+        if self.error.is_none() {
+            if let Err(e) = self.out.flush() {
+                self.error = Some(e);
+            }
+        }
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    fn write(&mut self, record: &JsonlRecord) {
+        if self.error.is_some() {
+            return;
+        }
+        let mut line = match serde_json::to_vec(record) {
+            Ok(l) => l,
+            Err(e) => {
+                self.error = Some(e.into());
+                return;
+            }
+        };
+        line.push(b'\n');
+        if let Err(e) = self.out.write_all(&line) {
+            self.error = Some(e);
+        }
+    }
+
+    fn close_step(&mut self) {
+        if let Some((seq, step)) = self.open.pop() {
+            self.write(&JsonlRecord::Step { seq, step });
+        }
+    }
+}
+
+impl<W: Write> ExecutionObserver for JsonlSink<W> {
+    fn on_stmt_start(&mut self, loc: StmtLocation<'_>, _stmt: &DirStmt, tick: LogicalTick) {
+        if !self.steps {
+            return;
+        }
+        let step = StepRecord {
+            forge: loc.forge.to_string(),
+            proc: loc.proc.to_string(),
+            index: loc.index,
+            tick,
+            bindings: IndexMap::new(),
+            effects: Vec::new(),
+        };
+        self.open.push((self.seq, step));
+        self.seq += 1;
+    }
+
+    fn on_stmt_end(&mut self, _loc: StmtLocation<'_>, _tick: LogicalTick) {
+        self.close_step();
+    }
+
+    fn on_binding(&mut self, _loc: StmtLocation<'_>, name: &str, value: &Value) {
+        if let Some((_, step)) = self.open.last_mut() {
+            step.bindings.insert(name.to_string(), value.clone());
+        }
+    }
+
+    fn on_effect(&mut self, _loc: StmtLocation<'_>, event: &EffectEvent) {
+        if let Some((_, step)) = self.open.last_mut() {
+            step.effects.push(event.clone());
+        }
+        let index = self.effects;
+        self.effects += 1;
+        self.write(&JsonlRecord::Effect {
+            index,
+            event: event.clone(),
+        });
+    }
+
+    fn on_fault(&mut self, _loc: Option<StmtLocation<'_>>, _fault: &DvmFault) {
+        // The faulting statement and its callers never complete.
+        while !self.open.is_empty() {
+            self.close_step();
+        }
+    }
+}
+
+/// The contents of a JSONL trace stream.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct JsonlContents {
+    pub effects: EffectLog,
+
+    /// Step records in `seq` order.
+    pub steps: Vec<StepRecord>,
+
+    /// `None` if the stream ended before its outcome record.
+    pub outcome: Option<DvmTrace>,
+}
+
+impl JsonlContents {
+    /// The reassembled trace; an error if the stream has no outcome record.
+    pub fn into_trace(self) -> Result<DvmTrace, TraceSchemaError> {
+        let Some(mut trace) = self.outcome else {
+            return Err(TraceSchemaError::Invalid(format!(
+                "JSONL trace has no outcome record ({} effect(s), {} step(s) recovered)",
+                self.effects.events.len(),
+                self.steps.len()
+            )));
+        };
+        match &mut trace {
+            DvmTrace::Success(t) => {
+                t.effects = self.effects;
+                t.steps = self.steps;
+            }
+            DvmTrace::Failure(t) => {
+                t.effects = (!self.effects.events.is_empty()).then_some(self.effects);
+                t.steps = self.steps;
+            }
+        }
+        Ok(trace)
+    }
+}
+
+/// Read a JSONL trace stream, tolerating a missing outcome record and an
+/// unterminated last line (a record cut off mid-write).
+pub fn read(mut input: impl BufRead) -> Result<JsonlContents, TraceSchemaError> {
+    let mut contents = JsonlContents::default();
+    let mut steps: Vec<(usize, StepRecord)> = Vec::new();
+    let mut header = false;
+    let mut line = String::new();
+
+    for n in 1.. {
+        line.clear();
+        let read = input
+            .read_line(&mut line)
+            .map_err(|e| TraceSchemaError::Invalid(format!("line {n}: {e}")))?;
+        if read == 0 {
+            break;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let invalid = |msg: String| TraceSchemaError::Invalid(format!("line {n}: {msg}"));
+
+        let v: serde_json::Value = match serde_json::from_str(&line) {
+            Ok(v) => v,
+            Err(_) if !line.ends_with('\n') => break,
+            Err(e) => return Err(invalid(e.to_string())),
+        };
+        if !header {
+            if v.get("record").and_then(|r| r.as_str()) != Some("header") {
+                return Err(invalid("expected a header record".into()));
+            }
+            let version = schema_version(&v)?;
+            if version != u64::from(TRACE_SCHEMA_VERSION) {
+                return Err(TraceSchemaError::Unsupported(version));
+            }
+            header = true;
+            continue;
+        }
+        if contents.outcome.is_some() {
+            return Err(invalid("record after the outcome record".into()));
+        }
+
+        match serde_json::from_value(v).map_err(|e| invalid(e.to_string()))? {
+            JsonlRecord::Header { .. } => return Err(invalid("duplicate header record".into())),
+            JsonlRecord::Effect { index, event } => {
+                if index != contents.effects.events.len() {
+                    return Err(invalid(format!(
+                        "effect index {index}, expected {}",
+                        contents.effects.events.len()
+                    )));
+                }
+                contents.effects.events.push(event);
+            }
+            JsonlRecord::Step { seq, step } => steps.push((seq, step)),
+            JsonlRecord::Outcome { trace } => contents.outcome = Some(trace),
+        }
+    }
+
+    if !header {
+        return Err(TraceSchemaError::Invalid("empty JSONL trace".into()));
+    }
+
+    steps.sort_by_key(|(seq, _)| *seq);
+    if let Some(w) = steps.windows(2).find(|w| w[0].0 == w[1].0) {
+        return Err(TraceSchemaError::Invalid(format!(
+            "duplicate step seq {}",
+            w[0].0
+        )));
+    }
+    contents.steps = steps.into_iter().map(|(_, s)| s).collect();
+    Ok(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Dvm, DvmConfig};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    const PROGRAM: &str = include_str!("../../../../tests/fixtures/dir/regime_tour.dir.json");
+
+    fn streamed() -> (Vec<u8>, DvmTrace) {
+        let sink = Rc::new(RefCell::new(JsonlSink::new(Vec::new(), true)));
+        let mut dvm = Dvm::new(DvmConfig {
+            steps: true,
+            ..DvmConfig::default()
+        });
+        dvm.attach_observer(sink.clone());
+        // Fail the final constraint: the stream still ends in an outcome.
+        let program = dvm
+            .load_dir_json(PROGRAM.replace("m Gt n", "m Lt n").as_bytes())
+            .unwrap();
+        let trace = dvm.run_entrypoint_trace(&program, "main");
+        drop(dvm);
+
+        sink.borrow_mut().finish(&trace).unwrap();
+        let out = Rc::try_unwrap(sink).unwrap().into_inner().into_inner();
+        (out, trace)
+    }
+
+    #[test]
+    fn stream_reassembles_into_the_same_trace() {
+        let (out, trace) = streamed();
+        let text = String::from_utf8(out.clone()).unwrap();
+        assert!(text.starts_with("{\"record\":\"header\",\"schema_version\":3}\n"));
+        assert_eq!(
+            text.lines().last().map(|l| l.contains("\"outcome\"")),
+            Some(true)
+        );
+
+        let read_back = read(out.as_slice()).unwrap().into_trace().unwrap();
+        assert_eq!(read_back, trace);
+    }
+
+    #[test]
+    fn truncated_stream_keeps_partial_evidence() {
+        let (out, _) = streamed();
+        let text = String::from_utf8(out).unwrap();
+        let mut cut: String = text.lines().take(15).map(|l| format!("{l}\n")).collect();
+        cut.push_str("{\"record\":\"eff");
+
+        let contents = read(cut.as_bytes()).unwrap();
+        assert!(contents.outcome.is_none());
+        assert_eq!(contents.effects.events.len(), 2);
+        let e = contents.into_trace().unwrap_err().to_string();
+        assert!(
+            e.contains("no outcome record (2 effect(s), 12 step(s)"),
+            "{e}"
+        );
+    }
+}
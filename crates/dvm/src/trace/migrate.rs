@@ -0,0 +1,114 @@
+//! Upgrades of older traces to the current schema version.
+//!
+//! Each step rewrites a JSON trace from version `n` to `n + 1`; `upgrade` chains
+//! the steps from the trace's version and then reads the result strictly.
+//!
+//! Versions cover the whole trace, including the optional `steps` records:
+//! - 1: unversioned traces (no `schema_version` field).
+//! - 2: adds `schema_version`; unknown fields are rejected.
+//! - 3: adds the optional source span of a failure (`error.span`).
+//!
+//! Step records have kept their version 1 shape; a change to them is a new
+//! version with its own step here.
+
+use super::{schema_version, DvmTrace, TraceSchemaError, TRACE_SCHEMA_VERSION};
+use serde_json::{Map, Value as Json};
+
+/// A trace read through the migration layer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Upgraded {
+    pub trace: DvmTrace,
+
+    /// Schema version of the input.
+    pub from: u64,
+}
+
+impl Upgraded {
+    /// Whether the input was older than the current schema.
+    pub fn changed(&self) -> bool {
+        self.from != u64::from(TRACE_SCHEMA_VERSION)
+    }
+}
+
+/// Read a trace of any supported schema version, upgrading it to the current one.
+pub fn upgrade(mut v: Json) -> Result<Upgraded, TraceSchemaError> {
+    let from = schema_version(&v)?;
+    if from == 0 || from > u64::from(TRACE_SCHEMA_VERSION) {
+        return Err(TraceSchemaError::Unsupported(from));
+    }
+
+    let obj = v
+        .as_object_mut()
+        .ok_or_else(|| TraceSchemaError::Invalid("trace must be a JSON object".into()))?;
+    if from < 2 {
+        v1_to_v2(obj);
+    }
+    if from < 3 {
+        v2_to_v3(obj);
+    }
+
+    Ok(Upgraded {
+        trace: DvmTrace::from_json(v)?,
+        from,
+    })
+}
+
+fn v1_to_v2(obj: &mut Map<String, Json>) {
+    obj.insert("schema_version".into(), Json::from(2u32));
+}
+
+fn v2_to_v3(obj: &mut Map<String, Json>) {
+    obj.insert("schema_version".into(), Json::from(3u32));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn legacy_traces_are_upgraded() {
+        let success = json!({
+            "returned": { "Int": 0 },
+            "effects": { "events": [ { "kind": "emit", "payload": "hi" } ] },
+            "time": { "tick": 1 }
+        });
+        let up = upgrade(success).unwrap();
+        assert_eq!(up.from, 1);
+        assert!(up.changed());
+        assert!(matches!(up.trace, DvmTrace::Success(ref t) if t.schema_version == 3));
+
+        let failure = json!({ "error": { "kind": "Runtime", "message": "boom" } });
+        let up = upgrade(failure).unwrap();
+        assert!(matches!(up.trace, DvmTrace::Failure(ref t) if t.error.message == "boom"));
+
+        let with_steps = json!({
+            "returned": "Unit",
+            "effects": { "events": [] },
+            "time": { "tick": 1 },
+            "steps": [ { "forge": "core", "proc": "main", "index": 0, "tick": 0,
+                         "bindings": { "x": { "Int": 1 } } } ]
+        });
+        let up_steps = upgrade(with_steps).unwrap();
+        assert!(matches!(up_steps.trace, DvmTrace::Success(ref t)
+            if t.steps.len() == 1 && t.steps[0].bindings["x"] == crate::Value::Int(1)));
+
+        // Upgrading is idempotent.
+        let again = upgrade(serde_json::to_value(&up.trace).unwrap()).unwrap();
+        assert!(!again.changed());
+        assert_eq!(again.trace, up.trace);
+    }
+
+    #[test]
+    fn upgrade_still_rejects_malformed_and_future_traces() {
+        let legacy_extra = json!({ "error": { "kind": "Runtime", "message": "x" }, "extra": 1 });
+        assert!(matches!(
+            upgrade(legacy_extra),
+            Err(TraceSchemaError::Invalid(_))
+        ));
+        assert_eq!(
+            upgrade(json!({ "schema_version": 4 })),
+            Err(TraceSchemaError::Unsupported(4))
+        );
+    }
+}
@@ -0,0 +1,277 @@
+//! Conformance traces: the observable outcome of a run (`docs/trace-schema.md`).
+//!
+//! Traces carry a `schema_version` and are read strictly: unknown fields, a
+//! missing or unsupported version, or a malformed variant are errors rather than
+//! silently producing the other variant. Older traces are brought up to date by
+//! `migrate::upgrade`.
+
+pub mod diff;
+pub mod jsonl;
+pub mod migrate;
+
+pub use diff::{diff, TraceDiff, TraceDifference};
+
+use crate::{
+    admissibility::Explanation, DirSpan, DvmError, DvmFault, DvmOutcome, EffectLog, PhiWitness,
+    StepRecord, TimeState, Value,
+};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+use thiserror::Error;
+
+/// Version of the trace schema produced by this build.
+pub const TRACE_SCHEMA_VERSION: u32 = 3;
+
+/// Why a trace could not be read.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TraceSchemaError {
+    #[error("trace has no schema_version (legacy v1); run `dustrun trace upgrade`")]
+    Unversioned,
+
+    #[error(
+        "trace schema_version {0} is not supported (this build reads up to {TRACE_SCHEMA_VERSION})"
+    )]
+    Unsupported(u64),
+
+    #[error("trace schema_version {0} is outdated; run `dustrun trace upgrade`")]
+    Outdated(u64),
+
+    #[error("invalid trace: {0}")]
+    Invalid(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct TraceError {
+    pub kind: String,
+    pub message: String,
+
+    /// Source span of the failing statement, when the DIR carries spans.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span: Option<Box<DirSpan>>,
+}
+
+impl From<&DvmError> for TraceError {
+    fn from(e: &DvmError) -> Self {
+        let (kind, message) = match e {
+            DvmError::DirLoad(s) => ("DirLoad", s.clone()),
+            DvmError::DirValidate(s) => ("DirValidate", s.clone()),
+            DvmError::EntrypointNotFound(s) => ("EntrypointNotFound", s.clone()),
+            DvmError::UnsupportedRegime(s) => ("UnsupportedRegime", s.clone()),
+            DvmError::Inadmissible(s) => ("Inadmissible", s.clone()),
+            DvmError::ConstraintFailure(s) => ("ConstraintFailure", s.clone()),
+            DvmError::EffectViolation(s) => ("EffectViolation", s.clone()),
+            DvmError::TimeViolation(s) => ("TimeViolation", s.clone()),
+            DvmError::Runtime(s) => ("Runtime", s.clone()),
+        };
+        Self {
+            kind: kind.to_string(),
+            message,
+            span: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct DvmSuccessTrace {
+    pub schema_version: u32,
+
+    pub returned: Option<Value>,
+    pub effects: EffectLog,
+    pub time: TimeState,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<StepRecord>,
+}
+
+impl From<DvmOutcome> for DvmSuccessTrace {
+    fn from(o: DvmOutcome) -> Self {
+        Self {
+            schema_version: TRACE_SCHEMA_VERSION,
+            returned: o.returned,
+            effects: o.effects,
+            time: o.time,
+            steps: o.steps,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct DvmFailureTrace {
+    pub schema_version: u32,
+
+    pub error: TraceError,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effects: Option<EffectLog>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<TimeState>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub witness: Option<Box<PhiWitness>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<Box<Explanation>>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<StepRecord>,
+}
+
+impl DvmFailureTrace {
+    /// A failure trace without partial context (e.g. a DIR load error).
+    pub fn from_error(e: &DvmError) -> Self {
+        Self {
+            schema_version: TRACE_SCHEMA_VERSION,
+            error: TraceError::from(e),
+            effects: None,
+            time: None,
+            witness: None,
+            explanation: None,
+            steps: Vec::new(),
+        }
+    }
+}
+
+impl From<DvmFault> for DvmFailureTrace {
+    fn from(fault: DvmFault) -> Self {
+        let effects = if fault.effects.events.is_empty() {
+            None
+        } else {
+            Some(fault.effects)
+        };
+
+        let time = if fault.time.tick.0 == 0 {
+            None
+        } else {
+            Some(fault.time)
+        };
+
+        let mut error = TraceError::from(&fault.error);
+        error.span = fault.span;
+
+        Self {
+            error,
+            effects,
+            time,
+            witness: fault.witness,
+            explanation: fault.explanation,
+            steps: fault.steps,
+            ..Self::from_error(&fault.error)
+        }
+    }
+}
+
+/// A run's trace: failure traces are those with an `error` field.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(untagged)]
+pub enum DvmTrace {
+    Failure(DvmFailureTrace),
+    Success(DvmSuccessTrace),
+}
+
+impl DvmTrace {
+    /// Read a trace of the current schema version from JSON.
+    pub fn from_json(v: serde_json::Value) -> Result<Self, TraceSchemaError> {
+        match schema_version(&v)? {
+            n if n == u64::from(TRACE_SCHEMA_VERSION) => {}
+            1 => return Err(TraceSchemaError::Unversioned),
+            n if n < u64::from(TRACE_SCHEMA_VERSION) => return Err(TraceSchemaError::Outdated(n)),
+            n => return Err(TraceSchemaError::Unsupported(n)),
+        }
+
+        if v.get("error").is_some() {
+            serde_json::from_value(v)
+                .map(DvmTrace::Failure)
+                .map_err(|e| TraceSchemaError::Invalid(format!("failure trace: {e}")))
+        } else {
+            serde_json::from_value(v)
+                .map(DvmTrace::Success)
+                .map_err(|e| TraceSchemaError::Invalid(format!("success trace: {e}")))
+        }
+    }
+}
+
+impl DvmTrace {
+    /// Drop source spans, which follow the DPL source layout rather than
+    /// semantics (golden comparisons ignore them unless asked not to).
+    pub fn strip_spans(&mut self) {
+        if let DvmTrace::Failure(f) = self {
+            f.error.span = None;
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for DvmTrace {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let v = serde_json::Value::deserialize(d)?;
+        DvmTrace::from_json(v).map_err(D::Error::custom)
+    }
+}
+
+/// The `schema_version` of a trace object; traces without one are version 1.
+pub(crate) fn schema_version(v: &serde_json::Value) -> Result<u64, TraceSchemaError> {
+    let obj = v
+        .as_object()
+        .ok_or_else(|| TraceSchemaError::Invalid("trace must be a JSON object".into()))?;
+    match obj.get("schema_version") {
+        None => Ok(1),
+        Some(n) => n.as_u64().ok_or_else(|| {
+            TraceSchemaError::Invalid(format!("schema_version must be an integer, got {n}"))
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn traces_round_trip_with_schema_version() {
+        let trace = DvmTrace::Failure(DvmFailureTrace::from_error(&DvmError::Runtime(
+            "boom".into(),
+        )));
+        let s = serde_json::to_string(&trace).unwrap();
+        assert!(s.starts_with(r#"{"schema_version":3,"#));
+        assert_eq!(serde_json::from_str::<DvmTrace>(&s).unwrap(), trace);
+    }
+
+    #[test]
+    fn malformed_traces_are_rejected() {
+        // A failure trace with a bad error object is not silently read as success.
+        let bad_failure = json!({
+            "schema_version": 3,
+            "error": { "kind": "Runtime" },
+            "effects": { "events": [] },
+            "time": { "tick": 0 },
+            "returned": null
+        });
+        let e = DvmTrace::from_json(bad_failure).unwrap_err().to_string();
+        assert!(e.starts_with("invalid trace: failure trace:"), "{e}");
+
+        let unknown_field = json!({
+            "schema_version": 3,
+            "returned": null,
+            "effects": { "events": [] },
+            "time": { "tick": 0 },
+            "extra": 1
+        });
+        let e = DvmTrace::from_json(unknown_field).unwrap_err().to_string();
+        assert!(e.contains("unknown field `extra`"), "{e}");
+
+        let legacy =
+            json!({ "returned": null, "effects": { "events": [] }, "time": { "tick": 0 } });
+        assert_eq!(
+            DvmTrace::from_json(legacy),
+            Err(TraceSchemaError::Unversioned)
+        );
+
+        let future = json!({ "schema_version": 99 });
+        assert_eq!(
+            DvmTrace::from_json(future),
+            Err(TraceSchemaError::Unsupported(99))
+        );
+    }
+}